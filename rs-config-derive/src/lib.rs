@@ -5,8 +5,6 @@ extern crate quote;
 
 use proc_macro::TokenStream;
 
-use std::collections::HashSet;
-
 
 fn get_meta_attrs(attrs: &Vec<syn::Attribute>) -> Option<&Vec<syn::NestedMetaItem>> {
     for attr in attrs {
@@ -23,6 +21,85 @@ fn get_meta_attrs(attrs: &Vec<syn::Attribute>) -> Option<&Vec<syn::NestedMetaIte
     return None;
 }
 
+/// Get the lines of the doc comment (`///` or `#[doc = "..."]`) of an item
+fn get_docs(attrs: &Vec<syn::Attribute>) -> Vec<String> {
+    let mut ret = Vec::new();
+
+    for attr in attrs {
+        if let syn::MetaItem::NameValue(ref id, syn::Lit::Str(ref val, _)) = attr.value {
+            if id != "doc" {
+                continue;
+            }
+
+            let line = if attr.is_sugared_doc { val.trim_start_matches("///").trim_start_matches("//!") } else { val.as_str() };
+            let line = line.strip_prefix(' ').unwrap_or(line).trim_end();
+
+            ret.push(line.to_string());
+        }
+    }
+
+    /* Empty lines at the end only take space in the output */
+    while ret.last().map(|x| x.is_empty()) == Some(true) {
+        ret.pop();
+    }
+
+    ret
+}
+
+/// Render doc lines for the format description. Each item gets a (hanging) indented block
+fn format_docs(prefix: &str, docs: &[String], out: &mut String) {
+    for (i, line) in docs.iter().enumerate() {
+        if line.is_empty() {
+            out.push('\n');
+            continue;
+        }
+
+        out.push_str("\n    ");
+        if i == 0 {
+            out.push_str(prefix);
+        } else {
+            out.push_str(&" ".repeat(prefix.len()));
+        }
+        out.push_str(line);
+    }
+}
+
+/// Collect the doc comments of the type, its fields and variants for the format description
+fn collect_docs(ast: &syn::MacroInput) -> String {
+    fn fields_docs(fields: &[syn::Field], prefix: &str, out: &mut String) {
+        for (i, field) in fields.iter().enumerate() {
+            let name = match field.ident {
+                Some(_) => field_name(field),
+                None => i.to_string(),
+            };
+            let mut docs = get_docs(&field.attrs);
+            let rules: Vec<String> = get_validations(field, &name).into_iter().map(|x| x.1).collect();
+            if !rules.is_empty() {
+                docs.push(format!("Rules: {}", rules.join(", ")));
+            }
+            format_docs(&format!("{}{}: ", prefix, name), &docs, out);
+        }
+    }
+
+    let mut ret = String::new();
+    format_docs("", &get_docs(&ast.attrs), &mut ret);
+
+    match ast.body {
+        syn::Body::Enum(ref vars) => {
+            for var in vars {
+                let vname = config_name(&var.attrs, &var.ident);
+                format_docs(&format!("{}: ", vname), &get_docs(&var.attrs), &mut ret);
+                fields_docs(var.data.fields(), &format!("{}.", vname), &mut ret);
+            }
+        },
+        syn::Body::Struct(ref data) => {
+            fields_docs(data.fields(), "", &mut ret);
+        },
+    }
+
+    ret
+}
+
 fn get_attrs(field: &syn::Field) -> Option<&Vec<syn::NestedMetaItem>> {
     return get_meta_attrs(&field.attrs);
 }
@@ -47,6 +124,321 @@ fn find_attr_lit<'a>(name: &str, attrs: &'a Vec<syn::NestedMetaItem>) -> Option<
     return None;
 }
 
+/// Check whether a flag (e.g. `#[ConfigAttrs(must_exist)]`) is set
+fn has_attr_word(name: &str, attrs: &Vec<syn::NestedMetaItem>) -> bool {
+    for attr in attrs {
+        if let &syn::NestedMetaItem::MetaItem(syn::MetaItem::Word(ref id)) = attr {
+            if id == name {
+                return true;
+            }
+        }
+    }
+
+    false
+}
+
+/// The names of a field or variant in the config: `rename` (or the identifier) first, then every
+/// `alias`
+fn config_names(attrs: &Vec<syn::Attribute>, ident: &syn::Ident) -> Vec<String> {
+    let attrs = get_meta_attrs(attrs);
+    let name = match attrs.and_then(|x| find_attr_lit("rename", x)) {
+        Some(syn::Lit::Str(val, _)) => val.clone(),
+        Some(_) => panic!("rename must be a string!"),
+        None => ident.to_string(),
+    };
+
+    let mut ret = vec![name];
+    for attr in attrs.into_iter().flatten() {
+        if let &syn::NestedMetaItem::MetaItem(syn::MetaItem::NameValue(ref id, ref lit)) = attr {
+            match *lit {
+                syn::Lit::Str(ref val, _) if id == "alias" => ret.push(val.clone()),
+                _ if id == "alias" => panic!("alias must be a string!"),
+                _ => {},
+            }
+        }
+    }
+
+    ret
+}
+
+/// The name of a field or variant in the config
+fn config_name(attrs: &Vec<syn::Attribute>, ident: &syn::Ident) -> String {
+    config_names(attrs, ident).remove(0)
+}
+
+/// The name of a named field in the config
+fn field_name(field: &syn::Field) -> String {
+    config_name(&field.attrs, field.ident.as_ref().unwrap())
+}
+
+/// Split an identifier into its words, at `_` and in front of capitals (`maxConnections`)
+fn split_words(ident: &str) -> Vec<String> {
+    let mut ret: Vec<String> = Vec::new();
+    let mut prev: Option<char> = None;
+
+    for c in ident.chars() {
+        if c == '_' {
+            prev = None;
+            continue;
+        }
+
+        let split = match prev {
+            None => true,
+            Some(p) => c.is_uppercase() && (p.is_lowercase() || p.is_ascii_digit()),
+        };
+        if split {
+            ret.push(String::new());
+        }
+
+        ret.last_mut().unwrap().push(c);
+        prev = Some(c);
+    }
+
+    ret
+}
+
+/// Convert an identifier into the case convention of `rename_all`
+fn rename_case(ident: &str, case: &str) -> String {
+    let words: Vec<String> = split_words(ident).iter().map(|x| x.to_lowercase()).collect();
+    let capitalize = |x: &String| {
+        let mut chars = x.chars();
+        chars.next().map(|c| c.to_uppercase().chain(chars).collect::<String>()).unwrap_or_default()
+    };
+
+    match case {
+        "lowercase" => words.concat(),
+        "UPPERCASE" => words.concat().to_uppercase(),
+        "PascalCase" => words.iter().map(capitalize).collect(),
+        "camelCase" => {
+            let rest: String = words.iter().skip(1).map(capitalize).collect();
+            format!("{}{}", words.first().cloned().unwrap_or_default(), rest)
+        },
+        "snake_case" => words.join("_"),
+        "SCREAMING_SNAKE_CASE" => words.join("_").to_uppercase(),
+        "kebab-case" => words.join("-"),
+        "SCREAMING-KEBAB-CASE" => words.join("-").to_uppercase(),
+        _ => panic!("rename_all must be one of: lowercase, UPPERCASE, PascalCase, camelCase, snake_case, SCREAMING_SNAKE_CASE, kebab-case, SCREAMING-KEBAB-CASE"),
+    }
+}
+
+/// Get the `rename_all` case convention from attributes
+fn get_rename_all(attrs: &Vec<syn::Attribute>) -> Option<String> {
+    match get_meta_attrs(attrs).and_then(|x| find_attr_lit("rename_all", x)) {
+        Some(syn::Lit::Str(val, _)) => Some(val.clone()),
+        Some(_) => panic!("rename_all must be a string!"),
+        None => None,
+    }
+}
+
+/// Rename a field or variant to its identifier in the `case` convention, unless it has an explicit
+/// `rename`
+fn apply_case(attrs: &mut Vec<syn::Attribute>, ident: &syn::Ident, case: &str) {
+    let rename = syn::NestedMetaItem::MetaItem(syn::MetaItem::NameValue(
+        syn::Ident::new("rename"), syn::Lit::Str(rename_case(ident.as_ref(), case), syn::StrStyle::Cooked)));
+
+    for attr in attrs.iter_mut() {
+        if let syn::MetaItem::List(ref id, ref mut items) = attr.value {
+            if id == "ConfigAttrs" {
+                if find_attr_lit("rename", items).is_none() {
+                    items.push(rename);
+                }
+                return;
+            }
+        }
+    }
+
+    attrs.push(syn::Attribute {
+        style: syn::AttrStyle::Outer,
+        value: syn::MetaItem::List(syn::Ident::new("ConfigAttrs"), vec![rename]),
+        is_sugared_doc: false,
+    });
+}
+
+/// Apply `rename_all` as `rename` on the fields of a struct (or struct variant), and the variants
+/// of an enum. Everything else only looks at `rename`
+fn apply_rename_all(ast: &mut syn::MacroInput) {
+    let case = get_rename_all(&ast.attrs);
+
+    match ast.body {
+        syn::Body::Enum(ref mut vars) => {
+            for var in vars.iter_mut() {
+                if let Some(ref case) = case {
+                    apply_case(&mut var.attrs, &var.ident, case);
+                }
+
+                if let (Some(case), &mut syn::VariantData::Struct(ref mut fields)) = (get_rename_all(&var.attrs), &mut var.data) {
+                    for field in fields.iter_mut() {
+                        apply_case(&mut field.attrs, field.ident.as_ref().unwrap(), &case);
+                    }
+                }
+            }
+        },
+        syn::Body::Struct(syn::VariantData::Struct(ref mut fields)) => {
+            if let Some(ref case) = case {
+                for field in fields.iter_mut() {
+                    apply_case(&mut field.attrs, field.ident.as_ref().unwrap(), case);
+                }
+            }
+        },
+        syn::Body::Struct(_) => {},
+    }
+}
+
+/// Get the PathChecks for a field, if any path check attribute is set
+fn get_path_checks(field: &syn::Field) -> Option<quote::Tokens> {
+    let attrs = get_attrs(field)?;
+    let flags: Vec<bool> = ["expand_tilde", "must_exist", "is_dir", "absolute"].iter().map(|x| has_attr_word(x, attrs)).collect();
+    if !flags.iter().any(|x| *x) {
+        return None;
+    }
+
+    let (expand_tilde, must_exist, is_dir, absolute) = (flags[0], flags[1], flags[2], flags[3]);
+    Some(quote!{
+        rs_config::PathChecks { expand_tilde: #expand_tilde, must_exist: #must_exist, is_dir: #is_dir, absolute: #absolute }
+    })
+}
+
+/// Get a list attribute, e.g. `one_of("a", "b")`
+fn find_attr_list<'a>(name: &str, attrs: &'a Vec<syn::NestedMetaItem>) -> Option<&'a Vec<syn::NestedMetaItem>> {
+    for attr in attrs {
+        if let &syn::NestedMetaItem::MetaItem(syn::MetaItem::List(ref id, ref items)) = attr {
+            if id == name {
+                return Some(items);
+            }
+        }
+    }
+
+    None
+}
+
+/// Render a literal from an attribute as Rust source
+fn lit_source(lit: &syn::Lit) -> String {
+    match *lit {
+        syn::Lit::Str(ref x, _) => format!("{:?}", x),
+        syn::Lit::Char(x) => format!("{:?}", x),
+        syn::Lit::Int(x, _) => x.to_string(),
+        syn::Lit::Float(ref x, _) => x.to_string(),
+        syn::Lit::Bool(x) => x.to_string(),
+        _ => panic!("Only strings, chars, numbers and bools are supported as literals in ConfigAttrs"),
+    }
+}
+
+/// Get the validation rules of a field (see rs_config::validate).
+///
+/// Every rule is the code of the check, which uses the parsed value `x`, and a description for
+/// the format.
+fn get_validations(field: &syn::Field, name: &str) -> Vec<(String, String)> {
+    let attrs = match get_attrs(field) {
+        Some(x) => x,
+        None => { return Vec::new(); },
+    };
+    let mut ret = Vec::new();
+
+    let str_attr = |attr: &str| match find_attr_lit(attr, attrs) {
+        Some(syn::Lit::Str(val, _)) => Some(val.clone()),
+        Some(_) => panic!("{} must be a string!", attr),
+        None => None,
+    };
+    let len_attr = |attr: &str| match find_attr_lit(attr, attrs) {
+        Some(&syn::Lit::Int(val, _)) => Some(val as usize),
+        Some(_) => panic!("{} must be a number!", attr),
+        None => None,
+    };
+
+    if let Some(range) = str_attr("range") {
+        ret.push((format!("rs_config::validate::check_range(&x, {}, {:?}, {:?}, &rs_config_mark, fun)", range, range, name),
+                  format!("in {}", range)));
+    }
+
+    let non_empty = has_attr_word("non_empty", attrs);
+    let (min, max) = (len_attr("min_len"), len_attr("max_len"));
+    if non_empty || min.is_some() || max.is_some() {
+        let min = if non_empty { Some(min.unwrap_or(1).max(1)) } else { min };
+        let mut desc = Vec::new();
+        if non_empty {
+            desc.push("not empty".to_string());
+        }
+        if let Some(x) = min.filter(|x| !non_empty || *x > 1) {
+            desc.push(format!("at least {} long", x));
+        }
+        if let Some(x) = max {
+            desc.push(format!("at most {} long", x));
+        }
+
+        ret.push((format!("rs_config::validate::check_len(&x, {:?}, {:?}, {:?}, &rs_config_mark, fun)", min, max, name),
+                  desc.join(", ")));
+    }
+
+    if let Some(items) = find_attr_list("one_of", attrs) {
+        let values: Vec<String> = items.iter().map(|x| match x {
+            syn::NestedMetaItem::Literal(lit) => lit_source(lit),
+            _ => panic!("one_of must be a list of literals!"),
+        }).collect();
+        ret.push((format!("rs_config::validate::check_one_of(&x, &[{}], {:?}, &rs_config_mark, fun)", values.join(", "), name),
+                  format!("one of {}", values.join(", "))));
+    }
+
+    if has_attr_word("unique", attrs) {
+        ret.push((format!("rs_config::validate::check_unique(&x, {:?}, &rs_config_mark, fun)", name),
+                  "without duplicates".to_string()));
+    }
+
+    if let Some(fun) = str_attr("validate") {
+        ret.push((format!("rs_config::validate::check_custom({}(&x), {:?}, &rs_config_mark, fun)", fun, name),
+                  format!("checked by {}", fun)));
+    }
+
+    ret
+}
+
+/// Append the parsing of a field into `rs_config_val`, including the checks of its attributes.
+///
+/// Checks on the value need its location, so they can be reported there
+fn append_checked_parse(field: &syn::Field, name: &str, tok: &mut quote::Tokens) {
+    let ty = &field.ty;
+    let path_checks = get_path_checks(field);
+    let validations = get_validations(field, name);
+
+    if path_checks.is_none() && validations.is_empty() {
+        tok.append(quote!{let rs_config_val = <#ty as ConfigAble>::parse_from(provider, fun);});
+        return;
+    }
+
+    tok.append(quote!{let rs_config_mark = provider.mark(0, 1);});
+    if get_attrs(field).map(|x| has_attr_word("absolute", x)).unwrap_or(false) {
+        /* Relative paths have to stay relative, to be reported */
+        tok.append(quote!{
+            let rs_config_resolve = provider.path_resolution();
+            provider.set_path_resolution(false);
+            let rs_config_parsed = <#ty as ConfigAble>::parse_from(provider, fun);
+            provider.set_path_resolution(rs_config_resolve);
+        });
+    } else {
+        tok.append(quote!{let rs_config_parsed = <#ty as ConfigAble>::parse_from(provider, fun);});
+    }
+    tok.append("let rs_config_val = match rs_config_parsed {"); /* Open result matching */
+    tok.append("Ok(mut x) => {"); /* Open Ok match */
+    append_field_checks(path_checks, validations, tok);
+    tok.append("},"); /* Close Ok match */
+    tok.append("Err(x) => Err(x),");
+    tok.append("};"); /* Close result matching */
+}
+
+/// Run the checks of a field on the value `x`, reporting at `rs_config_mark`. This evaluates to
+/// `x`, or the error of the field
+fn append_field_checks(path_checks: Option<quote::Tokens>, validations: Vec<(String, String)>, tok: &mut quote::Tokens) {
+    tok.append("let mut rs_config_ok = true;");
+    if let Some(checks) = path_checks {
+        tok.append(quote!{rs_config_ok &= rs_config::check_paths(&mut x, &#checks, &rs_config_mark, fun).is_ok();});
+    }
+    for (check, _) in validations {
+        tok.append(format!("rs_config_ok &= {}.is_ok();", check));
+    }
+    tok.append(quote!{
+        if rs_config_ok { Ok(x) } else { Err(rs_config::ParseError::Recoverable) }
+    });
+}
+
 fn impl_get_name(ast: &syn::MacroInput, tok: &mut quote::Tokens) {
     let name = &ast.ident;
     tok.append("fn get_name() -> &'static str { ");
@@ -57,7 +449,7 @@ fn impl_get_name(ast: &syn::MacroInput, tok: &mut quote::Tokens) {
     tok.append("}"); /* Close get_name() */
 }
 
-fn append_fields<'a, I>(fields: I, tok: &mut quote::Tokens, others: &mut HashSet<&'a syn::Ty>)
+fn append_fields<'a, I>(fields: I, tok: &mut quote::Tokens, others: &mut Vec<&'a syn::Ty>)
     where I: std::iter::Iterator<Item=&'a syn::Field> {
     let mut first = true;
 
@@ -69,16 +461,28 @@ fn append_fields<'a, I>(fields: I, tok: &mut quote::Tokens, others: &mut HashSet
         }
         let ty = &field.ty;
 
-        if let Some(ref id) = field.ident {
-            tok.append(quote!{fun(stringify!(#id)); fun(": ");});
+        if field.ident.is_some() {
+            let name = field_name(field);
+            tok.append(quote!{fun(#name); fun(": ");});
         }
 
         tok.append(quote!{fun(stringify!(#ty));});
-        others.insert(ty);
+        if !others.contains(&ty) {
+            others.push(ty);
+        }
     }
 }
 
-fn impl_parse_named<'a, I>(fields: I, tok: &mut quote::Tokens)
+/// The condition for the key token `rs_config_key` being one of `names`
+fn key_condition(names: &[String]) -> String {
+    names.iter().map(|x| format!("rs_config_key == {:?}", x)).collect::<Vec<_>>().join(" || ")
+}
+
+/// Parse the fields of a struct (or struct variant) in `{}`.
+///
+/// With `track`, the location of every field is pushed into `rs_config_fields`, for the checks on
+/// the entire value
+fn impl_parse_named<'a, I>(fields: I, track: bool, tok: &mut quote::Tokens)
     where I: std::iter::Iterator<Item=&'a syn::Field> + std::clone::Clone {
 
     tok.append(quote!{provider.consume_char('{', fun)?;});
@@ -89,7 +493,9 @@ fn impl_parse_named<'a, I>(fields: I, tok: &mut quote::Tokens)
         };
         let ty = &field.ty;
 
-        tok.append(quote!{let mut #name:rs_config::ParseTmp<#ty> = rs_config::ParseTmp::new(stringify!(#name).into());});
+        let cname = field_name(field);
+
+        tok.append(quote!{let mut #name:rs_config::ParseTmp<#ty> = rs_config::ParseTmp::new(#cname.into());});
         match get_attrs(field).and_then(|x| find_attr_lit("default", x)) {
             Some(x) => {
                 match x {
@@ -117,24 +523,39 @@ fn impl_parse_named<'a, I>(fields: I, tok: &mut quote::Tokens)
         let nxt = match provider.get_next() {
             Some(x) => x,
             None => {
-                fun("Reached end of file while trying to parse named values".to_string());
+                fun(provider.error(0, 0, "Reached end of file while trying to parse named values"));
                 return Err(rs_config::ParseError::Final);
             }
         };
+        /* Keys are matched as a whole, so a field can't match the start of a longer key */
+        let rs_config_key = rs_config::key_token(&nxt);
     });
 
+    let mut names = Vec::new();
     for ref field in fields {
         let name = match field.ident {
             Some(ref x) => x,
             None => panic!("Encountered unnamed field while trying to derive named field parsing")
         };
-        let ty = &field.ty;
+        let cname = field_name(field);
+        names.push(cname.clone());
 
+        let mut parse = quote::Tokens::new();
+        append_checked_parse(field, &cname, &mut parse);
+
+        let mut location = quote::Tokens::new();
+        if track {
+            location.append(quote!{rs_config_fields.push(#cname, provider.mark(0, rs_config_key.len()));});
+        }
+
+        tok.append(format!("if {}", key_condition(&config_names(&field.attrs, name))));
         tok.append(quote!{
-            if nxt.starts_with(stringify!(#name)) {
-                provider.consume(stringify!(#name).len(), fun)?;
+            {
+                #location
+                provider.consume(rs_config_key.len(), fun)?;
                 provider.consume_char(':', fun)?;
-                #name.push_found(<#ty as ConfigAble>::parse_from(provider, fun), provider, fun)?;
+                #parse
+                #name.push_found(rs_config_val, provider, fun)?;
 
                 if provider.peek_char() == Some(',') {
                     provider.consume(1, fun)?;
@@ -144,9 +565,9 @@ fn impl_parse_named<'a, I>(fields: I, tok: &mut quote::Tokens)
         });
     }
 
+    let expected = format!("one of: {}", names.join(", "));
     tok.append(quote!{
-        provider.print_error(0, fun);
-        fun(format!("Found invalid field name !{}!", nxt));
+        fun(provider.error(0, rs_config_key.len(), "Found invalid field name").expected(#expected).found(rs_config_key));
         return Err(rs_config::ParseError::Final);
     });
 
@@ -161,18 +582,17 @@ fn impl_parse_ordered<'a, I>(fields: I, tok: &mut quote::Tokens)
         if index > 0 {
             tok.append(quote!{provider.consume_char(',', fun)?;});
         }
-        let ty = &field.ty;
-
-        tok.append(format!("let var{} =", index));
-
-        tok.append(quote!{<#ty as ConfigAble>::parse_from(provider, fun)?; });
+        tok.append(format!("let var{} = {{", index)); /* Open field block */
+        append_checked_parse(field, &index.to_string(), tok);
+        tok.append("rs_config_val? };"); /* Close field block */
     }
     tok.append(quote!{provider.consume_char(')', fun)?;});
 }
 
 fn impl_get_format(ast: &syn::MacroInput, tok: &mut quote::Tokens) {
     let name = &ast.ident;
-    let mut others = HashSet::new();
+    /* The used types in field order, so the format is the same on every build */
+    let mut others = Vec::new();
 
     tok.append(quote!{
         #[allow(unused_variables)] /* We need this, since we may not use the set */
@@ -199,7 +619,8 @@ fn impl_get_format(ast: &syn::MacroInput, tok: &mut quote::Tokens) {
                     tok.append(quote!{fun(" | ");});
                 }
 
-                tok.append(quote!{fun(stringify!(#vname));});
+                let cname = config_name(&var.attrs, vname);
+                tok.append(quote!{fun(#cname);});
 
                 match var.data {
                     syn::VariantData::Unit => {},
@@ -222,7 +643,7 @@ fn impl_get_format(ast: &syn::MacroInput, tok: &mut quote::Tokens) {
 
             match data {
                 &syn::VariantData::Unit => {
-                    panic!("Error while creating config scheme for {}. Can't do anything nice with Unit structs", name);
+                    tok.append(quote!{fun(stringify!(#name)); fun(" | {}");});
                 },
                 &syn::VariantData::Tuple(ref fields) => {
                     tok.append(quote!{fun("(");});
@@ -238,6 +659,11 @@ fn impl_get_format(ast: &syn::MacroInput, tok: &mut quote::Tokens) {
         }
     }
 
+    let docs = collect_docs(ast);
+    if !docs.is_empty() {
+        tok.append(quote!{fun(#docs);});
+    }
+
     /* Append other types format, so the entire used type tree will be displayed */
     for other in others {
         tok.append(quote!{
@@ -284,6 +710,7 @@ fn impl_get_default(ast: &syn::MacroInput, tok: &mut quote::Tokens) {
         syn::Body::Struct(ref data) => {
             match data {
                 &syn::VariantData::Unit => {
+                    tok.append(quote!{return Ok(#name);});
                 },
                 &syn::VariantData::Tuple(ref fields) => {
                     tok.append("return Ok(");
@@ -473,6 +900,8 @@ fn impl_merge(ast: &syn::MacroInput, tok: &mut quote::Tokens) {
 
             match data {
                 &syn::VariantData::Unit => {
+                    /* There is only one value, so merging is the identity */
+                    tok.append(quote!{return Ok(());});
                 },
                 &syn::VariantData::Tuple(ref fields) => {
                     for (i, _) in fields.iter().enumerate() {
@@ -502,64 +931,481 @@ fn impl_merge(ast: &syn::MacroInput, tok: &mut quote::Tokens) {
     tok.append("}"); /* close merge function */
 }
 
+/// Append the code writing a list of fields.
+/// `bindings` are the expressions that refer to the fields values
+fn append_write_fields(fields: &[syn::Field], bindings: &[String], tok: &mut quote::Tokens) {
+    for (i, field) in fields.iter().enumerate() {
+        if i > 0 {
+            tok.append(quote!{fun(", ");});
+        }
+        let ty = &field.ty;
+
+        if field.ident.is_some() {
+            let name = field_name(field);
+            tok.append(quote!{fun(#name); fun(": ");});
+        }
+
+        tok.append(quote!{<#ty as ConfigAble>::write_to});
+        tok.append(format!("({}, fun);", bindings[i]));
+    }
+}
+
+fn impl_write_to(ast: &syn::MacroInput, tok: &mut quote::Tokens) {
+    let name = &ast.ident;
+
+    tok.append(quote!{
+        #[allow(unused_variables)]
+        fn write_to<F>(&self, fun: &mut F)
+            where F: FnMut(&str)
+    });
+    tok.append("{"); /* Open write_to function */
+
+    match ast.body {
+        /* Handle Enums */
+        syn::Body::Enum(ref vars) => {
+            tok.append("match *self {"); /* Open self matching */
+            for var in vars {
+                let vname = &var.ident;
+                let cname = config_name(&var.attrs, vname);
+                tok.append(quote!{#name::#vname});
+
+                match var.data {
+                    syn::VariantData::Unit => {
+                        tok.append("=> {");
+                        tok.append(quote!{fun(#cname);});
+                    },
+                    syn::VariantData::Tuple(ref fields) => {
+                        let bindings: Vec<String> = (0..fields.len()).map(|i| format!("var{}", i)).collect();
+                        tok.append(format!("({}) => {{", bindings.iter().map(|x| format!("ref {}", x)).collect::<Vec<_>>().join(",")));
+                        tok.append(quote!{fun(#cname); fun("(");});
+                        append_write_fields(fields, &bindings, tok);
+                        tok.append(quote!{fun(")");});
+                    },
+                    syn::VariantData::Struct(ref fields) => {
+                        let names: Vec<String> = fields.iter().map(|x| x.ident.as_ref().unwrap().to_string()).collect();
+                        let bindings: Vec<String> = names.iter().map(|x| format!("var_{}", x)).collect();
+                        tok.append(format!("{{ {} }} => {{", names.iter().map(|x| format!("{}: ref var_{}", x, x)).collect::<Vec<_>>().join(",")));
+                        tok.append(quote!{fun(#cname); fun("{");});
+                        append_write_fields(fields, &bindings, tok);
+                        tok.append(quote!{fun("}");});
+                    },
+                }
+                tok.append("},"); /* Close variant match */
+            }
+            tok.append("}"); /* Close self matching */
+        },
+        /* Handle structs */
+        syn::Body::Struct(ref data) => {
+            match *data {
+                syn::VariantData::Unit => {
+                    tok.append(quote!{fun(stringify!(#name));});
+                },
+                syn::VariantData::Tuple(ref fields) => {
+                    let bindings: Vec<String> = (0..fields.len()).map(|i| format!("&self.{}", i)).collect();
+                    tok.append(quote!{fun("(");});
+                    append_write_fields(fields, &bindings, tok);
+                    tok.append(quote!{fun(")");});
+                },
+                syn::VariantData::Struct(ref fields) => {
+                    let bindings: Vec<String> = fields.iter().map(|x| format!("&self.{}", x.ident.as_ref().unwrap())).collect();
+                    tok.append(quote!{fun("{");});
+                    append_write_fields(fields, &bindings, tok);
+                    tok.append(quote!{fun("}");});
+                },
+            }
+        }
+    }
+
+    tok.append("}"); /* close write_to function */
+}
+
+fn impl_write_sample(ast: &syn::MacroInput, tok: &mut quote::Tokens) {
+    /* A default for the entire type wins, the default implementation writes that */
+    if get_meta_attrs(&ast.attrs).and_then(|x| find_attr_lit("default", x)).is_some() {
+        return;
+    }
+
+    let fields = match ast.body {
+        syn::Body::Struct(syn::VariantData::Struct(ref fields)) => fields,
+        /* Everything else can't be split up, so the default implementation is used */
+        _ => { return; },
+    };
+
+    tok.append(quote!{
+        fn write_sample<F>(level: usize, fun: &mut F) -> bool
+            where F: FnMut(&str)
+    });
+    tok.append("{"); /* Open write_sample function */
+    tok.append(quote!{fun("{\n");});
+
+    /* The documentation of the type becomes the header of the file */
+    let docs = get_docs(&ast.attrs);
+    if !docs.is_empty() {
+        let comment: String = docs.iter().map(|x| if x.is_empty() { "    #\n".to_string() } else { format!("    # {}\n", x) }).collect::<Vec<_>>().concat() + "\n";
+        tok.append(quote!{
+            if level == 0 {
+                fun(#comment);
+            }
+        });
+    }
+
+    for field in fields {
+        let name = field_name(field);
+        let ty = &field.ty;
+        let placeholder = format!("{}: <{}>", name, quote!{#ty});
+
+        for line in get_docs(&field.attrs) {
+            let comment = if line.is_empty() { "#\n".to_string() } else { format!("# {}\n", line) };
+            tok.append(quote!{
+                rs_config::write_indent(level + 1, fun);
+                fun(#comment);
+            });
+        }
+
+        tok.append("{"); /* Open block for the field */
+        match get_attrs(field).and_then(|x| find_attr_lit("default", x)) {
+            Some(syn::Lit::Str(val, _)) => {
+                tok.append(quote!{let val: #ty = });
+                tok.append(format!("{};", val));
+                tok.append(quote!{
+                    rs_config::write_indent(level + 1, fun);
+                    fun(#name);
+                    fun(": ");
+                    <#ty as ConfigAble>::write_to(&val, fun);
+                    fun(",\n");
+                });
+            },
+            Some(_) => {
+                panic!("default must be a string that will be parsed!");
+            },
+            None => {
+                tok.append(quote!{
+                    let mut val = String::new();
+                    if <#ty as ConfigAble>::write_sample(level + 1, &mut |x| val.push_str(x)) {
+                        rs_config::write_indent(level + 1, fun);
+                        fun(#name);
+                        fun(": ");
+                        fun(&val);
+                        fun(",\n");
+                    } else {
+                        rs_config::write_placeholder(level + 1, #placeholder, &<#ty as ConfigAble>::get_format_str(), fun);
+                    }
+                });
+            },
+        }
+        tok.append("}"); /* Close block for the field */
+    }
+
+    tok.append(quote!{
+        rs_config::write_indent(level, fun);
+        fun("}");
+        return true;
+    });
+    tok.append("}"); /* close write_sample function */
+}
+
+/// The description of an item for the schema, built from its doc comment
+fn schema_description(attrs: &Vec<syn::Attribute>) -> quote::Tokens {
+    let docs = get_docs(attrs);
+    if docs.is_empty() {
+        return quote!{None};
+    }
+
+    let desc = docs.join("\n");
+    quote!{Some(#desc.to_string())}
+}
+
+/// Append a `vec![...]` of SchemaFields for named fields
+fn append_schema_fields(fields: &[syn::Field], tok: &mut quote::Tokens) {
+    tok.append("vec![");
+    for field in fields {
+        let name = field_name(field);
+        let ty = &field.ty;
+        let description = schema_description(&field.attrs);
+
+        /* A field is required, if there is no way to default it */
+        let (required, default) = match get_attrs(field).and_then(|x| find_attr_lit("default", x)) {
+            Some(syn::Lit::Str(val, _)) => {
+                let mut default = quote::Tokens::new();
+                default.append("{"); /* Open block for the default value */
+                default.append(quote!{let val: #ty = });
+                default.append(format!("{};", val));
+                default.append(quote!{Some(<#ty as ConfigAble>::to_config_string(&val))});
+                default.append("}"); /* Close block for the default value */
+                (quote!{false}, default)
+            },
+            Some(_) => {
+                panic!("default must be a string that will be parsed!");
+            },
+            None => {
+                (quote!{<#ty as ConfigAble>::get_default().is_err()},
+                 quote!{<#ty as ConfigAble>::get_default().ok().map(|x| <#ty as ConfigAble>::to_config_string(&x))})
+            },
+        };
+
+        let mut aliases = quote::Tokens::new();
+        aliases.append("vec!["); /* Open aliases */
+        for alias in config_names(&field.attrs, field.ident.as_ref().unwrap()).iter().skip(1) {
+            aliases.append(quote!{#alias.to_string(),});
+        }
+        aliases.append("]"); /* Close aliases */
+
+        tok.append(quote!{
+            rs_config::schema::SchemaField {
+                name: #name.to_string(),
+                aliases: #aliases,
+                description: #description,
+                required: #required,
+                default: #default,
+                schema: <#ty as ConfigAble>::get_schema(defs),
+            },
+        });
+    }
+    tok.append("]");
+}
+
+/// Append a `vec![...]` of the schemas of unnamed fields
+fn append_schema_tuple(fields: &[syn::Field], tok: &mut quote::Tokens) {
+    tok.append("vec![");
+    for field in fields {
+        let ty = &field.ty;
+        tok.append(quote!{<#ty as ConfigAble>::get_schema(defs),});
+    }
+    tok.append("]");
+}
+
+fn impl_get_schema(ast: &syn::MacroInput, tok: &mut quote::Tokens) {
+    let name = &ast.ident;
+    let description = schema_description(&ast.attrs);
+
+    tok.append(quote!{
+        fn get_schema(defs: &mut rs_config::SchemaDefinitions) -> rs_config::SchemaNode
+    });
+    tok.append("{"); /* Open get_schema function */
+    tok.append(quote!{
+        let name = stringify!(#name).to_string();
+        if defs.contains_key(&name) {
+            return rs_config::SchemaNode::Ref(name);
+        }
+        /* Insert a placeholder first, so recursive types find themselves */
+        defs.insert(name.clone(), rs_config::SchemaNode::Any);
+    });
+
+    tok.append("let node = ");
+    match ast.body {
+        /* Handle Enums */
+        syn::Body::Enum(ref vars) => {
+            tok.append(quote!{rs_config::SchemaNode::Enum});
+            tok.append("{");
+            tok.append(quote!{description: #description, variants:});
+            tok.append("vec![");
+            for var in vars {
+                let vname = config_name(&var.attrs, &var.ident);
+                let vdesc = schema_description(&var.attrs);
+                tok.append(quote!{rs_config::schema::SchemaVariant});
+                tok.append("{"); /* Open variant */
+                tok.append(quote!{
+                    name: #vname.to_string(),
+                    description: #vdesc,
+                    fields:
+                });
+                match var.data {
+                    syn::VariantData::Unit => {
+                        tok.append(quote!{rs_config::schema::VariantFields::Unit});
+                    },
+                    syn::VariantData::Tuple(ref fields) => {
+                        tok.append(quote!{rs_config::schema::VariantFields::Tuple});
+                        tok.append("(");
+                        append_schema_tuple(fields, tok);
+                        tok.append(")");
+                    },
+                    syn::VariantData::Struct(ref fields) => {
+                        tok.append(quote!{rs_config::schema::VariantFields::Struct});
+                        tok.append("(");
+                        append_schema_fields(fields, tok);
+                        tok.append(")");
+                    },
+                }
+                tok.append("},"); /* Close variant */
+            }
+            tok.append("]}");
+        },
+        /* Handle structs */
+        syn::Body::Struct(ref data) => {
+            match *data {
+                syn::VariantData::Unit => {
+                    tok.append(quote!{rs_config::SchemaNode::Struct { description: #description, fields: Vec::new() }});
+                },
+                syn::VariantData::Tuple(ref fields) => {
+                    tok.append(quote!{rs_config::SchemaNode::Tuple});
+                    tok.append("(");
+                    append_schema_tuple(fields, tok);
+                    tok.append(")");
+                },
+                syn::VariantData::Struct(ref fields) => {
+                    tok.append(quote!{rs_config::SchemaNode::Struct});
+                    tok.append("{");
+                    tok.append(quote!{description: #description, fields:});
+                    append_schema_fields(fields, tok);
+                    tok.append("}");
+                },
+            }
+        },
+    }
+    tok.append(";");
+
+    tok.append(quote!{
+        defs.insert(name.clone(), node);
+        return rs_config::SchemaNode::Ref(name);
+    });
+    tok.append("}"); /* close get_schema function */
+}
+
+/// Get all list attributes with a name, e.g. every `mutually_exclusive(...)`
+fn find_attr_lists(name: &str, attrs: &Vec<syn::NestedMetaItem>) -> Vec<Vec<String>> {
+    let mut ret = Vec::new();
+    for attr in attrs {
+        if let &syn::NestedMetaItem::MetaItem(syn::MetaItem::List(ref id, ref items)) = attr {
+            if id == name {
+                ret.push(items.iter().map(|x| match x {
+                    &syn::NestedMetaItem::Literal(syn::Lit::Str(ref val, _)) => val.clone(),
+                    _ => panic!("{} must be a list of field names!", name),
+                }).collect());
+            }
+        }
+    }
+
+    ret
+}
+
+/// Get the code of the checks on an entire value (see rs_config::validate). These use the value
+/// `rs_config_ret` and the field locations in `rs_config_fields`.
+/// # Arguments
+/// * `attrs`: The attributes of the type and the variant
+/// * `fields`: The fields of the struct or variant, group checks can only name these
+fn get_value_checks(attrs: &[&Vec<syn::Attribute>], fields: &[syn::Field]) -> String {
+    let names: Vec<(String, String)> = fields.iter().filter(|x| x.ident.is_some())
+        .map(|x| (x.ident.as_ref().unwrap().to_string(), field_name(x))).collect();
+    let mut ret = String::new();
+
+    for attrs in attrs.iter().filter_map(|x| get_meta_attrs(x)) {
+        for (attr, check) in [("mutually_exclusive", "check_mutually_exclusive"), ("required_together", "check_required_together")] {
+            for group in find_attr_lists(attr, attrs) {
+                let group: Vec<String> = group.iter().map(|x| {
+                    match names.iter().find(|n| n.0 == *x) {
+                        Some(n) => format!("{:?}", n.1),
+                        None => panic!("{} names {}, which isn't a named field!", attr, x),
+                    }
+                }).collect();
+                ret.push_str(&format!("rs_config_ok &= rs_config::validate::{}(&rs_config_fields, &[{}], fun).is_ok();", check, group.join(", ")));
+            }
+        }
+
+        match find_attr_lit("validate", attrs) {
+            Some(syn::Lit::Str(val, _)) => {
+                ret.push_str(&format!("rs_config_ok &= rs_config::validate::check_value({}(&rs_config_ret, &rs_config_fields), fun).is_ok();", val));
+            },
+            Some(_) => panic!("validate must be a string that's a function name!"),
+            None => {},
+        }
+    }
+
+    if ret.is_empty() {
+        return ret;
+    }
+
+    format!("let mut rs_config_ok = true; {} if !rs_config_ok {{ return Err(rs_config::ParseError::Recoverable); }}", ret)
+}
+
+/// Whether the type or any variant has checks on the entire value
+fn has_value_checks(ast: &syn::MacroInput) -> bool {
+    match ast.body {
+        syn::Body::Enum(ref vars) => vars.iter().any(|x| !get_value_checks(&[&ast.attrs, &x.attrs], x.data.fields()).is_empty()),
+        syn::Body::Struct(ref data) => !get_value_checks(&[&ast.attrs], data.fields()).is_empty(),
+    }
+}
+
+/// Return the value built by `expr`, after running the checks on it
+fn append_checked_return(expr: quote::Tokens, checks: &str, tok: &mut quote::Tokens) {
+    if checks.is_empty() {
+        tok.append("return Ok(");
+        tok.append(expr);
+        tok.append(");");
+        return;
+    }
+
+    tok.append("let rs_config_ret = ");
+    tok.append(expr);
+    tok.append(";");
+    tok.append(checks);
+    tok.append("return Ok(rs_config_ret);");
+}
+
 fn impl_parse_from(ast: &syn::MacroInput, tok: &mut quote::Tokens) {
     let name = &ast.ident;
-    tok.append(quote!{#[allow(unused_variables, unreachable_code, unused_assignments)]
+    let track = has_value_checks(ast);
+    tok.append(quote!{#[allow(unused_variables, unreachable_code, unused_assignments, unused_mut)]
         fn parse_from<F>(provider: &mut rs_config::ConfigProvider, fun: &mut F) -> Result<Self, rs_config::ParseError>
-           where  F: FnMut(String)
+           where  F: FnMut(rs_config::Diagnostic)
     });
     tok.append("{");
     tok.append(quote!{let nxt = match provider.get_next() {
             Some(x) => x,
             None => {
-                fun("Was at end of file.".to_string());
+                fun(provider.error(0, 0, "Was at end of file."));
                 return Err(rs_config::ParseError::Final);
             }
         };
+        let rs_config_key = rs_config::key_token(&nxt);
     });
+    if track {
+        tok.append(quote!{let mut rs_config_fields = rs_config::validate::ValidationContext::new(provider.mark(0, 1));});
+    }
 
     match ast.body {
         /* Handle Enums */
         syn::Body::Enum(ref vars) => {
             for ref var in vars {
                 let vname = &var.ident;
+                let checks = get_value_checks(&[&ast.attrs, &var.attrs], var.data.fields());
+                let condition = key_condition(&config_names(&var.attrs, vname));
 
                 match var.data {
                     syn::VariantData::Unit => {
-                        tok.append(quote!{
-                            if nxt.starts_with(stringify!(#vname)) {
-                                provider.consume(stringify!(#vname).len(), fun)?;
-                                return Ok(#name::#vname);
-                            }
-                        });
+                        tok.append(format!("if {}", condition));
+                        tok.append("{");
+                        tok.append(quote!{ provider.consume(rs_config_key.len(), fun)?;});
+                        append_checked_return(quote!{#name::#vname}, &checks, tok);
+                        tok.append("}");
                     },
                     syn::VariantData::Tuple(ref fields) => {
-                        tok.append(quote!{ if nxt.starts_with(stringify!(#vname))});
+                        tok.append(format!("if {}", condition));
                         tok.append("{");
-                        tok.append(quote!{ provider.consume(stringify!(#vname).len(), fun)?;});
+                        tok.append(quote!{ provider.consume(rs_config_key.len(), fun)?;});
                         impl_parse_ordered(fields.iter(), tok);
-                        tok.append("return Ok(");
-                        tok.append(quote!{#name::#vname});
-                        tok.append("(");
+
+                        let mut ret_expr = quote::Tokens::new();
+                        ret_expr.append(quote!{#name::#vname});
+                        ret_expr.append("(");
 
                         for (i, _) in fields.iter().enumerate() {
                             if i > 0 {
-                                tok.append(",");
+                                ret_expr.append(",");
                             }
-                            tok.append(format!("var{}", i));
+                            ret_expr.append(format!("var{}", i));
                         }
 
-                        tok.append("));");
+                        ret_expr.append(")");
+                        append_checked_return(ret_expr, &checks, tok);
                         tok.append("}");
                     },
                     syn::VariantData::Struct(ref fields) => {
-                        tok.append(quote!{ if nxt.starts_with(stringify!(#vname))});
+                        tok.append(format!("if {}", condition));
                         tok.append("{");
-                        tok.append(quote!{ provider.consume(stringify!(#vname).len(), fun)?;});
-                        impl_parse_named(fields.iter(), tok);
+                        tok.append(quote!{ provider.consume(rs_config_key.len(), fun)?;});
+                        impl_parse_named(fields.iter(), track, tok);
 
                         let mut ret_expr = quote::Tokens::new();
-                        ret_expr.append("return Ok(");
                         ret_expr.append(quote!{#name::#vname});
                         ret_expr.append("{");
 
@@ -573,45 +1419,61 @@ fn impl_parse_from(ast: &syn::MacroInput, tok: &mut quote::Tokens) {
                                 None => panic!("Encountered unnamed field while trying to derive named field parsing")
                             };
 
-                            tok.append(format!("let {}_r = {}.get_value(fun);", name, name));
+                            tok.append(format!("let {}_r = {}.get_value(provider, fun);", name, name));
 
 
                             ret_expr.append(format!("{}: {}_r?", name, name));
                         }
 
-                        ret_expr.append("});");
                         ret_expr.append("}");
-
-                        tok.append(ret_expr);
+                        append_checked_return(ret_expr, &checks, tok);
+                        tok.append("}");
                     },
                 }
             }
         },
         /* Handle structs */
         syn::Body::Struct(ref data) => {
+            let checks = get_value_checks(&[&ast.attrs], data.fields());
 
             match data {
                 &syn::VariantData::Unit => {
+                    /* Unit structs are written as their name, or as an empty struct */
+                    tok.append(quote!{ if rs_config_key == stringify!(#name)});
+                    tok.append("{");
+                    tok.append(quote!{ provider.consume(rs_config_key.len(), fun)?;});
+                    append_checked_return(quote!{#name}, &checks, tok);
+                    tok.append("}");
+
+                    tok.append(quote!{ if nxt.starts_with('{')});
+                    tok.append("{");
+                    tok.append(quote!{
+                        provider.consume_char('{', fun)?;
+                        provider.consume_char('}', fun)?;
+                    });
+                    append_checked_return(quote!{#name}, &checks, tok);
+                    tok.append("}");
                 },
                 &syn::VariantData::Tuple(ref fields) => {
                     impl_parse_ordered(fields.iter(), tok);
-                    tok.append("return Ok(");
-                    tok.append(quote!{#name});
-                    tok.append("(");
+
+                    let mut ret_expr = quote::Tokens::new();
+                    ret_expr.append(quote!{#name});
+                    ret_expr.append("(");
 
                     for (i, _) in fields.iter().enumerate() {
                         if i > 0 {
-                            tok.append(",");
+                            ret_expr.append(",");
                         }
-                        tok.append(format!("var{}", i));
+                        ret_expr.append(format!("var{}", i));
                     }
 
-                    tok.append("));");
+                    ret_expr.append(")");
+                    append_checked_return(ret_expr, &checks, tok);
                 },
                 &syn::VariantData::Struct(ref fields) => {
-                    impl_parse_named(fields.iter(), tok);
+                    impl_parse_named(fields.iter(), track, tok);
                     let mut ret_expr = quote::Tokens::new();
-                    ret_expr.append("return Ok(");
                     ret_expr.append(quote!{#name});
                     ret_expr.append("{");
 
@@ -625,27 +1487,314 @@ fn impl_parse_from(ast: &syn::MacroInput, tok: &mut quote::Tokens) {
                             None => panic!("Encountered unnamed field while trying to derive named field parsing")
                         };
 
-                        tok.append(format!("let {}_r = {}.get_value(fun);", name, name));
+                        tok.append(format!("let {}_r = {}.get_value(provider, fun);", name, name));
 
 
                         ret_expr.append(format!("{}: {}_r?", name, name));
                     }
 
-                    ret_expr.append("});");
-
-                    tok.append(ret_expr);
+                    ret_expr.append("}");
+                    append_checked_return(ret_expr, &checks, tok);
                 },
             }
         }
     }
 
     tok.append(quote!{
-        fun(format!("Tried to parse {}, found '{}' which I couldn't handle", stringify!(#name), nxt));
+        fun(provider.error(0, nxt.len(), format!("Tried to parse {}, found something which I couldn't handle", stringify!(#name)))
+            .expected(stringify!(#name)).found(nxt));
         return Err(rs_config::ParseError::Final);
     });
     tok.append("}");
 }
 
+/// Apply the override to a field. Fields with checks are overridden on a copy, which is only kept
+/// if it passes them, just like a parsed value
+fn append_override_field(field: &syn::Field, name: &str, binding: &str, tok: &mut quote::Tokens) {
+    let path_checks = get_path_checks(field);
+    let validations = get_validations(field, name);
+
+    if path_checks.is_none() && validations.is_empty() {
+        tok.append(format!("ConfigAble::apply_override({}, path, value, policy, fun)", binding));
+        return;
+    }
+
+    tok.append(format!("rs_config::apply_checked({}, path, value, fun, |mut x, path, rs_config_mark, fun| {{", binding));
+    tok.append(quote!{ConfigAble::apply_override(&mut x, path, value, policy, fun)?;});
+    append_field_checks(path_checks, validations, tok);
+    tok.append("})"); /* Close apply_checked */
+}
+
+/// Walk the path into the fields. The bindings are expressions for `&mut` references to the fields
+fn append_override_named(fields: &[syn::Field], bindings: &[String], tok: &mut quote::Tokens) {
+    /* The config names of all fields come first, so errors only list those. Aliases follow */
+    let mut names: Vec<(String, usize)> = fields.iter().enumerate().map(|(i, x)| (field_name(x), i)).collect();
+    for (i, field) in fields.iter().enumerate() {
+        names.extend(config_names(&field.attrs, field.ident.as_ref().unwrap()).into_iter().skip(1).map(|x| (x, i)));
+    }
+
+    let list: Vec<String> = names.iter().map(|x| format!("{:?}", x.0)).collect();
+    tok.append("{"); /* Open field block */
+    tok.append(format!("let rs_config_names: &[&str] = &[{}];", list.join(", ")));
+
+    tok.append("match path.take_field(rs_config_names) {"); /* Open field matching */
+    for (i, &(_, field)) in names.iter().enumerate() {
+        tok.append(format!("Some({}) => {{", i)); /* Open field match */
+        append_override_field(&fields[field], &field_name(&fields[field]), &bindings[field], tok);
+        tok.append("},"); /* Close field match */
+    }
+    tok.append(format!("_ => {{ fun(path.unknown_field(&rs_config_names[..{}])); Err(rs_config::ParseError::Recoverable) }},", fields.len()));
+    tok.append("}"); /* Close field matching */
+    tok.append("}"); /* Close field block */
+}
+
+/// Walk the path into the elements of a tuple struct or variant
+fn append_override_ordered(fields: &[syn::Field], bindings: &[String], tok: &mut quote::Tokens) {
+    tok.append(format!("match path.take_index({}) {{", bindings.len())); /* Open index matching */
+    for (i, (field, binding)) in fields.iter().zip(bindings).enumerate() {
+        tok.append(format!("Some({}) => {{", i)); /* Open element match */
+        append_override_field(field, &i.to_string(), binding, tok);
+        tok.append("},"); /* Close element match */
+    }
+    tok.append(format!("_ => {{ fun(path.unknown_index({})); Err(rs_config::ParseError::Recoverable) }},", bindings.len()));
+    tok.append("}"); /* Close index matching */
+}
+
+/// Walk the path into the fields of `target` (`self`, or the copy `rs_config_ret`). This
+/// evaluates to the result of the override
+fn append_override_walk(ast: &syn::MacroInput, target: &str, tok: &mut quote::Tokens) {
+    let name = &ast.ident;
+
+    match ast.body {
+        /* Handle Enums, the path continues into the fields of the current variant */
+        syn::Body::Enum(ref vars) => {
+            tok.append(format!("match {} {{", if target == "self" { "*self" } else { target })); /* Open variant matching */
+            for var in vars {
+                let vname = &var.ident;
+                let cname = config_name(&var.attrs, vname);
+                match var.data {
+                    syn::VariantData::Unit => {
+                        tok.append(quote!{
+                            #name::#vname => {
+                                fun(path.no_fields(#cname));
+                                Err(rs_config::ParseError::Recoverable)
+                            },
+                        });
+                    },
+                    syn::VariantData::Tuple(ref fields) => {
+                        let bindings: Vec<String> = (0..fields.len()).map(|i| format!("rs_config_{}", i)).collect();
+                        tok.append(quote!{#name::#vname});
+                        tok.append(format!("({}) => {{", bindings.iter().map(|x| format!("ref mut {}", x)).collect::<Vec<_>>().join(",")));
+                        append_override_ordered(fields, &bindings, tok);
+                        tok.append("},"); /* Close variant match */
+                    },
+                    syn::VariantData::Struct(ref fields) => {
+                        let names: Vec<String> = fields.iter().map(|x| x.ident.as_ref().unwrap().to_string()).collect();
+                        let bindings: Vec<String> = names.iter().map(|x| format!("rs_config_{}", x)).collect();
+                        tok.append(quote!{#name::#vname});
+                        tok.append(format!("{{ {} }} => {{", names.iter().map(|x| format!("{}: ref mut rs_config_{}", x, x)).collect::<Vec<_>>().join(",")));
+                        append_override_named(fields, &bindings, tok);
+                        tok.append("},"); /* Close variant match */
+                    },
+                }
+            }
+            tok.append("}"); /* Close variant matching */
+        },
+        /* Handle structs */
+        syn::Body::Struct(ref data) => {
+            match *data {
+                syn::VariantData::Unit => {
+                    tok.append(quote!{
+                        {
+                            fun(path.no_fields(stringify!(#name)));
+                            Err(rs_config::ParseError::Recoverable)
+                        }
+                    });
+                },
+                syn::VariantData::Tuple(ref fields) => {
+                    let bindings: Vec<String> = (0..fields.len()).map(|i| format!("&mut {}.{}", target, i)).collect();
+                    append_override_ordered(fields, &bindings, tok);
+                },
+                syn::VariantData::Struct(ref fields) => {
+                    let bindings: Vec<String> = fields.iter().map(|x| format!("&mut {}.{}", target, x.ident.as_ref().unwrap())).collect();
+                    append_override_named(fields, &bindings, tok);
+                },
+            }
+        }
+    }
+}
+
+/// Run the checks on the entire value `rs_config_ret` after an override. Which fields were given
+/// in the config isn't known anymore, so the fields that differ from their default count as given.
+/// The bindings are expressions for `&` references to the fields
+fn append_override_value_checks(fields: &[syn::Field], bindings: &[String], checks: &str, tok: &mut quote::Tokens) {
+    tok.append(quote!{let mut rs_config_fields = rs_config::validate::ValidationContext::new(rs_config_mark.clone());});
+    for (field, binding) in fields.iter().zip(bindings).filter(|x| x.0.ident.is_some()) {
+        let ty = &field.ty;
+        let cname = field_name(field);
+        let default = match get_attrs(field).and_then(|x| find_attr_lit("default", x)) {
+            Some(syn::Lit::Str(val, _)) => format!("Some(<{} as ConfigAble>::to_config_string(&({})))", quote!{#ty}, val),
+            _ => format!("<{} as ConfigAble>::get_default().ok().map(|x| x.to_config_string())", quote!{#ty}),
+        };
+
+        tok.append(format!("if Some(ConfigAble::to_config_string({})) != {} {{", binding, default)); /* Open given check */
+        tok.append(quote!{rs_config_fields.push(#cname, rs_config_mark.clone());});
+        tok.append("}"); /* Close given check */
+    }
+    tok.append(checks);
+}
+
+fn impl_apply_override(ast: &syn::MacroInput, tok: &mut quote::Tokens) {
+    let name = &ast.ident;
+    tok.append(quote!{
+        #[allow(unused_variables, unused_mut, unreachable_code, unreachable_patterns)]
+        fn apply_override<F>(&mut self, path: &mut rs_config::OverridePath, value: &str, policy: rs_config::OverridePolicy, fun: &mut F) -> Result<(), rs_config::ParseError>
+            where F: FnMut(rs_config::Diagnostic)
+    });
+    tok.append("{"); /* Open apply_override function */
+    tok.append(quote!{
+        if path.is_empty() {
+            return rs_config::apply_value(self, path, value, policy, fun);
+        }
+    });
+
+    if !has_value_checks(ast) {
+        tok.append("return ");
+        append_override_walk(ast, "self", tok);
+        tok.append(";");
+        tok.append("}"); /* Close apply_override function */
+        return;
+    }
+
+    /* The checks on the entire value run on a copy, which is only kept if it passes them */
+    tok.append("return rs_config::apply_checked(self, path, value, fun, |mut rs_config_ret, path, rs_config_mark, fun| {"); /* Open apply_checked */
+    append_override_walk(ast, "rs_config_ret", tok);
+    tok.append("?;");
+
+    match ast.body {
+        syn::Body::Enum(ref vars) => {
+            tok.append("match rs_config_ret {"); /* Open variant matching */
+            for var in vars {
+                let vname = &var.ident;
+                let checks = get_value_checks(&[&ast.attrs, &var.attrs], var.data.fields());
+                if checks.is_empty() {
+                    continue;
+                }
+
+                let fields = var.data.fields();
+                let bindings: Vec<String> = (0..fields.len()).map(|i| format!("rs_config_{}", i)).collect();
+                tok.append(quote!{#name::#vname});
+                match var.data {
+                    syn::VariantData::Unit => {},
+                    syn::VariantData::Tuple(_) => { tok.append("(..)"); },
+                    syn::VariantData::Struct(_) => {
+                        tok.append(format!("{{ {} }}", fields.iter().zip(&bindings).map(|(x, b)| format!("{}: ref {}", x.ident.as_ref().unwrap(), b)).collect::<Vec<_>>().join(",")));
+                    },
+                }
+                tok.append("=> {"); /* Open variant match */
+                append_override_value_checks(fields, &bindings, &checks, tok);
+                tok.append("},"); /* Close variant match */
+            }
+            tok.append("_ => {},");
+            tok.append("}"); /* Close variant matching */
+        },
+        syn::Body::Struct(ref data) => {
+            let bindings: Vec<String> = data.fields().iter().enumerate().map(|(i, x)| match x.ident {
+                Some(ref x) => format!("&rs_config_ret.{}", x),
+                None => format!("&rs_config_ret.{}", i),
+            }).collect();
+            append_override_value_checks(data.fields(), &bindings, &get_value_checks(&[&ast.attrs], data.fields()), tok);
+        },
+    }
+
+    tok.append("Ok(rs_config_ret)");
+    tok.append("});"); /* Close apply_checked */
+    tok.append("}"); /* Close apply_override function */
+}
+
+/// Compare the fields pairwise. `names` are the path segments, the bindings are the `&` references
+/// to the old and new values
+fn append_diff_fields(names: &[String], old: &[String], new: &[String], tok: &mut quote::Tokens) {
+    for (i, name) in names.iter().enumerate() {
+        tok.append(format!("ConfigAble::diff_at({}, {}, &rs_config::diff::field_path(path, \"{}\"), fun);", old[i], new[i], name));
+    }
+}
+
+/// The path segments of the fields: the config names of named fields, the positions otherwise
+fn diff_names(fields: &[syn::Field]) -> Vec<String> {
+    fields.iter().enumerate().map(|(i, x)| match x.ident {
+        Some(_) => field_name(x),
+        None => i.to_string(),
+    }).collect()
+}
+
+fn impl_diff(ast: &syn::MacroInput, tok: &mut quote::Tokens) {
+    let name = &ast.ident;
+    tok.append(quote!{
+        #[allow(unused_variables, unreachable_patterns)]
+        fn diff_at<F>(&self, new: &Self, path: &str, fun: &mut F)
+            where F: FnMut(rs_config::Change)
+    });
+    tok.append("{"); /* Open diff_at function */
+
+    match ast.body {
+        /* Handle Enums, values of the same variant are compared field by field */
+        syn::Body::Enum(ref vars) => {
+            tok.append("match (self, new) {"); /* Open variant matching */
+            for var in vars {
+                let vname = &var.ident;
+                let names: Vec<String> = match var.data {
+                    syn::VariantData::Unit => Vec::new(),
+                    syn::VariantData::Tuple(ref fields) => (0..fields.len()).map(|i| i.to_string()).collect(),
+                    syn::VariantData::Struct(ref fields) => fields.iter().map(|x| x.ident.as_ref().unwrap().to_string()).collect(),
+                };
+                let paths = diff_names(var.data.fields());
+                let old: Vec<String> = names.iter().map(|x| format!("rs_config_{}", x)).collect();
+                let new: Vec<String> = names.iter().map(|x| format!("rs_config_new_{}", x)).collect();
+
+                let pattern = |bindings: &[String]| -> String {
+                    match var.data {
+                        syn::VariantData::Unit => String::new(),
+                        syn::VariantData::Tuple(_) => {
+                            format!("({})", bindings.iter().map(|x| format!("ref {}", x)).collect::<Vec<_>>().join(","))
+                        },
+                        syn::VariantData::Struct(_) => {
+                            format!("{{ {} }}", names.iter().zip(bindings).map(|(n, x)| format!("{}: ref {}", n, x)).collect::<Vec<_>>().join(","))
+                        },
+                    }
+                };
+
+                tok.append("(&");
+                tok.append(quote!{#name::#vname});
+                tok.append(pattern(&old));
+                tok.append(", &");
+                tok.append(quote!{#name::#vname});
+                tok.append(pattern(&new));
+                tok.append(") => {"); /* Open variant match */
+                append_diff_fields(&paths, &old, &new, tok);
+                tok.append("},"); /* Close variant match */
+            }
+            tok.append(quote!{
+                _ => { rs_config::diff::diff_value(self, new, path, fun); },
+            });
+            tok.append("}"); /* Close variant matching */
+        },
+        /* Handle structs */
+        syn::Body::Struct(ref data) => {
+            let names: Vec<String> = match *data {
+                syn::VariantData::Unit => Vec::new(),
+                syn::VariantData::Tuple(ref fields) => (0..fields.len()).map(|i| i.to_string()).collect(),
+                syn::VariantData::Struct(ref fields) => fields.iter().map(|x| x.ident.as_ref().unwrap().to_string()).collect(),
+            };
+            let old: Vec<String> = names.iter().map(|x| format!("&self.{}", x)).collect();
+            let new: Vec<String> = names.iter().map(|x| format!("&new.{}", x)).collect();
+            append_diff_fields(&diff_names(data.fields()), &old, &new, tok);
+        }
+    }
+
+    tok.append("}"); /* Close diff_at function */
+}
+
 fn impl_derive_config_able(ast: &syn::MacroInput) -> quote::Tokens {
     let name = &ast.ident;
 
@@ -655,9 +1804,14 @@ fn impl_derive_config_able(ast: &syn::MacroInput) -> quote::Tokens {
 
     impl_get_format(ast, &mut start);
     impl_get_name(ast, &mut start);
+    impl_get_schema(ast, &mut start);
     impl_parse_from(ast, &mut start);
     impl_get_default(ast, &mut start);
+    impl_write_to(ast, &mut start);
+    impl_write_sample(ast, &mut start);
     impl_merge(ast, &mut start);
+    impl_apply_override(ast, &mut start);
+    impl_diff(ast, &mut start);
 
     start.append("}"); /* Close impl */
 
@@ -669,7 +1823,8 @@ fn impl_derive_config_able(ast: &syn::MacroInput) -> quote::Tokens {
 pub fn print_format(input: TokenStream) -> TokenStream {
     let s = input.to_string();
 
-    let ast = syn::parse_derive_input(&s).unwrap();
+    let mut ast = syn::parse_derive_input(&s).unwrap();
+    apply_rename_all(&mut ast);
 
     let gen = impl_derive_config_able(&ast);
 
@@ -0,0 +1,193 @@
+use std;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+/// How bad a diagnostic is.
+pub enum Severity {
+    /// Additional information, e.g. while recovering from a previous error
+    Note,
+    /// Something is suspicious, but the config is still valid
+    Warning,
+    /// The config is invalid
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Severity::Note => f.write_str("note"),
+            Severity::Warning => f.write_str("warning"),
+            Severity::Error => f.write_str("error"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+/// A location inside a config file.
+///
+/// Lines and columns are 1-based, `end_column` is exclusive.
+pub struct Span {
+    pub file: String,
+    pub line: usize,
+    pub start_column: usize,
+    pub end_column: usize,
+}
+
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{},{}", self.file, self.line, self.start_column)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A position captured from a ConfigProvider, that can be turned into a diagnostic later.
+///
+/// This is used when the error can only be detected after the provider moved on, e.g. when an
+/// array turns out to have the wrong length after it was parsed.
+pub struct Mark {
+    span: Span,
+    include_chain: Vec<Span>,
+}
+
+impl Mark {
+    /// Create a new mark
+    /// # Arguments
+    /// * `span`: The location in the innermost file
+    /// * `include_chain`: The `!include` lines that lead to the innermost file, innermost first
+    pub fn new(span: Span, include_chain: Vec<Span>) -> Self {
+        return Mark { span, include_chain };
+    }
+
+    /// The location this mark points to
+    pub fn span(&self) -> &Span {
+        return &self.span;
+    }
+
+    /// Create an error diagnostic pointing at this mark
+    pub fn error<S: Into<String>>(&self, message: S) -> Diagnostic {
+        return Diagnostic::error(message).with_mark(self);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A single problem found while reading a config.
+///
+/// This is what gets passed into the error reporting function of `ConfigAble::parse_from`.
+/// The `Display` implementation renders it for humans, the fields are public for tools.
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+    /// Where the problem was found. None if there's no sensible location
+    pub span: Option<Span>,
+    /// The `!include` lines that lead to `span`, innermost first
+    pub include_chain: Vec<Span>,
+    /// What the parser wanted to see
+    pub expected: Option<String>,
+    /// What the parser actually found
+    pub found: Option<String>,
+}
+
+impl Diagnostic {
+    /// Create a new diagnostic without location
+    pub fn new<S: Into<String>>(severity: Severity, message: S) -> Self {
+        return Diagnostic {
+            severity,
+            message: message.into(),
+            span: None,
+            include_chain: Vec::new(),
+            expected: None,
+            found: None,
+        };
+    }
+
+    /// Create a new error without location
+    pub fn error<S: Into<String>>(message: S) -> Self {
+        return Diagnostic::new(Severity::Error, message);
+    }
+
+    /// Create a new warning without location
+    pub fn warning<S: Into<String>>(message: S) -> Self {
+        return Diagnostic::new(Severity::Warning, message);
+    }
+
+    /// Create a new note without location
+    pub fn note<S: Into<String>>(message: S) -> Self {
+        return Diagnostic::new(Severity::Note, message);
+    }
+
+    /// Set the location from a Mark
+    pub fn with_mark(mut self, mark: &Mark) -> Self {
+        self.span = Some(mark.span.clone());
+        self.include_chain = mark.include_chain.clone();
+        return self;
+    }
+
+    /// Set the location, for diagnostics outside of a ConfigProvider
+    pub fn with_span(mut self, span: Span) -> Self {
+        self.span = Some(span);
+        return self;
+    }
+
+    /// Set the severity
+    pub fn with_severity(mut self, severity: Severity) -> Self {
+        self.severity = severity;
+        return self;
+    }
+
+    /// Set what the parser expected to find
+    pub fn expected<S: Into<String>>(mut self, expected: S) -> Self {
+        self.expected = Some(expected.into());
+        return self;
+    }
+
+    /// Set what the parser actually found
+    pub fn found<S: Into<String>>(mut self, found: S) -> Self {
+        self.found = Some(found.into());
+        return self;
+    }
+
+    /// Check whether this diagnostic invalidates the config
+    pub fn is_error(&self) -> bool {
+        return self.severity == Severity::Error;
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.severity, self.message)?;
+
+        if let Some(ref span) = self.span {
+            write!(f, "\n  --> {}", span)?;
+        }
+
+        for include in &self.include_chain {
+            write!(f, "\n  included from {}", include)?;
+        }
+
+        match (self.expected.as_ref(), self.found.as_ref()) {
+            (Some(e), Some(x)) => write!(f, "\n  expected {}, found {}", e, x)?,
+            (Some(e), None) => write!(f, "\n  expected {}", e)?,
+            (None, Some(x)) => write!(f, "\n  found {}", x)?,
+            (None, None) => {},
+        }
+
+        return Ok(());
+    }
+}
+
+impl std::error::Error for Diagnostic {}
+
+#[cfg(test)]
+mod test {
+    use diagnostic::{Diagnostic, Mark, Span};
+
+    #[test]
+    fn test_diagnostic_display() {
+        let span = Span { file: "Testfile".into(), line: 2, start_column: 4, end_column: 5 };
+        let include = Span { file: "Root".into(), line: 7, start_column: 1, end_column: 9 };
+        let diag = Mark::new(span, vec![include]).error("Tried to consume {").expected("'{'").found("'['");
+
+        assert_eq!(diag.to_string(), "error: Tried to consume {\n  --> Testfile:2,4\n  included from Root:7,1\n  expected '{', found '['");
+        assert_eq!(Diagnostic::warning("Careful").to_string(), "warning: Careful");
+    }
+}
@@ -0,0 +1,140 @@
+//! Differences between two values of a config, e.g. to find out what changed on a reload.
+//!
+//! Paths to the changed values use the same syntax as overrides: struct fields by name
+//! (`output.path`), elements of lists by index (`inputs[1]`), and elements of tuples by position
+//! (`pair.0`). Map values use their key in config syntax (`servers["a"]`).
+use std;
+use ConfigAble;
+
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A value that differs between two configs
+pub struct Change {
+    /// The path to the value, empty for the entire config
+    pub path: String,
+    /// The old value in config syntax. None if it was added, e.g. an element at the end of a list
+    pub old: Option<String>,
+    /// The new value in config syntax. None if it was removed
+    pub new: Option<String>,
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let path = if self.path.is_empty() { "the config" } else { &self.path };
+
+        match (&self.old, &self.new) {
+            (Some(old), Some(new)) => write!(f, "{}: {} -> {}", path, old, new),
+            (Some(old), None) => write!(f, "{}: removed {}", path, old),
+            (None, Some(new)) => write!(f, "{}: added {}", path, new),
+            (None, None) => write!(f, "{}: changed", path),
+        }
+    }
+}
+
+/// The path to the field `name` inside the value at `path`. Also used for elements of tuples
+pub fn field_path(path: &str, name: &str) -> String {
+    if path.is_empty() {
+        return name.to_string();
+    }
+
+    return format!("{}.{}", path, name);
+}
+
+/// The path to the list element `index` inside the value at `path`
+pub fn index_path(path: &str, index: usize) -> String {
+    return format!("{}[{}]", path, index);
+}
+
+/// Compare two values as a whole, by their config text.
+///
+/// This is the default of ConfigAble::diff_at, for values without fields
+pub fn diff_value<T, F>(old: &T, new: &T, path: &str, fun: &mut F)
+    where T: ConfigAble,
+          F: FnMut(Change) {
+    let old = old.to_config_string();
+    let new = new.to_config_string();
+
+    if old != new {
+        fun(Change { path: path.to_string(), old: Some(old), new: Some(new) });
+    }
+}
+
+/// Compare two lists element-wise. Elements past the end of the shorter list were added or removed
+pub fn diff_elements<'a, T, I, F>(old: I, new: I, path: &str, fun: &mut F)
+    where T: ConfigAble + 'a,
+          I: IntoIterator<Item=&'a T>,
+          F: FnMut(Change) {
+    let mut old = old.into_iter();
+    let mut new = new.into_iter();
+
+    for index in 0.. {
+        match (old.next(), new.next()) {
+            (Some(x), Some(y)) => x.diff_at(y, &index_path(path, index), fun),
+            (Some(x), None) => fun(Change { path: index_path(path, index), old: Some(x.to_config_string()), new: None }),
+            (None, Some(y)) => fun(Change { path: index_path(path, index), old: None, new: Some(y.to_config_string()) }),
+            (None, None) => { return; },
+        }
+    }
+}
+
+/// Compare the values of two maps by key. Keys are rendered in config syntax, and reported in
+/// their order
+pub fn diff_entries<'a, K, V, F>(old: Vec<(&'a K, &'a V)>, new: Vec<(&'a K, &'a V)>, path: &str, fun: &mut F)
+    where K: ConfigAble + 'a,
+          V: ConfigAble + 'a,
+          F: FnMut(Change) {
+    let mut entries: std::collections::BTreeMap<String, (Option<&V>, Option<&V>)> = std::collections::BTreeMap::new();
+    for (key, value) in old {
+        entries.entry(key.to_config_string()).or_default().0 = Some(value);
+    }
+    for (key, value) in new {
+        entries.entry(key.to_config_string()).or_default().1 = Some(value);
+    }
+
+    for (key, values) in entries {
+        let path = format!("{}[{}]", path, key);
+        match values {
+            (Some(x), Some(y)) => x.diff_at(y, &path, fun),
+            (x, y) => fun(Change { path, old: x.map(|x| x.to_config_string()), new: y.map(|y| y.to_config_string()) }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ConfigAble;
+    use super::Change;
+
+    use std::collections::HashMap;
+
+    fn change(path: &str, old: Option<&str>, new: Option<&str>) -> Change {
+        return Change { path: path.to_string(), old: old.map(|x| x.to_string()), new: new.map(|x| x.to_string()) };
+    }
+
+    #[test]
+    fn test_diff_value() {
+        assert!(1u8.diff(&1).is_empty());
+        assert!("a".to_string().diff(&"b".to_string()) == vec![change("", Some("\"a\""), Some("\"b\""))]);
+        assert!(Some(1u8).diff(&None) == vec![change("", Some("Some(1)"), Some("None"))]);
+    }
+
+    #[test]
+    fn test_diff_elements() {
+        let old = vec![(1u8, 'a'), (2, 'b'), (3, 'c')];
+        let new = vec![(1u8, 'a'), (2, 'x')];
+        assert!(old.diff(&new) == vec![change("[1].1", Some("'b'"), Some("'x'")), change("[2]", Some("(3, 'c')"), None)]);
+        assert!(new.diff(&old)[1] == change("[2]", None, Some("(3, 'c')")));
+
+        assert!([Some(1u8), None].diff(&[Some(2), None]) == vec![change("[0]", Some("Some(1)"), Some("Some(2)"))]);
+    }
+
+    #[test]
+    fn test_diff_entries() {
+        let old: HashMap<String, u8> = vec![("a".to_string(), 1), ("b".to_string(), 2)].into_iter().collect();
+        let new: HashMap<String, u8> = vec![("b".to_string(), 3), ("c".to_string(), 4)].into_iter().collect();
+
+        let changes: Vec<String> = old.diff(&new).iter().map(|x| x.to_string()).collect();
+        assert!(changes == vec!["[\"a\"]: removed 1", "[\"b\"]: 2 -> 3", "[\"c\"]: added 4"]);
+    }
+}
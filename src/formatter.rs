@@ -0,0 +1,583 @@
+use std;
+use std::path::Path;
+
+use ConfigError;
+use diagnostic::{Diagnostic, Span};
+use provider::read_lines;
+
+/// The width the formatter tries to keep lines in
+const MAX_WIDTH: usize = 100;
+/// The indentation used per nesting level
+const INDENT: &str = "    ";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Lines that don't carry config values, but have to survive formatting
+pub enum Trivia {
+    /// A comment line (starting with '#'), without surrounding whitespace
+    Comment(String),
+    /// A special line (starting with '!'), e.g. `!include`
+    Directive(String),
+    /// An empty line
+    BlankLine,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// The kind of brackets around a Group
+pub enum Delimiter {
+    /// `{ }` for structs
+    Brace,
+    /// `[ ]` for lists
+    Bracket,
+    /// `( )` for tuples
+    Paren,
+}
+
+impl Delimiter {
+    fn from_open(c: char) -> Option<Self> {
+        match c {
+            '{' => Some(Delimiter::Brace),
+            '[' => Some(Delimiter::Bracket),
+            '(' => Some(Delimiter::Paren),
+            _ => None,
+        }
+    }
+
+    fn from_close(c: char) -> Option<Self> {
+        match c {
+            '}' => Some(Delimiter::Brace),
+            ']' => Some(Delimiter::Bracket),
+            ')' => Some(Delimiter::Paren),
+            _ => None,
+        }
+    }
+
+    fn open(&self) -> char {
+        match *self {
+            Delimiter::Brace => '{',
+            Delimiter::Bracket => '[',
+            Delimiter::Paren => '(',
+        }
+    }
+
+    fn close(&self) -> char {
+        match *self {
+            Delimiter::Brace => '}',
+            Delimiter::Bracket => ']',
+            Delimiter::Paren => ')',
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A single element of an Entry
+pub enum Node {
+    /// Anything that isn't structure. Identifiers, numbers and literals are kept verbatim
+    Atom(String),
+    /// A ':'
+    Colon,
+    /// A bracketed list of entries
+    Group(Group),
+    /// A comment or directive in the middle of an entry
+    Trivia(Trivia),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// One comma separated element of a Group, e.g. `key: Value(1)`
+pub struct Entry {
+    /// Comments, directives and blank lines in front of the entry
+    pub leading: Vec<Trivia>,
+    /// The content
+    pub nodes: Vec<Node>,
+    /// Whether the node at the same index was directly attached to its predecessor in the input
+    /// (no whitespace in between). This keeps things like `fe80::1` intact
+    glued: Vec<bool>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A bracketed list of entries
+pub struct Group {
+    pub delimiter: Delimiter,
+    pub entries: Vec<Entry>,
+    /// Comments, directives and blank lines after the last entry
+    pub trailing: Vec<Trivia>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A lossless representation of a config file.
+///
+/// Unlike ConfigProvider this keeps comments, blank lines and directives, so it can be written
+/// back out with canonical layout without losing anything.
+pub struct SyntaxTree {
+    /// The top level entries (a file is implicitly wrapped in {})
+    pub entries: Vec<Entry>,
+    /// Comments, directives and blank lines after the last entry
+    pub trailing: Vec<Trivia>,
+}
+
+/// The tokens found in the input
+enum Token {
+    Trivia(Trivia),
+    Open(Delimiter),
+    Close(Delimiter),
+    Comma,
+    Colon,
+    Atom(String),
+}
+
+struct Lexed {
+    token: Token,
+    span: Span,
+    /// No whitespace between this and the previous token
+    glued: bool,
+}
+
+/// Find the end of a quoted literal starting at `start`. Returns the byte offset after it
+fn literal_end(line: &str, start: usize, quote: char) -> Option<usize> {
+    let mut escaped = false;
+
+    for (i, c) in line[start + 1..].char_indices() {
+        if escaped {
+            escaped = false;
+        } else if c == '\\' {
+            escaped = true;
+        } else if c == quote {
+            return Some(start + 1 + i + 1);
+        }
+    }
+
+    return None;
+}
+
+fn is_delimiter(c: char) -> bool {
+    return c.is_whitespace() || "{}[](),:\"'".contains(c);
+}
+
+/// Split the lines into tokens. This uses the same line rules as ConfigProvider.
+fn lex(lines: &[(usize, String)], file: &str) -> Result<Vec<Lexed>, Box<Diagnostic>> {
+    let mut ret = Vec::new();
+
+    for &(num, ref line) in lines {
+        let span = |start: usize, end: usize| Span { file: file.to_string(), line: num, start_column: start + 1, end_column: end + 1 };
+        let trimmed = line.trim();
+
+        if trimmed.is_empty() {
+            ret.push(Lexed { token: Token::Trivia(Trivia::BlankLine), span: span(0, 0), glued: false });
+            continue;
+        }
+        if trimmed.starts_with('#') {
+            ret.push(Lexed { token: Token::Trivia(Trivia::Comment(trimmed.to_string())), span: span(0, line.len()), glued: false });
+            continue;
+        }
+        if trimmed.starts_with('!') {
+            ret.push(Lexed { token: Token::Trivia(Trivia::Directive(trimmed.to_string())), span: span(0, line.len()), glued: false });
+            continue;
+        }
+
+        let mut pos = 0;
+        let mut glued = false;
+        while let Some(c) = line[pos..].chars().next() {
+            if c.is_whitespace() {
+                pos += c.len_utf8();
+                glued = false;
+                continue;
+            }
+
+            let (token, end) = match c {
+                '"' | '\'' => {
+                    match literal_end(line, pos, c) {
+                        Some(end) => (Token::Atom(line[pos..end].to_string()), end),
+                        None => {
+                            return Err(Box::new(Diagnostic::error("Unterminated literal").expected(format!("closing {}", c))
+                                .with_span(span(pos, line.len()))));
+                        },
+                    }
+                },
+                ',' => (Token::Comma, pos + 1),
+                ':' => (Token::Colon, pos + 1),
+                c if Delimiter::from_open(c).is_some() => (Token::Open(Delimiter::from_open(c).unwrap()), pos + 1),
+                c if Delimiter::from_close(c).is_some() => (Token::Close(Delimiter::from_close(c).unwrap()), pos + 1),
+                _ => {
+                    let end = line[pos..].find(is_delimiter).map(|x| x + pos).unwrap_or(line.len());
+                    (Token::Atom(line[pos..end].to_string()), end)
+                },
+            };
+
+            ret.push(Lexed { token, span: span(pos, end), glued });
+            pos = end;
+            glued = true;
+        }
+    }
+
+    return Ok(ret);
+}
+
+impl Entry {
+    fn new(leading: Vec<Trivia>) -> Self {
+        return Entry { leading, nodes: Vec::new(), glued: Vec::new() };
+    }
+
+    fn push(&mut self, node: Node, glued: bool) {
+        self.nodes.push(node);
+        self.glued.push(glued);
+    }
+
+    /// Check whether this already is a complete `key: value` entry
+    fn has_value(&self) -> bool {
+        match self.nodes.iter().position(|x| *x == Node::Colon) {
+            Some(x) => x + 1 < self.nodes.len() && self.nodes.last() != Some(&Node::Colon),
+            None => false,
+        }
+    }
+}
+
+struct Parser<I: std::iter::Iterator<Item=Lexed>> {
+    tokens: std::iter::Peekable<I>,
+}
+
+impl<I: std::iter::Iterator<Item=Lexed>> Parser<I> {
+    /// Check if the next structural token is a ':'
+    fn colon_follows(&mut self) -> bool {
+        return matches!(self.tokens.peek(), Some(&Lexed { token: Token::Colon, .. }));
+    }
+
+    /// Parse entries until the closing delimiter (or end of input for the top level)
+    fn parse_entries(&mut self, until: Option<(Delimiter, Span)>) -> Result<(Vec<Entry>, Vec<Trivia>), Box<Diagnostic>> {
+        /* Structs (and the top level) may leave out the comma between `key: value` entries */
+        let keyed = matches!(until, None | Some((Delimiter::Brace, _)));
+        let mut entries = Vec::new();
+        let mut cur = Entry::new(Vec::new());
+        let mut pending = Vec::new();
+
+        loop {
+            let lexed = match self.tokens.next() {
+                Some(x) => x,
+                None => {
+                    if let Some((delim, span)) = until {
+                        return Err(Box::new(Diagnostic::error("Unclosed bracket").expected(format!("'{}'", delim.close()))
+                            .found("end of file").with_span(span)));
+                    }
+                    break;
+                },
+            };
+
+            let node = match lexed.token {
+                Token::Trivia(x) => {
+                    pending.push(x);
+                    continue;
+                },
+                Token::Comma => {
+                    if cur.nodes.is_empty() {
+                        return Err(Box::new(Diagnostic::error("Unexpected ','").with_span(lexed.span)));
+                    }
+                    entries.push(cur);
+                    cur = Entry::new(Vec::new());
+                    continue;
+                },
+                Token::Close(delim) => {
+                    match until {
+                        Some((x, _)) if x == delim => { break; },
+                        _ => {
+                            let diag = Diagnostic::error("Unexpected closing bracket").found(format!("'{}'", delim.close()));
+                            let diag = match until {
+                                Some((x, _)) => diag.expected(format!("'{}'", x.close())),
+                                None => diag,
+                            };
+                            return Err(Box::new(diag.with_span(lexed.span)));
+                        },
+                    }
+                },
+                Token::Open(delim) => {
+                    let (entries, trailing) = self.parse_entries(Some((delim, lexed.span)))?;
+                    Node::Group(Group { delimiter: delim, entries, trailing })
+                },
+                Token::Colon => Node::Colon,
+                Token::Atom(x) => {
+                    if keyed && !lexed.glued && cur.has_value() && self.colon_follows() {
+                        entries.push(cur);
+                        cur = Entry::new(Vec::new());
+                    }
+                    Node::Atom(x)
+                },
+            };
+
+            if cur.nodes.is_empty() {
+                cur.leading.append(&mut pending);
+            } else {
+                for x in pending.drain(..) {
+                    cur.push(Node::Trivia(x), false);
+                }
+            }
+            cur.push(node, lexed.glued);
+        }
+
+        if !cur.nodes.is_empty() {
+            entries.push(cur);
+        }
+
+        return Ok((entries, pending));
+    }
+}
+
+/// Remove blank lines at the start and end, and collapse runs of them
+fn clean_trivia(trivia: &[Trivia], at_start: bool, at_end: bool) -> Vec<&Trivia> {
+    let mut ret: Vec<&Trivia> = Vec::new();
+
+    for x in trivia {
+        if *x == Trivia::BlankLine && (ret.last() == Some(&&Trivia::BlankLine) || (ret.is_empty() && at_start)) {
+            continue;
+        }
+        ret.push(x);
+    }
+
+    if at_end {
+        while ret.last() == Some(&&Trivia::BlankLine) {
+            ret.pop();
+        }
+    }
+
+    return ret;
+}
+
+fn write_trivia(trivia: &Trivia, out: &mut String) {
+    match *trivia {
+        Trivia::Comment(ref x) | Trivia::Directive(ref x) => out.push_str(x),
+        Trivia::BlankLine => {},
+    }
+}
+
+fn indent(level: usize, out: &mut String) {
+    for _ in 0..level {
+        out.push_str(INDENT);
+    }
+}
+
+impl Group {
+    /// Check whether there's any trivia inside, which forces a multi line layout
+    fn has_trivia(&self) -> bool {
+        return !self.trailing.is_empty() || self.entries.iter().any(|x| x.has_trivia());
+    }
+
+    /// Write this group on a single line
+    fn write_inline(&self, out: &mut String) {
+        out.push(self.delimiter.open());
+        if !self.entries.is_empty() && self.delimiter == Delimiter::Brace {
+            out.push(' ');
+        }
+
+        for (i, entry) in self.entries.iter().enumerate() {
+            if i > 0 {
+                out.push_str(", ");
+            }
+            entry.write(0, out, self.delimiter == Delimiter::Brace, None);
+        }
+
+        if !self.entries.is_empty() && self.delimiter == Delimiter::Brace {
+            out.push(' ');
+        }
+        out.push(self.delimiter.close());
+    }
+
+    /// Write this group. Uses a single line if possible
+    fn write(&self, level: usize, column: usize, out: &mut String) {
+        if !self.has_trivia() {
+            let mut inline = String::new();
+            self.write_inline(&mut inline);
+
+            if column + inline.len() <= MAX_WIDTH && !inline.contains('\n') {
+                out.push_str(&inline);
+                return;
+            }
+        }
+
+        out.push(self.delimiter.open());
+        out.push('\n');
+        write_entries(&self.entries, &self.trailing, level + 1, self.delimiter == Delimiter::Brace, out);
+        indent(level, out);
+        out.push(self.delimiter.close());
+    }
+}
+
+impl Entry {
+    fn has_trivia(&self) -> bool {
+        return !self.leading.is_empty() || self.nodes.iter().any(|x| match *x {
+            Node::Trivia(_) => true,
+            Node::Group(ref g) => g.has_trivia(),
+            _ => false,
+        });
+    }
+
+    /// Write the content of this entry (without leading trivia)
+    /// # Arguments
+    /// * `level`: The indentation level of the line the entry starts on
+    /// * `keyed`: Whether this is a `key: value` entry, which gets canonical spacing
+    /// * `line_start`: The length of `out` at the start of the current line, to compute the
+    ///   column. None if the entry is written inline and can't break
+    fn write(&self, level: usize, out: &mut String, keyed: bool, line_start: Option<usize>) {
+        let mut line_start = line_start;
+
+        for (i, node) in self.nodes.iter().enumerate() {
+            if i > 0 {
+                let prev = &self.nodes[i - 1];
+                let space = match (prev, node) {
+                    (_, Node::Trivia(_)) | (Node::Trivia(_), _) => false,
+                    (Node::Atom(_), Node::Colon) if keyed && i == 1 => false,
+                    (Node::Colon, _) if keyed && i == 2 => true,
+                    (Node::Atom(_), Node::Group(g)) if g.delimiter == Delimiter::Paren => false,
+                    (Node::Atom(_), Node::Group(g)) if g.delimiter == Delimiter::Brace => true,
+                    _ => !self.glued[i],
+                };
+                if space {
+                    out.push(' ');
+                }
+            }
+
+            match *node {
+                Node::Atom(ref x) => out.push_str(x),
+                Node::Colon => out.push(':'),
+                Node::Group(ref g) => {
+                    let column = line_start.map(|x| out.len() - x).unwrap_or(0);
+                    g.write(level, column, out);
+                },
+                Node::Trivia(ref t) => {
+                    out.push('\n');
+                    indent(level + 1, out);
+                    write_trivia(t, out);
+                    out.push('\n');
+                    line_start = line_start.map(|_| out.len());
+                    indent(level + 1, out);
+                },
+            }
+        }
+    }
+}
+
+/// Write entries on separate lines with commas in between
+fn write_entries(entries: &[Entry], trailing: &[Trivia], level: usize, keyed: bool, out: &mut String) {
+    for (i, entry) in entries.iter().enumerate() {
+        for t in clean_trivia(&entry.leading, i == 0, false) {
+            if *t != Trivia::BlankLine {
+                indent(level, out);
+                write_trivia(t, out);
+            }
+            out.push('\n');
+        }
+
+        let line_start = out.len();
+        indent(level, out);
+        entry.write(level, out, keyed, Some(line_start));
+        if i + 1 < entries.len() {
+            out.push(',');
+        }
+        out.push('\n');
+    }
+
+    for t in clean_trivia(trailing, entries.is_empty(), true) {
+        if *t != Trivia::BlankLine {
+            indent(level, out);
+            write_trivia(t, out);
+        }
+        out.push('\n');
+    }
+}
+
+impl SyntaxTree {
+    /// Parse config lines into a syntax tree.
+    ///
+    /// This only checks the bracket structure, not whether the content fits any ConfigAble type.
+    /// # Arguments
+    /// * `lines`: The numbered lines of the config, as used by ConfigProvider
+    /// * `file`: The file name used for error reporting
+    pub fn parse(lines: &[(usize, String)], file: &str) -> Result<Self, Box<Diagnostic>> {
+        let tokens = lex(lines, file)?;
+        let mut parser = Parser { tokens: tokens.into_iter().peekable() };
+        let (entries, trailing) = parser.parse_entries(None)?;
+
+        return Ok(SyntaxTree { entries, trailing });
+    }
+
+    /// Parse a config from a string, see parse
+    pub fn parse_str(content: &str, file: &str) -> Result<Self, Box<Diagnostic>> {
+        let lines: Vec<(usize, String)> = content.lines().enumerate().map(|(i, x)| (i + 1, x.to_string())).collect();
+        return SyntaxTree::parse(&lines, file);
+    }
+
+    /// Write the tree with canonical layout.
+    ///
+    /// Every top level entry gets its own line, nested groups stay on one line if they fit and
+    /// don't contain comments. Commas are placed at the end of lines, never in front of a closing
+    /// bracket.
+    pub fn to_formatted_string(&self) -> String {
+        let mut ret = String::new();
+        write_entries(&self.entries, &self.trailing, 0, true, &mut ret);
+        return ret;
+    }
+}
+
+/// Format config file content
+pub fn format_str(content: &str) -> Result<String, Box<Diagnostic>> {
+    return Ok(SyntaxTree::parse_str(content, "memory")?.to_formatted_string());
+}
+
+/// Format a config file. This returns the formatted content, and doesn't change the file
+pub fn format_file<P: AsRef<Path>>(path: P) -> Result<String, ConfigError> {
+    let p = path.as_ref();
+    let lines = read_lines(p)?;
+    let tree = SyntaxTree::parse(&lines, p.to_str().unwrap_or("ERROR"))
+        .map_err(|x| ConfigError::Parse { diagnostics: vec![*x], format: None })?;
+
+    return Ok(tree.to_formatted_string());
+}
+
+#[cfg(test)]
+mod test {
+    use formatter::format_str;
+
+    #[test]
+    fn test_format_layout() {
+        let input = "position:Global (Top)\n  inputs : [{source: Spawn(\"monk\")  ,layer:1 }, {source: Stdin}],title: \"ongybar\"";
+        let expected = "position: Global(Top),\ninputs: [{ source: Spawn(\"monk\"), layer: 1 }, { source: Stdin }],\ntitle: \"ongybar\"\n";
+
+        assert_eq!(format_str(input).unwrap(), expected);
+        assert_eq!(format_str(expected).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_format_keeps_trivia() {
+        let input = "\n\n# Header\n!include other.conf\n\n\n\na: 1\n   # about b\nb: [\n# first\n'x', 'y'\n]\n\n# trailing\n\n";
+        let expected = "# Header\n!include other.conf\n\na: 1,\n# about b\nb: [\n    # first\n    'x',\n    'y'\n]\n\n# trailing\n";
+
+        assert_eq!(format_str(input).unwrap(), expected);
+        assert_eq!(format_str(expected).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_format_keeps_glued() {
+        let input = "addr: fe80::1, path: \"a, b: {\", c: Some('}'), d: Var2{x: 1}";
+        let expected = "addr: fe80::1,\npath: \"a, b: {\",\nc: Some('}'),\nd: Var2 { x: 1 }\n";
+
+        assert_eq!(format_str(input).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_format_long_lines() {
+        let input = format!("list: [{}]", (0..30).map(|x| format!("\"{}\"", x)).collect::<Vec<_>>().join(","));
+        let formatted = format_str(&input).unwrap();
+
+        assert!(formatted.starts_with("list: [\n    \"0\",\n    \"1\",\n"));
+        assert!(formatted.ends_with("    \"29\"\n]\n"));
+        assert_eq!(format_str(&formatted).unwrap(), formatted);
+    }
+
+    #[test]
+    fn test_format_errors() {
+        let err = format_str("a: [1, 2}\n").unwrap_err();
+        assert!(err.message == "Unexpected closing bracket");
+        assert!(err.span.map(|x| (x.line, x.start_column)) == Some((1, 9)));
+
+        let err = format_str("a: {\nb: 1\n").unwrap_err();
+        assert!(err.message == "Unclosed bracket");
+        assert!(err.span.map(|x| (x.line, x.start_column)) == Some((1, 4)));
+
+        assert!(format_str("a: \"open\n").is_err());
+    }
+}
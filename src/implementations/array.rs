@@ -2,69 +2,140 @@ use std;
 use ::provider::ConfigProvider;
 use ::ConfigAble;
 use ParseError;
+use Diagnostic;
+use ::schema::{SchemaDefinitions, SchemaNode};
 
+use std::convert::TryFrom;
 use std::vec::Vec;
 
-impl<T: ConfigAble> ConfigAble for [T;4] {
-    fn get_format<F>(_: &mut std::collections::HashSet<String>, fun: &mut F) 
+use implementations::vec::write_list;
+use overrides::{apply_to_element, apply_value, OverridePath, OverridePolicy};
+use diff::{diff_elements, Change};
+
+impl<T: ConfigAble, const N: usize> ConfigAble for [T; N] {
+    fn get_format<F>(set: &mut std::collections::HashSet<String>, fun: &mut F)
         where F: FnMut(&str) {
-            fun("[T;4]: [T, T, T, T]");
+        fun(format!("[{}; {}]: [ {}, {}, ... ] with exactly {} elements", T::get_name(), N, T::get_name(), T::get_name(), N).as_str());
+
+        let key = T::get_format_key();
+        if !set.contains(&key) {
+            set.insert(key);
+
+            fun("\n");
+            T::get_format(set, fun);
         }
+    }
 
-    fn get_name() -> &'static str { "[T;4]" }
+    fn get_name() -> &'static str { "[T; N]" }
+
+    fn get_schema(defs: &mut SchemaDefinitions) -> SchemaNode {
+        SchemaNode::Array { items: Box::new(T::get_schema(defs)), min_items: Some(N), max_items: Some(N) }
+    }
 
     fn parse_from<F>(provider: &mut ConfigProvider, fun: &mut F) -> Result<Self, ParseError>
-        where F: FnMut(String) {
+        where F: FnMut(Diagnostic) {
+        /* Length errors are only known after the array was read, so remember the bracket */
+        let mark = provider.mark(0, 1);
         let vec: Vec<T> = ConfigAble::parse_from(provider, fun)?;
 
-        if vec.len() != 4 {
-            fun(format!("Expected array of size 4, got array of size: {}", vec.len()));
-            return Err(ParseError::Recoverable);
-        }
+        return match <[T; N]>::try_from(vec) {
+            Ok(ret) => Ok(ret),
+            Err(vec) => {
+                fun(mark.error(format!("Expected array of size {}, got array of size: {}", N, vec.len()))
+                    .expected(format!("{} elements", N)).found(format!("{} elements", vec.len())));
+                Err(ParseError::Recoverable)
+            },
+        };
+    }
 
-        let mut iter = vec.into_iter();
+    fn get_default() -> Result<Self, ()> {
+        let vec = (0..N).map(|_| T::get_default()).collect::<Result<Vec<T>, ()>>()?;
+        return <[T; N]>::try_from(vec).map_err(|_| ());
+    }
 
-        let ret = [iter.next().unwrap(), iter.next().unwrap(), iter.next().unwrap(), iter.next().unwrap()];
-        return Ok(ret);
+    fn write_to<F>(&self, fun: &mut F)
+        where F: FnMut(&str) {
+        write_list(self.iter(), fun);
     }
 
-    fn get_default() -> Result<Self, ()> {
-        Ok([T::get_default()?, T::get_default()?, T::get_default()?, T::get_default()?])
+    /// Arrays are merged element-wise, in order. If an element can't be merged, the elements in
+    /// front of it are merged already (see ConfigAble::merge)
+    fn merge(&mut self, rhs: Self) -> Result<(), ()> {
+        for (lhs, rhs) in self.iter_mut().zip(IntoIterator::into_iter(rhs)) {
+            lhs.merge(rhs)?;
+        }
+
+        return Ok(());
     }
 
-    fn merge(&mut self, _: Self) -> Result<(), ()> {
-        return Err(());
+    fn apply_override<F>(&mut self, path: &mut OverridePath, value: &str, policy: OverridePolicy, fun: &mut F) -> Result<(), ParseError>
+        where F: FnMut(Diagnostic) {
+        if path.is_empty() {
+            return apply_value(self, path, value, policy, fun);
+        }
+
+        return apply_to_element(self, path, value, policy, fun);
+    }
+
+    fn diff_at<F>(&self, new: &Self, path: &str, fun: &mut F)
+        where F: FnMut(Change) {
+        diff_elements(self.iter(), new.iter(), path, fun);
     }
 }
 
-impl<T: ConfigAble> ConfigAble for [T;6] {
-    fn get_format<F>(_: &mut std::collections::HashSet<String>, fun: &mut F) 
-        where F: FnMut(&str) {
-            fun("[T;4]: [T, T, T, T, T, T]");
-        }
+#[cfg(test)]
+mod test {
+    use ConfigProvider;
+    use ConfigAble;
+    use Diagnostic;
 
-    fn get_name() -> &'static str { "[T;6]" }
+    #[test]
+    fn test_array_write() {
+        let val = ['a', 'b', 'c', 'd'];
+        assert!(val.to_config_string() == "['a', 'b', 'c', 'd']");
 
-    fn parse_from<F>(provider: &mut ConfigProvider, fun: &mut F) -> Result<Self, ParseError>
-        where F: FnMut(String) {
-        let vec: Vec<T> = ConfigAble::parse_from(provider, fun)?;
+        let mut provider = ConfigProvider::new_from_str(val.to_config_string());
+        assert!(<[char;4] as ConfigAble>::parse_from(&mut provider, &mut |_| {}) == Ok(val));
+    }
 
-        if vec.len() != 6 {
-            fun(format!("Expected array of size 6, got array of size: {}", vec.len()));
-            return Err(ParseError::Recoverable);
-        }
+    #[test]
+    fn test_array_parse() {
+        let mut provider = ConfigProvider::new_from_str("[1, 2, 3]");
+        assert!(<[u8; 3] as ConfigAble>::parse_from(&mut provider, &mut |_| {}) == Ok([1, 2, 3]));
+
+        let mut provider = ConfigProvider::new_from_str("[[0, 0], [1, 2]]");
+        assert!(<[[i32; 2]; 2] as ConfigAble>::parse_from(&mut provider, &mut |_| {}) == Ok([[0, 0], [1, 2]]));
+    }
 
-        let mut iter = vec.into_iter();
+    #[test]
+    fn test_array_size_error() {
+        let mut errors = Vec::new();
+        let mut provider = ConfigProvider::new_from_str("  [1, 2]");
+        assert!(<[u8; 3] as ConfigAble>::parse_from(&mut provider, &mut |x: Diagnostic| errors.push(x)).is_err());
 
-        let ret = [iter.next().unwrap(), iter.next().unwrap(), iter.next().unwrap(), iter.next().unwrap(), iter.next().unwrap(), iter.next().unwrap()];
-        return Ok(ret);
+        assert!(errors.len() == 1);
+        assert!(errors[0].message == "Expected array of size 3, got array of size: 2");
+        assert!(errors[0].span.as_ref().map(|x| (x.start_column, x.end_column)) == Some((3, 4)));
+        assert!(errors[0].expected.as_deref() == Some("3 elements"));
     }
 
-    fn get_default() -> Result<Self, ()> {
-        Ok([T::get_default()?, T::get_default()?, T::get_default()?, T::get_default()?, T::get_default()?, T::get_default()?])
+    #[test]
+    fn test_array_merge_default() {
+        let mut val = [vec!['a'], vec![]];
+        assert!(val.merge([vec!['b'], vec!['c']]).is_ok());
+        assert!(val == [vec!['a', 'b'], vec!['c']]);
+
+        /* The first element is merged, but the second can't be */
+        let mut val = [None, Some(1u8)];
+        assert!(val.merge([Some(5), Some(2)]).is_err());
+        assert!(val == [Some(5), Some(1)]);
+
+        assert!(<[Option<char>; 3] as ConfigAble>::get_default() == Ok([None, None, None]));
+        assert!(<[char; 2] as ConfigAble>::get_default().is_err());
     }
 
-    fn merge(&mut self, _: Self) -> Result<(), ()> {
-        return Err(());
+    #[test]
+    fn test_array_format() {
+        assert!(<[u8; 16] as ConfigAble>::get_format_str().starts_with("[u8; 16]: [ u8, u8, ... ] with exactly 16 elements\nu8: Integer"));
     }
 }
@@ -2,8 +2,10 @@ use std;
 use ::provider::ConfigProvider;
 use ::ConfigAble;
 use ParseError;
+use Diagnostic;
+use ::schema::{SchemaDefinitions, SchemaNode};
 
-use implementations::literals::parse_char;
+use implementations::literals::{parse_char, write_char};
 
 impl ConfigAble for char {
     fn get_format<F>(_: &mut std::collections::HashSet<String>, fun: &mut F) 
@@ -13,8 +15,12 @@ impl ConfigAble for char {
 
     fn get_name() -> &'static str { "char" }
 
+    fn get_schema(_: &mut SchemaDefinitions) -> SchemaNode {
+        SchemaNode::String { format: None, min_length: Some(1), max_length: Some(1) }
+    }
+
     fn parse_from<F>(provider: &mut ConfigProvider, fun: &mut F) -> Result<char, ParseError>
-        where F: FnMut(String) {
+        where F: FnMut(Diagnostic) {
 
         if let Some(content) = provider.get_next() {
             match parse_char(content.as_str()) {
@@ -23,30 +29,36 @@ impl ConfigAble for char {
                     return Ok(ret);
                 },
                 Err(x) => {
-                    fun(x);
+                    fun(provider.error(0, 1, x).expected("char literal"));
                     return Err(ParseError::Recoverable);
                 }
             }
         }
 
-        fun("At end of file :(".to_string());
+        fun(provider.error(0, 0, "At end of file :("));
         return Err(ParseError::Final);
     }
 
     fn get_default() -> Result<Self, ()> { Err(()) }
 
+    fn write_to<F>(&self, fun: &mut F)
+        where F: FnMut(&str) {
+        fun(write_char(*self).as_str());
+    }
+
     fn merge(&mut self, rhs: Self) -> Result<(), ()> { if *self == rhs { Ok(()) } else { Err(()) } }
 }
 
 #[cfg(test)]
 mod test {
     use ConfigProvider;
+    use Diagnostic;
     use ConfigAble;
 
     #[test]
     fn test_char_parse() {
         let mut builder = String::new();
-        let mut fun = |x: String| builder.push_str(x.as_str());
+        let mut fun = |x: Diagnostic| builder.push_str(&x.to_string());
         let mut provider = ConfigProvider::new_from_str("'\\\\'var");
 
         let val = char::parse_from(&mut provider, &mut fun);
@@ -56,4 +68,13 @@ mod test {
         assert!(nxt == Some("var".to_string()));
     }
 
+    #[test]
+    fn test_char_write() {
+        for c in &['a', '\'', '"', '\\', '\n', '\0', '\u{7f}', 'ü', '猫'] {
+            let mut provider = ConfigProvider::new_from_str(c.to_config_string());
+            assert!(char::parse_from(&mut provider, &mut |_| {}) == Ok(*c));
+            assert!(provider.get_next().is_none());
+        }
+    }
+
 }
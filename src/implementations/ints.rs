@@ -2,112 +2,438 @@ use std;
 use ::provider::ConfigProvider;
 use ::ConfigAble;
 use ParseError;
+use Diagnostic;
+use ::schema::{SchemaDefinitions, SchemaNode};
 
-impl ConfigAble for i32 {
-    fn get_format<F>(_: &mut std::collections::HashSet<String>, fun: &mut F) 
-        where F: FnMut(&str) {
-            fun("i32: Digits");
-        }
+use std::convert::TryFrom;
+use std::num::{NonZeroI8, NonZeroI16, NonZeroI32, NonZeroI64, NonZeroI128, NonZeroIsize};
+use std::num::{NonZeroU8, NonZeroU16, NonZeroU32, NonZeroU64, NonZeroU128, NonZeroUsize};
 
-    fn get_name() -> &'static str { "i32" }
+/// Get the length of the number (or other plain) token at the start of `content`.
+///
+/// Tokens end at whitespace and at the delimiters that can follow a value (or a map key)
+pub fn token_len(content: &str) -> usize {
+    return content.find(|c: char| c.is_whitespace() || ",}]):".contains(c)).unwrap_or(content.len());
+}
 
-    fn parse_from<F>(provider: &mut ConfigProvider, fun: &mut F) -> Result<Self, ParseError>
-        where F: FnMut(String) {
+/// Strip the `_` separators from the digits of a number.
+///
+/// Separators are only allowed between digits, not at the start
+fn strip_separators(digits: &str) -> Result<String, String> {
+    if digits.is_empty() {
+        return Err("no digits".to_string());
+    }
 
-        if let Some(content) = provider.get_next() {
-            /* Fix this, it's majorly wrong */
-            let max = content.find(|c: char| c.is_whitespace()).unwrap_or(content.len());
-            let tmp = &content[0 .. max];
-            match tmp.parse::<i32>() {
-                Ok(ret) => {
-                    provider.consume(max, fun)?;
-                    return Ok(ret);
-                },
-                Err(x) => {
-                    fun(format!("Failed to parse '{}' into an i32: {}", tmp, x));
-                    return Err(ParseError::Recoverable);
-                }
-            }
-        }
+    if digits.starts_with('_') {
+        return Err("digits can't start with '_'".to_string());
+    }
 
-        fun("At end of file :(".to_string());
-        return Err(ParseError::Final);
+    return Ok(digits.chars().filter(|c| *c != '_').collect());
+}
+
+/// Parse an integer literal into its sign (true if negative) and magnitude.
+///
+/// This accepts a leading `+` or `-`, the prefixes `0x`, `0o` and `0b` and `_` separators.
+fn parse_int_literal(lit: &str) -> Result<(bool, u128), String> {
+    let (negative, rest) = match lit.as_bytes().first() {
+        Some(b'-') => (true, &lit[1..]),
+        Some(b'+') => (false, &lit[1..]),
+        _ => (false, lit),
+    };
+
+    let (radix, digits) = match rest.get(0..2) {
+        Some("0x") | Some("0X") => (16, &rest[2..]),
+        Some("0o") | Some("0O") => (8, &rest[2..]),
+        Some("0b") | Some("0B") => (2, &rest[2..]),
+        _ => (10, rest),
+    };
+
+    let digits = strip_separators(digits)?;
+    if let Some(c) = digits.chars().find(|c| !c.is_digit(radix)) {
+        return Err(format!("invalid digit '{}' for base {}", c, radix));
     }
 
-    fn get_default() -> Result<Self, ()> { Err(()) }
+    return match u128::from_str_radix(&digits, radix) {
+        Ok(x) => Ok((negative, x)),
+        Err(_) => Err("number too large".to_string()),
+    };
+}
 
-    fn merge(&mut self, rhs: Self) -> Result<(), ()> { if *self == rhs { Ok(()) } else { Err(()) } }
+/// Parse a float literal, with the same sign and separator rules as integers.
+///
+/// `inf`, `-inf` and `nan` are the only accepted non-finite values. Finite literals that are too
+/// large for the type are rejected instead of turning into infinity.
+fn parse_float_literal<T>(lit: &str) -> Result<T, String>
+    where T: std::str::FromStr + Copy + std::ops::Neg<Output=T> + PartialEq {
+    let (negative, rest) = match lit.as_bytes().first() {
+        Some(b'-') => (true, &lit[1..]),
+        Some(b'+') => (false, &lit[1..]),
+        _ => (false, lit),
+    };
+
+    let val: T = match rest {
+        "inf" => "inf".parse().map_err(|_| String::new())?,
+        "nan" if lit == "nan" => { return "nan".parse().map_err(|_| String::new()); },
+        _ => {
+            if rest.chars().any(|c| c.is_alphabetic() && c != 'e' && c != 'E') {
+                return Err("invalid float literal".to_string());
+            }
+
+            let digits = strip_separators(rest)?;
+            let val: T = digits.parse().map_err(|_| "invalid float literal".to_string())?;
+            if val == "inf".parse().map_err(|_| String::new())? {
+                return Err("number out of range".to_string());
+            }
+            val
+        },
+    };
+
+    return Ok(if negative { -val } else { val });
 }
 
-impl ConfigAble for u32 {
-    fn get_format<F>(_: &mut std::collections::HashSet<String>, fun: &mut F) 
-        where F: FnMut(&str) {
-            fun("u32: Digits");
+/// Report that the token at the start of the provider isn't a number of type `name`
+fn number_error<F>(provider: &ConfigProvider, len: usize, tok: &str, name: &str, reason: &str, fun: &mut F)
+    where F: FnMut(Diagnostic) {
+    fun(provider.error(0, len, format!("Failed to parse '{}' into {}: {}", tok, name, reason)).expected(name).found(tok));
+}
+
+macro_rules! impl_int {
+    ($t:ident) => {
+        impl ConfigAble for $t {
+            fn get_format<F>(_: &mut std::collections::HashSet<String>, fun: &mut F)
+                where F: FnMut(&str) {
+                if $t::MIN == 0 {
+                    fun(&format!("{}: Integer in {}..={}, e.g. 42, 0x2a, 0o52, 0b10_1010", stringify!($t), $t::MIN, $t::MAX));
+                } else {
+                    fun(&format!("{}: Integer in {}..={}, e.g. 42, -0x2a, 0o52, 0b10_1010", stringify!($t), $t::MIN, $t::MAX));
+                }
+            }
+
+            fn get_name() -> &'static str { stringify!($t) }
+
+            fn get_schema(_: &mut SchemaDefinitions) -> SchemaNode {
+                SchemaNode::Integer { minimum: $t::MIN as i128, maximum: $t::MAX as u128 }
+            }
+
+            fn parse_from<F>(provider: &mut ConfigProvider, fun: &mut F) -> Result<Self, ParseError>
+                where F: FnMut(Diagnostic) {
+
+                if let Some(content) = provider.get_next() {
+                    let max = token_len(&content);
+                    let tmp = &content[0 .. max];
+
+                    let ret = match parse_int_literal(tmp) {
+                        Ok((true, x)) if x <= i128::MAX as u128 + 1 => $t::try_from((x as i128).wrapping_neg()).ok(),
+                        Ok((false, x)) => $t::try_from(x).ok(),
+                        Ok(_) => None,
+                        Err(x) => {
+                            number_error(provider, max, tmp, stringify!($t), &x, fun);
+                            return Err(ParseError::Recoverable);
+                        },
+                    };
+
+                    match ret {
+                        Some(ret) => {
+                            provider.consume(max, fun)?;
+                            return Ok(ret);
+                        },
+                        None => {
+                            let reason = format!("number out of range {}..={}", $t::MIN, $t::MAX);
+                            number_error(provider, max, tmp, stringify!($t), &reason, fun);
+                            return Err(ParseError::Recoverable);
+                        },
+                    }
+                }
+
+                fun(provider.error(0, 0, "At end of file :("));
+                return Err(ParseError::Final);
+            }
+
+            fn get_default() -> Result<Self, ()> { Err(()) }
+
+            fn write_to<F>(&self, fun: &mut F)
+                where F: FnMut(&str) {
+                fun(self.to_string().as_str());
+            }
+
+            fn merge(&mut self, rhs: Self) -> Result<(), ()> { if *self == rhs { Ok(()) } else { Err(()) } }
         }
+    };
+}
 
-    fn get_name() -> &'static str { "u32" }
+impl_int!(i8);
+impl_int!(i16);
+impl_int!(i32);
+impl_int!(i64);
+impl_int!(i128);
+impl_int!(isize);
+impl_int!(u8);
+impl_int!(u16);
+impl_int!(u32);
+impl_int!(u64);
+impl_int!(u128);
+impl_int!(usize);
 
-    fn parse_from<F>(provider: &mut ConfigProvider, fun: &mut F) -> Result<Self, ParseError>
-        where F: FnMut(String) {
+macro_rules! impl_non_zero {
+    ($t:ident, $inner:ident) => {
+        impl ConfigAble for $t {
+            fn get_format<F>(_: &mut std::collections::HashSet<String>, fun: &mut F)
+                where F: FnMut(&str) {
+                if $inner::MIN == 0 {
+                    fun(&format!("{}: Integer in 1..={}, e.g. 42", stringify!($t), $inner::MAX));
+                } else {
+                    fun(&format!("{}: Integer in {}..={} except 0, e.g. 42", stringify!($t), $inner::MIN, $inner::MAX));
+                }
+            }
 
-        if let Some(content) = provider.get_next() {
-            /* Fix this, it's majorly wrong */
-            let max = content.find(|c: char| c.is_whitespace()).unwrap_or(content.len());
-            let tmp = &content[0 .. max];
-            match tmp.parse::<u32>() {
-                Ok(ret) => {
-                    provider.consume(max, fun)?;
-                    return Ok(ret);
-                },
-                Err(x) => {
-                    fun(format!("Failed to parse '{}' into an u32: {}", tmp, x));
-                    return Err(ParseError::Recoverable);
+            fn get_name() -> &'static str { stringify!($t) }
+
+            fn get_schema(_: &mut SchemaDefinitions) -> SchemaNode {
+                SchemaNode::Integer { minimum: if $inner::MIN == 0 { 1 } else { $inner::MIN as i128 }, maximum: $inner::MAX as u128 }
+            }
+
+            fn parse_from<F>(provider: &mut ConfigProvider, fun: &mut F) -> Result<Self, ParseError>
+                where F: FnMut(Diagnostic) {
+                let mark = provider.mark(0, provider.get_next().map(|x| token_len(&x)).unwrap_or(0));
+                let val = $inner::parse_from(provider, fun)?;
+
+                match $t::new(val) {
+                    Some(x) => return Ok(x),
+                    None => {
+                        fun(mark.error(format!("{} can't be zero", stringify!($t))).expected("non-zero integer").found("0"));
+                        return Err(ParseError::Recoverable);
+                    },
                 }
             }
+
+            fn get_default() -> Result<Self, ()> { Err(()) }
+
+            fn write_to<F>(&self, fun: &mut F)
+                where F: FnMut(&str) {
+                fun(self.to_string().as_str());
+            }
+
+            fn merge(&mut self, rhs: Self) -> Result<(), ()> { if *self == rhs { Ok(()) } else { Err(()) } }
         }
+    };
+}
 
-        fun("At end of file :(".to_string());
-        return Err(ParseError::Final);
-    }
+impl_non_zero!(NonZeroI8, i8);
+impl_non_zero!(NonZeroI16, i16);
+impl_non_zero!(NonZeroI32, i32);
+impl_non_zero!(NonZeroI64, i64);
+impl_non_zero!(NonZeroI128, i128);
+impl_non_zero!(NonZeroIsize, isize);
+impl_non_zero!(NonZeroU8, u8);
+impl_non_zero!(NonZeroU16, u16);
+impl_non_zero!(NonZeroU32, u32);
+impl_non_zero!(NonZeroU64, u64);
+impl_non_zero!(NonZeroU128, u128);
+impl_non_zero!(NonZeroUsize, usize);
 
-    fn get_default() -> Result<Self, ()> { Err(()) }
+macro_rules! impl_float {
+    ($t:ident) => {
+        impl ConfigAble for $t {
+            fn get_format<F>(_: &mut std::collections::HashSet<String>, fun: &mut F)
+                where F: FnMut(&str) {
+                fun(concat!(stringify!($t), ": Number, e.g. 1.5, -2e10, 1_000.0, inf, -inf or nan"));
+            }
 
-    fn merge(&mut self, rhs: Self) -> Result<(), ()> { if *self == rhs { Ok(()) } else { Err(()) } }
+            fn get_name() -> &'static str { stringify!($t) }
+
+            fn get_schema(_: &mut SchemaDefinitions) -> SchemaNode {
+                SchemaNode::Number
+            }
+
+            fn parse_from<F>(provider: &mut ConfigProvider, fun: &mut F) -> Result<Self, ParseError>
+                where F: FnMut(Diagnostic) {
+
+                if let Some(content) = provider.get_next() {
+                    let max = token_len(&content);
+                    let tmp = &content[0 .. max];
+
+                    match parse_float_literal::<$t>(tmp) {
+                        Ok(ret) => {
+                            provider.consume(max, fun)?;
+                            return Ok(ret);
+                        },
+                        Err(x) => {
+                            number_error(provider, max, tmp, stringify!($t), &x, fun);
+                            return Err(ParseError::Recoverable);
+                        },
+                    }
+                }
+
+                fun(provider.error(0, 0, "At end of file :("));
+                return Err(ParseError::Final);
+            }
+
+            fn get_default() -> Result<Self, ()> { Err(()) }
+
+            fn write_to<F>(&self, fun: &mut F)
+                where F: FnMut(&str) {
+                /* Display writes NaN, but only the lower case version is parsed */
+                if self.is_nan() {
+                    fun("nan");
+                } else {
+                    fun(self.to_string().as_str());
+                }
+            }
+
+            fn merge(&mut self, rhs: Self) -> Result<(), ()> { if *self == rhs { Ok(()) } else { Err(()) } }
+        }
+    };
 }
 
+impl_float!(f32);
+impl_float!(f64);
 
-impl ConfigAble for u8 {
-    fn get_format<F>(_: &mut std::collections::HashSet<String>, fun: &mut F) 
+impl ConfigAble for bool {
+    fn get_format<F>(_: &mut std::collections::HashSet<String>, fun: &mut F)
         where F: FnMut(&str) {
-            fun("u8: Digits");
-        }
+        fun("bool: true | false");
+    }
 
-    fn get_name() -> &'static str { "u8" }
+    fn get_name() -> &'static str { "bool" }
+
+    fn get_schema(_: &mut SchemaDefinitions) -> SchemaNode {
+        SchemaNode::Boolean
+    }
 
     fn parse_from<F>(provider: &mut ConfigProvider, fun: &mut F) -> Result<Self, ParseError>
-        where F: FnMut(String) {
+        where F: FnMut(Diagnostic) {
 
         if let Some(content) = provider.get_next() {
-            /* Fix this, it's majorly wrong */
-            let max = content.find(|c: char| c.is_whitespace()).unwrap_or(content.len());
+            let max = token_len(&content);
             let tmp = &content[0 .. max];
-            match tmp.parse::<u8>() {
-                Ok(ret) => {
-                    provider.consume(max, fun)?;
-                    return Ok(ret);
-                },
-                Err(x) => {
-                    fun(format!("Failed to parse '{}' into an i32: {}", tmp, x));
+
+            let ret = match tmp {
+                "true" => true,
+                "false" => false,
+                _ => {
+                    fun(provider.error(0, max, format!("Failed to parse '{}' into bool", tmp)).expected("true | false").found(tmp));
                     return Err(ParseError::Recoverable);
-                }
-            }
+                },
+            };
+
+            provider.consume(max, fun)?;
+            return Ok(ret);
         }
 
-        fun("At end of file :(".to_string());
+        fun(provider.error(0, 0, "At end of file :("));
         return Err(ParseError::Final);
     }
 
     fn get_default() -> Result<Self, ()> { Err(()) }
 
+    fn write_to<F>(&self, fun: &mut F)
+        where F: FnMut(&str) {
+        fun(if *self { "true" } else { "false" });
+    }
+
     fn merge(&mut self, rhs: Self) -> Result<(), ()> { if *self == rhs { Ok(()) } else { Err(()) } }
 }
+
+#[cfg(test)]
+mod test {
+    use ConfigProvider;
+    use Diagnostic;
+    use ConfigAble;
+
+    fn parse<T: ConfigAble>(s: &str) -> (Result<T, ::ParseError>, String) {
+        let mut builder = String::new();
+        let ret = T::parse_from(&mut ConfigProvider::new_from_str(s), &mut |x: Diagnostic| builder.push_str(&x.to_string()));
+        return (ret, builder);
+    }
+
+    #[test]
+    fn test_int_literals() {
+        assert!(parse::<i32>("42").0 == Ok(42));
+        assert!(parse::<i32>("+42").0 == Ok(42));
+        assert!(parse::<i32>("-0x2a").0 == Ok(-42));
+        assert!(parse::<u8>("0o52").0 == Ok(42));
+        assert!(parse::<u16>("0b10_1010").0 == Ok(42));
+        assert!(parse::<u64>("1_000_000").0 == Ok(1_000_000));
+        assert!(parse::<i8>("-128").0 == Ok(-128));
+        assert!(parse::<i128>("-170141183460469231731687303715884105728").0 == Ok(i128::MIN));
+        assert!(parse::<u128>("340282366920938463463374607431768211455").0 == Ok(u128::MAX));
+        assert!(parse::<usize>("7,").0 == Ok(7));
+    }
+
+    #[test]
+    fn test_int_errors() {
+        let (val, err) = parse::<u8>("256");
+        assert!(val.is_err());
+        assert!(err == "error: Failed to parse '256' into u8: number out of range 0..=255\n  --> memory:1,1\n  expected u8, found 256");
+
+        let (val, err) = parse::<u16>("-1");
+        assert!(val.is_err());
+        assert!(err.starts_with("error: Failed to parse '-1' into u16: number out of range 0..=65535"));
+
+        let (val, err) = parse::<i32>("0xfg");
+        assert!(val.is_err());
+        assert!(err.starts_with("error: Failed to parse '0xfg' into i32: invalid digit 'g' for base 16"));
+
+        assert!(parse::<i32>("_1").0.is_err());
+        assert!(parse::<i32>("0x").0.is_err());
+        assert!(parse::<u128>("340282366920938463463374607431768211456").0.is_err());
+    }
+
+    #[test]
+    fn test_float_literals() {
+        assert!(parse::<f64>("1.5").0 == Ok(1.5));
+        assert!(parse::<f64>("-2e3").0 == Ok(-2000.0));
+        assert!(parse::<f32>("1_000.25").0 == Ok(1000.25));
+        assert!(parse::<f64>("-inf").0 == Ok(-f64::INFINITY));
+        assert!(parse::<f64>("nan").0.map(|x| x.is_nan()) == Ok(true));
+
+        assert!(parse::<f64>("infinity").0.is_err());
+        assert!(parse::<f64>("NaN").0.is_err());
+        assert!(parse::<f64>("-nan").0.is_err());
+
+        let (val, err) = parse::<f32>("1e39");
+        assert!(val.is_err());
+        assert!(err.starts_with("error: Failed to parse '1e39' into f32: number out of range"));
+    }
+
+    #[test]
+    fn test_bool() {
+        assert!(parse::<bool>("true").0 == Ok(true));
+        assert!(parse::<bool>("false}").0 == Ok(false));
+        assert!(parse::<bool>("yes").0.is_err());
+    }
+
+    #[test]
+    fn test_number_write() {
+        assert!((-42i64).to_config_string() == "-42");
+        assert!(f64::NAN.to_config_string() == "nan");
+        assert!(f64::NEG_INFINITY.to_config_string() == "-inf");
+
+        let val = vec![1.5f64, -0.25, 1e300];
+        let mut provider = ConfigProvider::new_from_str(val.to_config_string());
+        assert!(Vec::<f64>::parse_from(&mut provider, &mut |_| {}) == Ok(val));
+
+        let val = vec![1u32, 2, 3];
+        let mut provider = ConfigProvider::new_from_str(val.to_config_string());
+        assert!(Vec::<u32>::parse_from(&mut provider, &mut |_| {}) == Ok(val));
+    }
+
+    #[test]
+    fn test_int_format() {
+        assert!(u8::get_format_str() == "u8: Integer in 0..=255, e.g. 42, 0x2a, 0o52, 0b10_1010");
+        assert!(i8::get_format_str() == "i8: Integer in -128..=127, e.g. 42, -0x2a, 0o52, 0b10_1010");
+    }
+
+    #[test]
+    fn test_non_zero() {
+        use std::num::{NonZeroI8, NonZeroU32};
+
+        assert!(parse::<NonZeroU32>("42").0 == Ok(NonZeroU32::new(42).unwrap()));
+        assert!(parse::<NonZeroI8>("-0x80").0 == Ok(NonZeroI8::new(-128).unwrap()));
+
+        let (val, err) = parse::<NonZeroU32>("0x0");
+        assert!(val.is_err());
+        assert!(err.starts_with("error: NonZeroU32 can't be zero"));
+        assert!(NonZeroU32::get_format_str() == "NonZeroU32: Integer in 1..=4294967295, e.g. 42");
+    }
+}
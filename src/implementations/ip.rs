@@ -1,51 +1,478 @@
 use ::provider::ConfigProvider;
 use ::ConfigAble;
 use ParseError;
+use Diagnostic;
+use ::schema::{SchemaDefinitions, SchemaNode};
 use std;
 
-use std::net::Ipv4Addr;
-use std::str::FromStr;
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6};
 
-impl ConfigAble for Ipv4Addr {
-    fn get_format<F>(_: &mut std::collections::HashSet<String>, fun: &mut F) 
-        where F: FnMut(&str) {
-            fun("a.b.c.d");
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+/// An IPv4 network prefix in CIDR notation, e.g. `10.0.0.0/8`.
+///
+/// The address may have host bits set (`10.0.0.1/8`), see network() for the masked address.
+pub struct Ipv4Net {
+    addr: Ipv4Addr,
+    prefix_len: u8,
+}
+
+impl Ipv4Net {
+    /// Create a new prefix. Returns None if `prefix_len` is larger than 32
+    pub fn new(addr: Ipv4Addr, prefix_len: u8) -> Option<Self> {
+        if prefix_len > 32 {
+            return None;
+        }
+
+        return Some(Ipv4Net { addr, prefix_len });
+    }
+
+    /// The address as written
+    pub fn addr(&self) -> Ipv4Addr {
+        return self.addr;
+    }
+
+    /// The number of leading bits that belong to the network
+    pub fn prefix_len(&self) -> u8 {
+        return self.prefix_len;
+    }
+
+    /// The address with all host bits cleared
+    pub fn network(&self) -> Ipv4Addr {
+        let mask = u32::MAX.checked_shl(32 - self.prefix_len as u32).unwrap_or(0);
+        return Ipv4Addr::from(u32::from(self.addr) & mask);
+    }
+
+    /// Check whether `addr` is inside this network
+    pub fn contains(&self, addr: &Ipv4Addr) -> bool {
+        return Ipv4Net { addr: *addr, prefix_len: self.prefix_len }.network() == self.network();
+    }
+}
+
+impl fmt::Display for Ipv4Net {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}/{}", self.addr, self.prefix_len)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+/// An IPv6 network prefix in CIDR notation, e.g. `fd00::/8`.
+///
+/// The address may have host bits set, see network() for the masked address.
+pub struct Ipv6Net {
+    addr: Ipv6Addr,
+    prefix_len: u8,
+}
+
+impl Ipv6Net {
+    /// Create a new prefix. Returns None if `prefix_len` is larger than 128
+    pub fn new(addr: Ipv6Addr, prefix_len: u8) -> Option<Self> {
+        if prefix_len > 128 {
+            return None;
+        }
+
+        return Some(Ipv6Net { addr, prefix_len });
+    }
+
+    /// The address as written
+    pub fn addr(&self) -> Ipv6Addr {
+        return self.addr;
+    }
+
+    /// The number of leading bits that belong to the network
+    pub fn prefix_len(&self) -> u8 {
+        return self.prefix_len;
+    }
+
+    /// The address with all host bits cleared
+    pub fn network(&self) -> Ipv6Addr {
+        let mask = u128::MAX.checked_shl(128 - self.prefix_len as u32).unwrap_or(0);
+        return Ipv6Addr::from(u128::from(self.addr) & mask);
+    }
+
+    /// Check whether `addr` is inside this network
+    pub fn contains(&self, addr: &Ipv6Addr) -> bool {
+        return Ipv6Net { addr: *addr, prefix_len: self.prefix_len }.network() == self.network();
+    }
+}
+
+impl fmt::Display for Ipv6Net {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}/{}", self.addr, self.prefix_len)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+/// An IPv4 or IPv6 network prefix in CIDR notation
+pub enum IpNet {
+    V4(Ipv4Net),
+    V6(Ipv6Net),
+}
+
+impl IpNet {
+    /// Check whether `addr` is inside this network. Addresses of the other family never are
+    pub fn contains(&self, addr: &IpAddr) -> bool {
+        match (*self, *addr) {
+            (IpNet::V4(net), IpAddr::V4(addr)) => net.contains(&addr),
+            (IpNet::V6(net), IpAddr::V6(addr)) => net.contains(&addr),
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for IpNet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            IpNet::V4(ref x) => x.fmt(f),
+            IpNet::V6(ref x) => x.fmt(f),
+        }
+    }
+}
+
+/// A problem inside an address token. The position is relative to the start of the token
+struct AddrError {
+    start: usize,
+    len: usize,
+    message: String,
+}
+
+fn addr_error<S: Into<String>>(start: usize, len: usize, message: S) -> AddrError {
+    return AddrError { start, len: std::cmp::max(len, 1), message: message.into() };
+}
+
+/// Get the length of the address token at the start of `content`.
+///
+/// This takes everything that could be part of an address, so errors can point into it. A single
+/// trailing `:` isn't taken, since it separates a map key from its value.
+fn addr_token_len(content: &str) -> usize {
+    let bytes = content.as_bytes();
+    let mut i = 0;
+
+    let is_addr = |c: u8| c.is_ascii_hexdigit() || c == b'.' || c == b':' || c == b'/';
+    let is_scope = |c: u8| c.is_ascii_alphanumeric() || c == b'_' || c == b'-';
+
+    if bytes.first() == Some(&b'[') {
+        i = content.find(']').map(|x| x + 1).unwrap_or(bytes.len());
+        if bytes.get(i) == Some(&b':') {
+            i += 1;
+            while i < bytes.len() && bytes[i].is_ascii_alphanumeric() {
+                i += 1;
+            }
         }
+        return i;
+    }
 
-    fn get_name() -> &'static str { "Ipv4Addr" }
+    while i < bytes.len() && is_addr(bytes[i]) {
+        i += 1;
+    }
 
-    fn parse_from<F>(provider: &mut ConfigProvider, fun: &mut F) -> Result<Self, ParseError>
-        where F: FnMut(String) {
+    if bytes.get(i) == Some(&b'%') {
+        i += 1;
+        while i < bytes.len() && is_scope(bytes[i]) {
+            i += 1;
+        }
+    }
 
-        if let Some(content) = provider.get_next() {
-            let parse_str: String = content.chars().take_while(|x| x.is_digit(10) || *x == '.').collect();
-            provider.consume(parse_str.len(), fun)?;
+    if i > 1 && bytes[i - 1] == b':' && bytes[i - 2] != b':' {
+        i -= 1;
+    }
+
+    return i;
+}
+
+/// Parse a dotted IPv4 address, reporting the first invalid octet
+fn parse_v4(s: &str, offset: usize) -> Result<Ipv4Addr, AddrError> {
+    let parts: Vec<&str> = s.split('.').collect();
+    if parts.len() != 4 {
+        return Err(addr_error(offset, s.len(), format!("Expected 4 octets in IPv4 address '{}', found {}", s, parts.len())));
+    }
+
+    let mut octets = [0u8; 4];
+    let mut pos = offset;
+    for (i, part) in parts.iter().enumerate() {
+        let err = |msg: &str| addr_error(pos, part.len(), format!("Octet {} of IPv4 address '{}' {}", i + 1, s, msg));
 
-            return match Ipv4Addr::from_str(parse_str.as_str()) {
-                Ok(x) => Ok(x),
-                Err(_) => Err(ParseError::Recoverable),
+        if part.is_empty() {
+            return Err(err("is empty"));
+        }
+        if !part.bytes().all(|c| c.is_ascii_digit()) {
+            return Err(err(&format!("('{}') isn't a decimal number", part)));
+        }
+        if part.len() > 1 && part.starts_with('0') {
+            return Err(err(&format!("('{}') has a leading zero", part)));
+        }
+
+        octets[i] = match part.parse() {
+            Ok(x) => x,
+            Err(_) => { return Err(err(&format!("('{}') is larger than 255", part))); },
+        };
+        pos += part.len() + 1;
+    }
+
+    return Ok(Ipv4Addr::from(octets));
+}
+
+/// Parse the `:` separated hex groups of one side of an IPv6 `::`.
+///
+/// `number` is the (1-based) number of the first group, for error messages. If `last` is set, the
+/// final group may be an embedded IPv4 address.
+fn parse_v6_groups(s: &str, addr: &str, offset: usize, number: usize, last: bool) -> Result<Vec<u16>, AddrError> {
+    let mut ret = Vec::new();
+    if s.is_empty() {
+        return Ok(ret);
+    }
+
+    let parts: Vec<&str> = s.split(':').collect();
+    let mut pos = offset;
+    for (i, part) in parts.iter().enumerate() {
+        let err = |msg: &str| addr_error(pos, part.len(), format!("Group {} of IPv6 address '{}' {}", number + ret.len(), addr, msg));
+
+        if part.contains('.') {
+            if !last || i + 1 != parts.len() {
+                return Err(err("can only be an IPv4 address at the end"));
             }
+            let v4 = parse_v4(part, pos)?.octets();
+            ret.push(u16::from_be_bytes([v4[0], v4[1]]));
+            ret.push(u16::from_be_bytes([v4[2], v4[3]]));
+            continue;
         }
 
-        fun("At end of file :(".to_string());
-        return Err(ParseError::Final);
+        if part.is_empty() {
+            return Err(err("is empty"));
+        }
+        if !part.bytes().all(|c| c.is_ascii_hexdigit()) {
+            return Err(err(&format!("('{}') isn't a hex number", part)));
+        }
+        if part.len() > 4 {
+            return Err(err(&format!("('{}') has more than 4 hex digits", part)));
+        }
+
+        ret.push(u16::from_str_radix(part, 16).unwrap());
+        pos += part.len() + 1;
+    }
+
+    return Ok(ret);
+}
+
+/// Parse an IPv6 address, reporting the first invalid group
+fn parse_v6(s: &str, offset: usize) -> Result<Ipv6Addr, AddrError> {
+    if let Some(i) = s.find('%') {
+        return Err(addr_error(offset + i, s.len() - i, "A scope ID is only allowed in a socket address, e.g. [fe80::1%2]:80"));
     }
 
-    fn get_default() -> Result<Self, ()> { Err(()) }
+    let mut groups = [0u16; 8];
+    match s.find("::") {
+        Some(i) => {
+            let tail = &s[i + 2..];
+            if let Some(j) = tail.find("::") {
+                return Err(addr_error(offset + i + 2 + j, 2, format!("IPv6 address '{}' can only contain one '::'", s)));
+            }
+
+            let head = parse_v6_groups(&s[..i], s, offset, 1, false)?;
+            let tail = parse_v6_groups(tail, s, offset + i + 2, head.len() + 1, true)?;
+            if head.len() + tail.len() > 7 {
+                return Err(addr_error(offset, s.len(), format!("IPv6 address '{}' has {} groups, there's no room for '::'", s, head.len() + tail.len())));
+            }
 
-    fn merge(&mut self, rhs: Self) -> Result<(), ()> { if *self == rhs { Ok(()) } else { Err(()) } }
+            groups[..head.len()].copy_from_slice(&head);
+            groups[8 - tail.len()..].copy_from_slice(&tail);
+        },
+        None => {
+            let found = parse_v6_groups(s, s, offset, 1, true)?;
+            if found.len() != 8 {
+                return Err(addr_error(offset, s.len(), format!("Expected 8 groups in IPv6 address '{}', found {}", s, found.len())));
+            }
+            groups.copy_from_slice(&found);
+        },
+    }
+
+    return Ok(Ipv6Addr::from(groups));
 }
 
+fn parse_ip(s: &str, offset: usize) -> Result<IpAddr, AddrError> {
+    if s.contains(':') {
+        return parse_v6(s, offset).map(IpAddr::V6);
+    }
+
+    return parse_v4(s, offset).map(IpAddr::V4);
+}
+
+fn parse_port(s: &str, offset: usize) -> Result<u16, AddrError> {
+    if s.is_empty() || !s.bytes().all(|c| c.is_ascii_digit()) {
+        return Err(addr_error(offset, s.len(), format!("Port '{}' isn't a decimal number", s)));
+    }
+
+    return s.parse().map_err(|_| addr_error(offset, s.len(), format!("Port {} is larger than 65535", s)));
+}
+
+fn parse_socket_v4(s: &str, offset: usize) -> Result<SocketAddrV4, AddrError> {
+    let i = match s.rfind(':') {
+        Some(x) => x,
+        None => { return Err(addr_error(offset + s.len(), 1, "Missing port, expected a.b.c.d:port")); },
+    };
+
+    return Ok(SocketAddrV4::new(parse_v4(&s[..i], offset)?, parse_port(&s[i + 1..], offset + i + 1)?));
+}
+
+fn parse_socket_v6(s: &str, offset: usize) -> Result<SocketAddrV6, AddrError> {
+    if !s.starts_with('[') {
+        return Err(addr_error(offset, 1, "IPv6 socket addresses need brackets, e.g. [::1]:80"));
+    }
+    let end = match s.find(']') {
+        Some(x) => x,
+        None => { return Err(addr_error(offset + s.len(), 1, "Missing ']' after the IPv6 address")); },
+    };
+    if s.as_bytes().get(end + 1) != Some(&b':') {
+        return Err(addr_error(offset + end + 1, 1, "Missing port, expected [addr]:port"));
+    }
+
+    let inner = &s[1..end];
+    let (addr, scope) = match inner.find('%') {
+        Some(i) => {
+            let scope = &inner[i + 1..];
+            match scope.parse::<u32>() {
+                Ok(x) => (&inner[..i], x),
+                Err(_) => {
+                    return Err(addr_error(offset + 2 + i, scope.len(), format!("Scope ID '{}' must be a numeric interface index", scope)));
+                },
+            }
+        },
+        None => (inner, 0),
+    };
+
+    let addr = parse_v6(addr, offset + 1)?;
+    let port = parse_port(&s[end + 2..], offset + end + 2)?;
+    return Ok(SocketAddrV6::new(addr, port, 0, scope));
+}
+
+fn parse_socket(s: &str, offset: usize) -> Result<SocketAddr, AddrError> {
+    if s.starts_with('[') {
+        return parse_socket_v6(s, offset).map(SocketAddr::V6);
+    }
+
+    return parse_socket_v4(s, offset).map(SocketAddr::V4);
+}
+
+/// Split `addr/len` and parse the prefix length
+fn parse_prefix_len(s: &str, offset: usize, max: u8, example: &str) -> Result<(usize, u8), AddrError> {
+    let i = match s.find('/') {
+        Some(x) => x,
+        None => { return Err(addr_error(offset + s.len(), 1, format!("Missing prefix length, expected {}", example))); },
+    };
+
+    let len = &s[i + 1..];
+    match len.parse::<u8>() {
+        Ok(x) if x <= max => { return Ok((i, x)); },
+        _ => {
+            return Err(addr_error(offset + i + 1, len.len(), format!("Prefix length '{}' isn't a number from 0 to {}", len, max)));
+        },
+    }
+}
+
+fn parse_net_v4(s: &str, offset: usize) -> Result<Ipv4Net, AddrError> {
+    let (i, len) = parse_prefix_len(s, offset, 32, "a.b.c.d/len")?;
+    return Ok(Ipv4Net { addr: parse_v4(&s[..i], offset)?, prefix_len: len });
+}
+
+fn parse_net_v6(s: &str, offset: usize) -> Result<Ipv6Net, AddrError> {
+    let (i, len) = parse_prefix_len(s, offset, 128, "x:x::x/len")?;
+    return Ok(Ipv6Net { addr: parse_v6(&s[..i], offset)?, prefix_len: len });
+}
+
+fn parse_net(s: &str, offset: usize) -> Result<IpNet, AddrError> {
+    if s.contains(':') {
+        return parse_net_v6(s, offset).map(IpNet::V6);
+    }
+
+    return parse_net_v4(s, offset).map(IpNet::V4);
+}
+
+/// Parse an address token with `parse` and report problems at the exact location
+fn parse_addr<T, F>(provider: &mut ConfigProvider, fun: &mut F, name: &str, format: &str, parse: fn(&str, usize) -> Result<T, AddrError>) -> Result<T, ParseError>
+    where F: FnMut(Diagnostic) {
+    if let Some(content) = provider.get_next() {
+        let len = addr_token_len(&content);
+        let token = &content[..len];
+
+        let ret = parse(token, 0);
+        if let Err(ref x) = ret {
+            let diag = provider.error(x.start, x.len, format!("Failed to parse {}: {}", name, x.message));
+            fun(diag.expected(format).found(token));
+        }
+
+        provider.consume(len, fun)?;
+        return ret.map_err(|_| ParseError::Recoverable);
+    }
+
+    fun(provider.error(0, 0, "At end of file :("));
+    return Err(ParseError::Final);
+}
+
+macro_rules! impl_addr {
+    ($t:ident, $format:expr, $example:expr, $schema:expr, $parse:ident) => {
+        impl ConfigAble for $t {
+            fn get_format<F>(_: &mut std::collections::HashSet<String>, fun: &mut F)
+                where F: FnMut(&str) {
+                fun(concat!(stringify!($t), ": ", $format, ", e.g. ", $example));
+            }
+
+            fn get_name() -> &'static str { stringify!($t) }
+
+            fn get_schema(_: &mut SchemaDefinitions) -> SchemaNode {
+                let format: Option<&str> = $schema;
+                SchemaNode::String { format: format.map(|x| x.to_string()), min_length: None, max_length: None }
+            }
+
+            fn parse_from<F>(provider: &mut ConfigProvider, fun: &mut F) -> Result<Self, ParseError>
+                where F: FnMut(Diagnostic) {
+                parse_addr(provider, fun, stringify!($t), $format, $parse)
+            }
+
+            fn get_default() -> Result<Self, ()> { Err(()) }
+
+            fn write_to<F>(&self, fun: &mut F)
+                where F: FnMut(&str) {
+                fun(self.to_string().as_str());
+            }
+
+            fn merge(&mut self, rhs: Self) -> Result<(), ()> { if *self == rhs { Ok(()) } else { Err(()) } }
+        }
+    };
+}
+
+impl_addr!(Ipv4Addr, "a.b.c.d", "192.168.0.1", Some("ipv4"), parse_v4);
+impl_addr!(Ipv6Addr, "x:x:x:x:x:x:x:x", "fe80::1 or ::ffff:10.0.0.1", Some("ipv6"), parse_v6);
+impl_addr!(IpAddr, "a.b.c.d | x:x:x:x:x:x:x:x", "10.0.0.1 or ::1", None, parse_ip);
+impl_addr!(SocketAddrV4, "a.b.c.d:port", "127.0.0.1:8080", None, parse_socket_v4);
+impl_addr!(SocketAddrV6, "[x:x:x:x:x:x:x:x%scope]:port", "[::1]:8080 or [fe80::1%2]:80", None, parse_socket_v6);
+impl_addr!(SocketAddr, "a.b.c.d:port | [x:x:x:x:x:x:x:x%scope]:port", "0.0.0.0:80 or [::]:80", None, parse_socket);
+impl_addr!(Ipv4Net, "a.b.c.d/len", "10.0.0.0/8", None, parse_net_v4);
+impl_addr!(Ipv6Net, "x:x:x:x:x:x:x:x/len", "fd00::/8", None, parse_net_v6);
+impl_addr!(IpNet, "a.b.c.d/len | x:x:x:x:x:x:x:x/len", "192.168.0.0/16 or fd00::/8", None, parse_net);
+
 #[cfg(test)]
 mod test {
     use ConfigProvider;
+    use Diagnostic;
     use ConfigAble;
-    use std::net::Ipv4Addr;
+    use implementations::ip::{IpNet, Ipv4Net, Ipv6Net};
+    use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6};
+
+    fn parse<T: ConfigAble>(s: &str) -> (Result<T, ::ParseError>, Vec<Diagnostic>) {
+        let mut errors = Vec::new();
+        let ret = T::parse_from(&mut ConfigProvider::new_from_str(s), &mut |x| errors.push(x));
+        return (ret, errors);
+    }
+
+    fn round_trip<T: ConfigAble + PartialEq + std::fmt::Debug>(s: &str) {
+        let val = parse::<T>(s).0.unwrap();
+        assert_eq!(val.to_config_string(), s);
+    }
 
     #[test]
     fn test_ip_parse() {
         let mut builder = String::new();
-        let mut fun = |x: String| builder.push_str(x.as_str());
+        let mut fun = |x: Diagnostic| builder.push_str(&x.to_string());
         let mut provider = ConfigProvider::new_from_str("127.0.0.1var");
 
         let val = Ipv4Addr::parse_from(&mut provider, &mut fun);
@@ -55,4 +482,96 @@ mod test {
         assert!(nxt == Some("var".to_string()));
     }
 
+    #[test]
+    fn test_ip_write() {
+        let val = Ipv4Addr::new(10, 0, 255, 1);
+        assert!(val.to_config_string() == "10.0.255.1");
+
+        let mut provider = ConfigProvider::new_from_str(val.to_config_string());
+        assert!(Ipv4Addr::parse_from(&mut provider, &mut |_| {}) == Ok(val));
+    }
+
+    #[test]
+    fn test_ip_octet_errors() {
+        let (val, errors) = parse::<Ipv4Addr>("10.0.256.1");
+        assert!(val.is_err());
+        assert!(errors[0].message == "Failed to parse Ipv4Addr: Octet 3 of IPv4 address '10.0.256.1' ('256') is larger than 255");
+        assert!(errors[0].span.as_ref().map(|x| (x.start_column, x.end_column)) == Some((6, 9)));
+
+        let (_, errors) = parse::<Ipv4Addr>("10.0.1");
+        assert!(errors[0].message == "Failed to parse Ipv4Addr: Expected 4 octets in IPv4 address '10.0.1', found 3");
+
+        let (_, errors) = parse::<Ipv4Addr>("10.01.1.1");
+        assert!(errors[0].message.ends_with("Octet 2 of IPv4 address '10.01.1.1' ('01') has a leading zero"));
+    }
+
+    #[test]
+    fn test_ipv6() {
+        assert!(parse::<Ipv6Addr>("::1").0 == Ok(Ipv6Addr::LOCALHOST));
+        assert!(parse::<Ipv6Addr>("fe80::1:2").0 == Ok(Ipv6Addr::new(0xfe80, 0, 0, 0, 0, 0, 1, 2)));
+        assert!(parse::<Ipv6Addr>("::ffff:10.0.0.1").0 == Ok(Ipv4Addr::new(10, 0, 0, 1).to_ipv6_mapped()));
+        assert!(parse::<Ipv6Addr>("1:2:3:4:5:6:7:8").0 == Ok(Ipv6Addr::new(1, 2, 3, 4, 5, 6, 7, 8)));
+        round_trip::<Ipv6Addr>("fe80::1");
+
+        let (_, errors) = parse::<Ipv6Addr>("fe80::12345");
+        assert!(errors[0].message == "Failed to parse Ipv6Addr: Group 2 of IPv6 address 'fe80::12345' ('12345') has more than 4 hex digits");
+        assert!(errors[0].span.as_ref().map(|x| (x.start_column, x.end_column)) == Some((7, 12)));
+
+        let (_, errors) = parse::<Ipv6Addr>("1:2:3");
+        assert!(errors[0].message.ends_with("Expected 8 groups in IPv6 address '1:2:3', found 3"));
+
+        let (_, errors) = parse::<Ipv6Addr>("1::2::3");
+        assert!(errors[0].message.ends_with("IPv6 address '1::2::3' can only contain one '::'"));
+    }
+
+    #[test]
+    fn test_ip_addr() {
+        assert!(parse::<IpAddr>("10.0.0.1").0 == Ok(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1))));
+        assert!(parse::<IpAddr>("::").0 == Ok(IpAddr::V6(Ipv6Addr::UNSPECIFIED)));
+    }
+
+    #[test]
+    fn test_socket_addr() {
+        assert!(parse::<SocketAddrV4>("127.0.0.1:8080").0 == Ok(SocketAddrV4::new(Ipv4Addr::LOCALHOST, 8080)));
+        assert!(parse::<SocketAddrV6>("[fe80::1%2]:80").0 == Ok(SocketAddrV6::new(Ipv6Addr::new(0xfe80, 0, 0, 0, 0, 0, 0, 1), 80, 0, 2)));
+        assert!(parse::<SocketAddr>("[::1]:443").0 == Ok(SocketAddr::new(IpAddr::V6(Ipv6Addr::LOCALHOST), 443)));
+        round_trip::<SocketAddr>("0.0.0.0:80");
+        round_trip::<SocketAddrV6>("[fe80::1%2]:80");
+
+        let (_, errors) = parse::<SocketAddrV4>("127.0.0.1:70000");
+        assert!(errors[0].message == "Failed to parse SocketAddrV4: Port 70000 is larger than 65535");
+        assert!(errors[0].span.as_ref().map(|x| x.start_column) == Some(11));
+
+        let (_, errors) = parse::<SocketAddrV6>("[fe80::1%eth0]:80");
+        assert!(errors[0].message.ends_with("Scope ID 'eth0' must be a numeric interface index"));
+
+        let (_, errors) = parse::<SocketAddr>("[::1]");
+        assert!(errors[0].message.ends_with("Missing port, expected [addr]:port"));
+    }
+
+    #[test]
+    fn test_ip_net() {
+        let net = parse::<Ipv4Net>("10.1.2.3/8").0.unwrap();
+        assert!(net.network() == Ipv4Addr::new(10, 0, 0, 0));
+        assert!(net.contains(&Ipv4Addr::new(10, 255, 0, 1)));
+        assert!(!net.contains(&Ipv4Addr::new(11, 0, 0, 1)));
+        round_trip::<Ipv4Net>("10.1.2.3/8");
+        round_trip::<Ipv6Net>("fd00::/8");
+
+        let net = parse::<IpNet>("fd00::/8").0.unwrap();
+        assert!(net.contains(&IpAddr::V6(Ipv6Addr::new(0xfd12, 0, 0, 0, 0, 0, 0, 1))));
+        assert!(parse::<IpNet>("0.0.0.0/0").0.map(|x| x.contains(&IpAddr::V4(Ipv4Addr::new(1, 2, 3, 4)))) == Ok(true));
+
+        let (_, errors) = parse::<Ipv4Net>("10.0.0.0/33");
+        assert!(errors[0].message == "Failed to parse Ipv4Net: Prefix length '33' isn't a number from 0 to 32");
+
+        let (_, errors) = parse::<Ipv4Net>("10.0.0.0");
+        assert!(errors[0].message.ends_with("Missing prefix length, expected a.b.c.d/len"));
+    }
+
+    #[test]
+    fn test_addr_in_list() {
+        let val = parse::<Vec<SocketAddr>>("[[::1]:80, 10.0.0.1:22]").0.unwrap();
+        assert!(val.to_config_string() == "[[::1]:80, 10.0.0.1:22]");
+    }
 }
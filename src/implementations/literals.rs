@@ -1,4 +1,6 @@
 use std;
+use ::provider::ConfigProvider;
+use Diagnostic;
 
 
     // TODO: Bother with the license
@@ -15,7 +17,7 @@ fn char_lit(lit: &str) -> (char, usize) {
         // If the first byte isn't '\\' it might part of a multi-byte char, so
         // get the char with chars().
         let c = lit.chars().next().unwrap();
-        return (c, 1);
+        return (c, c.len_utf8());
     }
 
     // Handle escaped chars.
@@ -37,12 +39,52 @@ fn char_lit(lit: &str) -> (char, usize) {
             let idx = lit.find('}').unwrap();
             let v = u32::from_str_radix(&lit[3..idx], 16).unwrap();
             let c = char::from_u32(v).unwrap();
-            (c, (idx + 1) as usize)
+            (c, idx + 1)
         }
         _ => panic!("lexer should have rejected a bad character escape {}", lit)
     }
 }
 
+/// Escape a single character, so char_lit returns it again.
+/// # Arguments
+/// * `c`: The character to escape
+/// * `quote`: The quote character of the surrounding literal, which has to be escaped as well
+fn escape_into(c: char, quote: char, out: &mut String) {
+    match c {
+        '\\' => out.push_str("\\\\"),
+        '\n' => out.push_str("\\n"),
+        '\r' => out.push_str("\\r"),
+        '\t' => out.push_str("\\t"),
+        '\0' => out.push_str("\\0"),
+        c if c == quote => {
+            out.push('\\');
+            out.push(c);
+        },
+        c if c.is_control() => out.push_str(&format!("\\u{{{:x}}}", c as u32)),
+        c => out.push(c),
+    }
+}
+
+/// Write a char literal that parse_char will parse into `c`
+pub fn write_char(c: char) -> String {
+    let mut ret = String::with_capacity(3);
+    ret.push('\'');
+    escape_into(c, '\'', &mut ret);
+    ret.push('\'');
+    return ret;
+}
+
+/// Write a string literal that str_lit will parse into `s`
+pub fn write_str(s: &str) -> String {
+    let mut ret = String::with_capacity(s.len() + 2);
+    ret.push('"');
+    for c in s.chars() {
+        escape_into(c, '"', &mut ret);
+    }
+    ret.push('"');
+    return ret;
+}
+
 pub fn parse_char(lit: &str) -> Result<(usize, char), String> {
     if lit.as_bytes()[0] != b'\'' {
         return Err("Expected \"'\" at beginning of char literal".to_string());
@@ -69,13 +111,8 @@ pub fn str_lit(lit: &str) -> Result<(usize, String), String> {
 
     /// Eat everything up to a non-whitespace
     fn eat<'a>(it: &mut std::iter::Peekable<std::str::CharIndices<'a>>) {
-        loop {
-            match it.peek().map(|x| x.1) {
-                Some(' ') | Some('\n') | Some('\r') | Some('\t') => {
-                    it.next();
-                },
-                _ => { break; }
-            }
+        while let Some(' ') | Some('\n') | Some('\r') | Some('\t') = it.peek().map(|x| x.1) {
+            it.next();
         }
     }
 
@@ -128,7 +165,7 @@ pub fn str_lit(lit: &str) -> Result<(usize, String), String> {
             },
             '"' => {
                 /* We found the closing '"' */
-                count = Some(i as usize);
+                count = Some(i);
                 break;
             }
             c => res.push(c),
@@ -144,3 +181,156 @@ pub fn str_lit(lit: &str) -> Result<(usize, String), String> {
         None => return Err("Didn't find string closing '\"' for string literal".to_string()),
     }
 }
+
+/// An error while replacing variables in a string literal: the offset and length of the
+/// offending text in the literal, and the message
+type InterpolationError = (usize, usize, String);
+
+/// Look up the replacement for the inside of `${...}`, which is `VAR` or `VAR:-default`.
+///
+/// Like in the shell, the default is used when the variable is unset or empty. The default is
+/// unescaped like the rest of the literal
+fn lookup_var(inner: &str) -> Result<String, String> {
+    let (name, default) = match inner.find(":-") {
+        Some(i) => (&inner[..i], Some(&inner[i + 2..])),
+        None => (inner, None),
+    };
+
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        return Err(format!("Invalid variable name '{}' in ${{{}}}", name, inner));
+    }
+
+    match (std::env::var(name), default) {
+        (Ok(ref x), _) if !x.is_empty() => return Ok(x.clone()),
+        (Err(std::env::VarError::NotUnicode(_)), _) => return Err(format!("Environment variable {} isn't valid unicode", name)),
+        (_, Some(x)) => return str_lit(&format!("\"{}\"", x)).map(|x| x.1),
+        (Ok(_), None) => return Ok(String::new()),
+        (Err(_), None) => return Err(format!("Environment variable {} isn't set, and there's no default (${{{}:-default}})", name, name)),
+    }
+}
+
+/// Find the `}` that closes a `${`, skipping over escapes (`\u{...}` contains a `}` as well)
+fn find_closing_brace(raw: &str) -> Option<usize> {
+    let mut chars = raw.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' => {
+                if let Some((_, 'u')) = chars.next() {
+                    chars.find(|x| x.1 == '}');
+                }
+            },
+            '}' => return Some(i),
+            _ => {},
+        }
+    }
+
+    return None;
+}
+
+/// Parse a string literal like str_lit, and replace `${VAR}` and `${VAR:-default}` with the value
+/// of the environment variable `VAR`. `$${` is kept as `${`.
+///
+/// Returns the number of characters consumed, and the interpolated string or the location of the
+/// variable that couldn't be replaced. The outer error is the same as for str_lit
+fn str_lit_env(lit: &str) -> Result<(usize, Result<String, InterpolationError>), String> {
+    let (count, _) = str_lit(lit)?;
+    let raw = &lit[1 .. count - 1];
+
+    let mut res = String::with_capacity(raw.len());
+    /* The escaped text since the last variable. It's unescaped as a whole, escapes can't be split */
+    let mut text = String::new();
+    let mut pos = 0;
+
+    fn flush(text: &mut String, res: &mut String) {
+        if !text.is_empty() {
+            /* The text was part of a valid literal, and ${ can't be inside an escape */
+            let (_, val) = str_lit(&format!("\"{}\"", text)).unwrap();
+            res.push_str(&val);
+            text.clear();
+        }
+    }
+
+    while let Some(i) = raw[pos..].find('$').map(|x| x + pos) {
+        text.push_str(&raw[pos..i]);
+
+        if raw[i..].starts_with("$${") {
+            text.push_str("${");
+            pos = i + 3;
+        } else if raw[i..].starts_with("${") {
+            let end = match find_closing_brace(&raw[i + 2..]) {
+                Some(x) => i + 2 + x,
+                None => {
+                    return Ok((count, Err((i + 1, raw.len() - i, "Unterminated ${ in string, expected '}'".to_string()))));
+                },
+            };
+
+            match lookup_var(&raw[i + 2 .. end]) {
+                Ok(x) => {
+                    flush(&mut text, &mut res);
+                    res.push_str(&x);
+                },
+                Err(x) => {
+                    return Ok((count, Err((i + 1, end + 1 - i, x))));
+                },
+            }
+            pos = end + 1;
+        } else {
+            text.push('$');
+            pos = i + 1;
+        }
+    }
+
+    text.push_str(&raw[pos..]);
+    flush(&mut text, &mut res);
+
+    return Ok((count, Ok(res)));
+}
+
+/// Read the string literal at the start of `content`, with interpolation if it's enabled on the
+/// provider (see ConfigProvider::set_interpolation).
+///
+/// Returns the number of characters consumed, and the string or the error for the variable that
+/// couldn't be replaced. The outer error is the same as for str_lit
+pub fn read_str_lit(provider: &ConfigProvider, content: &str) -> Result<(usize, Result<String, Diagnostic>), String> {
+    if !provider.interpolation() {
+        return str_lit(content).map(|(count, x)| (count, Ok(x)));
+    }
+
+    let (count, res) = str_lit_env(content)?;
+    return Ok((count, res.map_err(|(start, len, message)| provider.error(start, len, message).found(&content[start .. start + len]))));
+}
+
+#[cfg(test)]
+mod test {
+    use ConfigProvider;
+    use implementations::literals::read_str_lit;
+
+    fn interpolate(s: &str) -> Result<String, (usize, String)> {
+        let mut provider = ConfigProvider::new_from_str(s);
+        provider.set_interpolation(true);
+
+        let (_, res) = read_str_lit(&provider, s).unwrap();
+        return res.map_err(|x| (x.span.unwrap().start_column, x.message));
+    }
+
+    #[test]
+    fn test_interpolation() {
+        std::env::set_var("RS_CONFIG_TEST_HOST", "example.org");
+        std::env::set_var("RS_CONFIG_TEST_EMPTY", "");
+
+        assert!(interpolate("\"http://${RS_CONFIG_TEST_HOST}/\\n\"") == Ok("http://example.org/\n".to_string()));
+        assert!(interpolate("\"${RS_CONFIG_TEST_UNSET:-local\\\\host}:${RS_CONFIG_TEST_EMPTY:-80}\"") == Ok("local\\host:80".to_string()));
+        assert!(interpolate("\"$${RS_CONFIG_TEST_HOST} costs $5\"") == Ok("${RS_CONFIG_TEST_HOST} costs $5".to_string()));
+        assert!(interpolate("\"\\t${RS_CONFIG_TEST_EMPTY}\\\"\"") == Ok("\t\"".to_string()));
+
+        let err = interpolate("\"a ${RS_CONFIG_TEST_UNSET} b\"").unwrap_err();
+        assert!(err == (4, "Environment variable RS_CONFIG_TEST_UNSET isn't set, and there's no default (${RS_CONFIG_TEST_UNSET:-default})".to_string()));
+
+        assert!(interpolate("\"${RS_CONFIG_TEST_HOST\"").unwrap_err().1 == "Unterminated ${ in string, expected '}'");
+        assert!(interpolate("\"${}\"").unwrap_err().1 == "Invalid variable name '' in ${}");
+        assert!(interpolate("\"${RS_CONFIG_TEST_UNSET:-\\u{7d}\\\"}\"") == Ok("}\"".to_string()));
+
+        let provider = ConfigProvider::new_from_str("");
+        assert!(read_str_lit(&provider, "\"${RS_CONFIG_TEST_HOST}\"").unwrap().1.unwrap() == "${RS_CONFIG_TEST_HOST}");
+    }
+}
@@ -7,6 +7,8 @@ use std::collections::HashSet;
 use ::ConfigAble;
 use ::provider::ConfigProvider;
 use ParseError;
+use Diagnostic;
+use ::schema::{SchemaDefinitions, SchemaNode, SchemaVariant, VariantFields};
 
 impl ConfigAble for LogLevel {
     fn get_format<F>(_: &mut HashSet<String>, fun: &mut F)
@@ -16,15 +18,31 @@ impl ConfigAble for LogLevel {
 
     fn get_name() -> &'static str { "LogLevel" }
 
+    fn get_schema(_: &mut SchemaDefinitions) -> SchemaNode {
+        let variants = ["Error", "Warn", "Info", "Debug", "Trace"].iter().map(|x| SchemaVariant {
+            name: x.to_string(),
+            description: None,
+            fields: VariantFields::Unit,
+        }).collect();
+
+        SchemaNode::Enum { description: None, variants }
+    }
+
     fn get_default() -> Result<Self, ()> { Ok(LogLevel::Warn) }
 
+    fn write_to<F>(&self, fun: &mut F)
+        where F: FnMut(&str) {
+        fun(format!("{:?}", self).as_str());
+    }
+
     fn parse_from<F>(provider: &mut ConfigProvider, fun: &mut F) -> Result<Self, ParseError>
-           where  F: FnMut(String) {
+           where  F: FnMut(Diagnostic) {
         if let Some(tmp) = provider.get_next() {
 
             let word: String = tmp.chars().take_while(|c| c.is_alphabetic()).collect();
 
             let ret = match word.as_ref() {
+                    "Error" => Ok(LogLevel::Error),
                     "Warn" => Ok(LogLevel::Warn),
                     "Info" => Ok(LogLevel::Info),
                     "Debug" => Ok(LogLevel::Debug),
@@ -39,7 +57,7 @@ impl ConfigAble for LogLevel {
             return ret;
         }
 
-        fun(String::from("At end of file :("));
+        fun(provider.error(0, 0, "At end of file :("));
         Err(ParseError::Final)
    }
 }
@@ -0,0 +1,241 @@
+use std;
+use ::provider::ConfigProvider;
+use ::ConfigAble;
+use ParseError;
+use Diagnostic;
+use ::schema::{SchemaDefinitions, SchemaNode};
+use diff::{diff_entries, Change};
+
+use std::collections::{BTreeMap, HashMap};
+use std::hash::Hash;
+
+/// Parse the entries of a map literal: `{ key: value, ... }`.
+///
+/// `insert` is called for every entry. It merges duplicate keys into the earlier value, and
+/// returns the key if that failed, so it can be reported.
+fn parse_map<K, V, F, I>(provider: &mut ConfigProvider, fun: &mut F, mut insert: I) -> Result<(), ParseError>
+    where K: ConfigAble,
+          V: ConfigAble,
+          F: FnMut(Diagnostic),
+          I: FnMut(K, V) -> Result<(), K> {
+
+    let mut first = true;
+    let mut failed = false;
+
+    provider.consume_char('{', fun)?;
+    loop {
+        if provider.is_at_end() {
+            fun(provider.error(0, 0, "Reached end of file while reading map :("));
+            return Err(ParseError::Final);
+        }
+
+        if provider.peek_char() == Some('}') {
+            provider.consume(1, fun)?;
+            return if failed { Err(ParseError::Recoverable) } else { Ok(()) };
+        }
+
+        if first {
+            first = false;
+        } else {
+            provider.consume_char(',', fun)?;
+        }
+
+        let mark = provider.mark(0, 1);
+        let key = K::parse_from(provider, fun)?;
+        provider.consume_char(':', fun)?;
+
+        match V::parse_from(provider, fun) {
+            Ok(value) => {
+                if let Err(key) = insert(key, value) {
+                    fun(mark.error(format!("Duplicate key {} in map, couldn't merge the values", key.to_config_string())));
+                    failed = true;
+                }
+            },
+            Err(ParseError::Final) => {
+                return Err(ParseError::Final);
+            },
+            Err(ParseError::Recoverable) => {
+                failed = true;
+            },
+        }
+    }
+}
+
+/// Write a map literal. The entries are sorted by their config text, to get a stable output
+fn write_map<'a, K, V, I, F>(it: I, fun: &mut F)
+    where K: ConfigAble + 'a,
+          V: ConfigAble + 'a,
+          I: std::iter::Iterator<Item=(&'a K, &'a V)>,
+          F: FnMut(&str) {
+    let mut entries: Vec<(String, String)> = it.map(|(k, v)| (k.to_config_string(), v.to_config_string())).collect();
+    entries.sort();
+
+    fun("{");
+    for (i, (key, value)) in entries.iter().enumerate() {
+        if i > 0 {
+            fun(", ");
+        }
+        fun(key);
+        fun(": ");
+        fun(value);
+    }
+    fun("}");
+}
+
+macro_rules! impl_map {
+    ($map:ident, $name:expr, $($bound:path),+) => {
+        impl<K, V> ConfigAble for $map<K, V>
+            where K: ConfigAble $(+ $bound)+,
+                  V: ConfigAble {
+            fn get_format<F>(set: &mut std::collections::HashSet<String>, fun: &mut F)
+                where F: FnMut(&str) {
+                fun(format!("{}<{}, {}>: {{ {}: {}, {}: {}, ... }}", $name, K::get_name(), V::get_name(), K::get_name(), V::get_name(), K::get_name(), V::get_name()).as_str());
+
+                let key = K::get_format_key();
+                if !set.contains(&key) {
+                    set.insert(key);
+
+                    fun("\n");
+                    K::get_format(set, fun);
+                }
+
+                let key = V::get_format_key();
+                if !set.contains(&key) {
+                    set.insert(key);
+
+                    fun("\n");
+                    V::get_format(set, fun);
+                }
+            }
+
+            fn get_name() -> &'static str {
+                concat!($name, "<>")
+            }
+
+            fn get_schema(defs: &mut SchemaDefinitions) -> SchemaNode {
+                SchemaNode::Map { key: Box::new(K::get_schema(defs)), value: Box::new(V::get_schema(defs)) }
+            }
+
+            fn parse_from<F>(provider: &mut ConfigProvider, fun: &mut F) -> Result<Self, ParseError>
+                where F: FnMut(Diagnostic) {
+                let mut ret: $map<K, V> = $map::new();
+
+                parse_map(provider, fun, |key: K, value: V| {
+                    if let Some(old) = ret.get_mut(&key) {
+                        return old.merge(value).map_err(|_| key);
+                    }
+
+                    ret.insert(key, value);
+                    return Ok(());
+                })?;
+
+                return Ok(ret);
+            }
+
+            fn get_default() -> Result<Self, ()> { Ok(Self::new()) }
+
+            fn write_to<F>(&self, fun: &mut F)
+                where F: FnMut(&str) {
+                write_map(self.iter(), fun);
+            }
+
+            /// Maps are merged by key. Values for keys in both maps are merged first, the other keys
+            /// are only inserted if that worked (see ConfigAble::merge)
+            fn merge(&mut self, rhs: Self) -> Result<(), ()> {
+                let mut new = Vec::new();
+                for (key, value) in rhs {
+                    match self.get_mut(&key) {
+                        Some(old) => old.merge(value)?,
+                        None => new.push((key, value)),
+                    }
+                }
+
+                self.extend(new);
+                return Ok(());
+            }
+
+            /// Values are compared by key
+            fn diff_at<F>(&self, new: &Self, path: &str, fun: &mut F)
+                where F: FnMut(Change) {
+                diff_entries(self.iter().collect(), new.iter().collect(), path, fun);
+            }
+        }
+    };
+}
+
+impl_map!(HashMap, "HashMap", Eq, Hash);
+impl_map!(BTreeMap, "BTreeMap", Ord);
+
+#[cfg(test)]
+mod test {
+    use ConfigProvider;
+    use Diagnostic;
+    use ConfigAble;
+
+    use std::collections::{BTreeMap, HashMap};
+
+    #[test]
+    fn test_map_parse() {
+        let mut provider = ConfigProvider::new_from_str("{ \"a\": 1, \"b\": 2 }");
+        let val = <HashMap<String, u32> as ConfigAble>::parse_from(&mut provider, &mut |_| {}).unwrap();
+        assert!(val.len() == 2 && val["a"] == 1 && val["b"] == 2);
+        assert!(provider.get_next().is_none());
+
+        let mut provider = ConfigProvider::new_from_str("{1: ['a'], 2: [], 1: ['b']}");
+        let val = <BTreeMap<u8, Vec<char>> as ConfigAble>::parse_from(&mut provider, &mut |_| {}).unwrap();
+        assert!(val.into_iter().collect::<Vec<_>>() == [(1, vec!['a', 'b']), (2, vec![])]);
+    }
+
+    #[test]
+    fn test_map_duplicate() {
+        let mut errors = Vec::new();
+        let mut provider = ConfigProvider::new_from_str("{'a': 1, 'a': 2}");
+        let val = <HashMap<char, u32> as ConfigAble>::parse_from(&mut provider, &mut |x: Diagnostic| errors.push(x));
+
+        assert!(val.is_err());
+        assert!(errors.len() == 1);
+        assert!(errors[0].message == "Duplicate key 'a' in map, couldn't merge the values");
+        assert!(errors[0].span.as_ref().map(|x| x.start_column) == Some(10));
+    }
+
+    #[test]
+    fn test_map_invalid_key() {
+        let mut errors = Vec::new();
+        let mut provider = ConfigProvider::new_from_str("{'a': 1, b: 2}");
+        let val = <HashMap<char, u32> as ConfigAble>::parse_from(&mut provider, &mut |x: Diagnostic| errors.push(x));
+
+        assert!(val.is_err());
+        assert!(errors.len() == 1);
+    }
+
+    #[test]
+    fn test_map_merge() {
+        let mut val: BTreeMap<u8, Option<u8>> = vec![(1, Some(1)), (2, None)].into_iter().collect();
+        assert!(ConfigAble::merge(&mut val, vec![(2, Some(2)), (3, None)].into_iter().collect()).is_ok());
+        assert!(val.clone().into_iter().collect::<Vec<_>>() == [(1, Some(1)), (2, Some(2)), (3, None)]);
+
+        assert!(ConfigAble::merge(&mut val, vec![(1, Some(3)), (4, None)].into_iter().collect()).is_err());
+        assert!(!val.contains_key(&4));
+    }
+
+    #[test]
+    fn test_map_write() {
+        let mut val = HashMap::new();
+        val.insert("b".to_string(), 2u32);
+        val.insert("a".to_string(), 1u32);
+        assert!(val.to_config_string() == "{\"a\": 1, \"b\": 2}");
+
+        let mut provider = ConfigProvider::new_from_str(val.to_config_string());
+        assert!(<HashMap<String, u32> as ConfigAble>::parse_from(&mut provider, &mut |_| {}) == Ok(val));
+    }
+
+    #[test]
+    fn test_map_format() {
+        let format = <BTreeMap<String, Vec<char>> as ConfigAble>::get_format_str();
+        assert!(format.starts_with("BTreeMap<String, Vec<>>: { String: Vec<>, String: Vec<>, ... }\nString: "));
+        assert!(format.contains("\nVec<char>: [ char, char, ... ]\nChar: "));
+
+        let format = <HashMap<(u8, u8), (char, char)> as ConfigAble>::get_format_str();
+        assert!(format.contains("(u8, u8): ") && format.contains("(char, char): "));
+    }
+}
@@ -1,4 +1,4 @@
-mod literals;
+pub(crate) mod literals;
 
 pub mod string;
 pub mod char;
@@ -7,6 +7,12 @@ pub mod option;
 pub mod ints;
 pub mod array;
 pub mod ip;
+pub mod map;
+pub mod set;
+pub mod path;
+pub mod tuple;
+pub mod units;
+pub mod wrappers;
 
 #[cfg(feature="log")]
 pub mod log;
@@ -2,6 +2,10 @@ use std;
 use ::provider::ConfigProvider;
 use ::ConfigAble;
 use ParseError;
+use Diagnostic;
+use ::schema::{SchemaDefinitions, SchemaNode};
+use overrides::{apply_value, OverridePath, OverridePolicy};
+use diff::{diff_value, Change};
 
 impl<T> ConfigAble for Option<T>
     where T: ConfigAble {
@@ -9,10 +13,10 @@ impl<T> ConfigAble for Option<T>
         where F: FnMut(&str) {
         // TODO: Re-do the newline appending
         fun(format!("Option<{}>: Some({}) | None", T::get_name(), T::get_name()).as_str());
-        let key = T::get_name().to_string();
+        let key = T::get_format_key();
 
         if !set.contains(&key) {
-            set.insert(T::get_name().to_string());
+            set.insert(key);
 
             fun("\n");
             T::get_format(set, fun);
@@ -23,8 +27,12 @@ impl<T> ConfigAble for Option<T>
         concat!("Option<", /*T::get_name(),*/ ">")
     }
 
+    fn get_schema(defs: &mut SchemaDefinitions) -> SchemaNode {
+        SchemaNode::Optional(Box::new(T::get_schema(defs)))
+    }
+
     fn parse_from<F>(provider: &mut ConfigProvider, fun: &mut F) -> Result<Self, ParseError>
-        where F: FnMut(String) {
+        where F: FnMut(Diagnostic) {
 
         if let Some(content) = provider.get_next() {
             if content.starts_with("None") {
@@ -42,7 +50,7 @@ impl<T> ConfigAble for Option<T>
 
         }
 
-        fun("At end of file :(".to_string());
+        fun(provider.error(0, 0, "At end of file :("));
         return Err(ParseError::Final);
     }
 
@@ -53,6 +61,18 @@ impl<T> ConfigAble for Option<T>
         }
     }
 
+    fn write_to<F>(&self, fun: &mut F)
+        where F: FnMut(&str) {
+        match *self {
+            Some(ref x) => {
+                fun("Some(");
+                x.write_to(fun);
+                fun(")");
+            },
+            None => fun("None"),
+        }
+    }
+
     fn merge(&mut self, rhs: Self) -> Result<(), ()> {
         match self {
             &mut None => {
@@ -67,11 +87,50 @@ impl<T> ConfigAble for Option<T>
             },
         }
     }
+
+    /// A path into a None value starts from the default of T, if there is one
+    fn apply_override<F>(&mut self, path: &mut OverridePath, value: &str, policy: OverridePolicy, fun: &mut F) -> Result<(), ParseError>
+        where F: FnMut(Diagnostic) {
+        if path.is_empty() {
+            return apply_value(self, path, value, policy, fun);
+        }
+
+        if self.is_none() {
+            match T::get_default() {
+                Ok(x) => *self = Some(x),
+                Err(()) => {
+                    fun(path.error(format!("{} is None, and {} has no default to apply the override to", path.location(), T::get_name())));
+                    return Err(ParseError::Recoverable);
+                },
+            }
+        }
+
+        return self.as_mut().unwrap().apply_override(path, value, policy, fun);
+    }
+
+    /// Two Some values are compared by their content. The content is still reported as Some(..)
+    /// if it changed as a whole
+    fn diff_at<F>(&self, new: &Self, path: &str, fun: &mut F)
+        where F: FnMut(Change) {
+        match (self, new) {
+            (Some(x), Some(y)) => {
+                x.diff_at(y, path, &mut |change: Change| {
+                    if change.path == path {
+                        diff_value(self, new, path, fun);
+                    } else {
+                        fun(change);
+                    }
+                });
+            },
+            _ => diff_value(self, new, path, fun),
+        }
+    }
 }
 
 #[cfg(test)]
 mod test {
     use ConfigProvider;
+    use Diagnostic;
     use ConfigAble;
 
     #[test]
@@ -82,14 +141,24 @@ mod test {
     #[test]
     fn test_option_parse() {
         let mut builder = String::new();
-        let mut fun = |x: String| builder.push_str(x.as_str());
+        let mut fun = |x: Diagnostic| builder.push_str(&x.to_string());
         let mut provider = ConfigProvider::new_from_str("Some(\"TestStr\")");
         assert!(<Option<String> as ConfigAble>::parse_from(&mut provider, &mut fun) == Ok(Some("TestStr".to_string())));
-        assert!(provider.get_next() == None);
+        assert!(provider.get_next().is_none());
 
         let mut provider2 = ConfigProvider::new_from_str("None");
         assert!(<Option<String> as ConfigAble>::parse_from(&mut provider2, &mut fun) == Ok(None));
-        assert!(provider2.get_next() == None);
+        assert!(provider2.get_next().is_none());
+    }
+
+    #[test]
+    fn test_option_write() {
+        assert!(Some("Test".to_string()).to_config_string() == "Some(\"Test\")");
+        assert!(<Option<String> as ConfigAble>::to_config_string(&None) == "None");
+
+        let val = Some(Some('c'));
+        let mut provider = ConfigProvider::new_from_str(val.to_config_string());
+        assert!(<Option<Option<char>> as ConfigAble>::parse_from(&mut provider, &mut |_| {}) == Ok(val));
     }
 
     #[test]
@@ -0,0 +1,225 @@
+use std;
+use ::provider::ConfigProvider;
+use ::ConfigAble;
+use ParseError;
+use Diagnostic;
+use Mark;
+use ::schema::{SchemaDefinitions, SchemaNode};
+
+use std::path::{Component, Path, PathBuf};
+
+use implementations::literals::{read_str_lit, write_str};
+
+/// Check whether the path starts with `~`, which is only resolved by expand_tilde
+fn is_tilde(path: &Path) -> bool {
+    return match path.components().next() {
+        Some(Component::Normal(x)) => x == "~",
+        _ => false,
+    };
+}
+
+impl ConfigAble for PathBuf {
+    fn get_format<F>(_: &mut std::collections::HashSet<String>, fun: &mut F)
+        where F: FnMut(&str) {
+        fun("PathBuf: \"path\", relative to the directory of the config file");
+    }
+
+    fn get_name() -> &'static str { "PathBuf" }
+
+    fn get_schema(_: &mut SchemaDefinitions) -> SchemaNode {
+        SchemaNode::String { format: None, min_length: Some(1), max_length: None }
+    }
+
+    /// Relative paths are resolved against the directory of the file they are written in.
+    /// Paths starting with `~` are kept as they are, see PathChecks::expand_tilde
+    fn parse_from<F>(provider: &mut ConfigProvider, fun: &mut F) -> Result<Self, ParseError>
+        where F: FnMut(Diagnostic) {
+
+        if let Some(content) = provider.get_next() {
+            let (count, path) = match read_str_lit(provider, content.as_str()) {
+                Ok((count, Ok(x))) => (count, x),
+                Ok((count, Err(x))) => {
+                    fun(x);
+                    provider.consume(count, fun)?;
+                    return Err(ParseError::Recoverable);
+                },
+                Err(x) => {
+                    fun(provider.error(0, 1, x).expected("path in string literal"));
+                    return Err(ParseError::Recoverable);
+                },
+            };
+
+            if path.is_empty() {
+                fun(provider.error(0, count, "Path can't be empty").expected("path").found("\"\""));
+                provider.consume(count, fun)?;
+                return Err(ParseError::Recoverable);
+            }
+
+            let path = PathBuf::from(path);
+            let ret = match provider.base_dir() {
+                Some(dir) if provider.path_resolution() && path.is_relative() && !is_tilde(&path) => dir.join(path),
+                _ => path,
+            };
+
+            provider.consume(count, fun)?;
+            return Ok(ret);
+        }
+
+        fun(provider.error(0, 0, "At end of file :("));
+        return Err(ParseError::Final);
+    }
+
+    fn get_default() -> Result<Self, ()> { Err(()) }
+
+    fn write_to<F>(&self, fun: &mut F)
+        where F: FnMut(&str) {
+        fun(write_str(&self.to_string_lossy()).as_str());
+    }
+
+    fn merge(&mut self, rhs: Self) -> Result<(), ()> { if *self == rhs { Ok(()) } else { Err(()) } }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+/// Checks for path fields, set with `#[ConfigAttrs(...)]` on the field
+pub struct PathChecks {
+    /// Replace a leading `~` with the home directory (`$HOME`)
+    pub expand_tilde: bool,
+    /// The path has to exist
+    pub must_exist: bool,
+    /// The path has to be an existing directory
+    pub is_dir: bool,
+    /// The path has to be absolute as written (or after expand_tilde). Relative paths aren't
+    /// resolved against the directory of the config file then
+    pub absolute: bool,
+}
+
+/// Types that contain paths, which PathChecks can be applied to
+pub trait PathValue {
+    /// Call `fun` for every path in the value
+    fn for_each_path<F>(&mut self, fun: &mut F)
+        where F: FnMut(&mut PathBuf);
+}
+
+impl PathValue for PathBuf {
+    fn for_each_path<F>(&mut self, fun: &mut F)
+        where F: FnMut(&mut PathBuf) {
+        fun(self);
+    }
+}
+
+impl<T: PathValue> PathValue for Option<T> {
+    fn for_each_path<F>(&mut self, fun: &mut F)
+        where F: FnMut(&mut PathBuf) {
+        if let Some(ref mut x) = *self {
+            x.for_each_path(fun);
+        }
+    }
+}
+
+impl<T: PathValue> PathValue for Vec<T> {
+    fn for_each_path<F>(&mut self, fun: &mut F)
+        where F: FnMut(&mut PathBuf) {
+        for x in self.iter_mut() {
+            x.for_each_path(fun);
+        }
+    }
+}
+
+/// Apply the checks to every path in `value`. Used by the derived parse_from.
+/// # Arguments
+/// * `value`: The parsed value
+/// * `checks`: The checks from the field attributes
+/// * `mark`: The location of the value, for error reporting
+/// * `fun`: The error reporting function
+pub fn check_paths<T, F>(value: &mut T, checks: &PathChecks, mark: &Mark, fun: &mut F) -> Result<(), ParseError>
+    where T: PathValue,
+          F: FnMut(Diagnostic) {
+    let mut failed = false;
+
+    value.for_each_path(&mut |path: &mut PathBuf| {
+        if checks.expand_tilde && is_tilde(path) {
+            match std::env::var_os("HOME") {
+                Some(home) => {
+                    *path = PathBuf::from(home).join(path.components().skip(1).collect::<PathBuf>());
+                },
+                None => {
+                    fun(mark.error(format!("Couldn't expand {}, HOME isn't set", path.display())));
+                    failed = true;
+                    return;
+                },
+            }
+        }
+
+        let problem = if checks.absolute && !path.is_absolute() {
+            Some("must be absolute")
+        } else if (checks.must_exist || checks.is_dir) && !path.exists() {
+            Some("doesn't exist")
+        } else if checks.is_dir && !path.is_dir() {
+            Some("isn't a directory")
+        } else {
+            None
+        };
+
+        if let Some(x) = problem {
+            fun(mark.error(format!("Path {} {}", path.display(), x)));
+            failed = true;
+        }
+    });
+
+    return if failed { Err(ParseError::Recoverable) } else { Ok(()) };
+}
+
+#[cfg(test)]
+mod test {
+    use ConfigProvider;
+    use Diagnostic;
+    use ConfigAble;
+    use implementations::path::{check_paths, PathChecks};
+
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_path_parse() {
+        let mut provider = ConfigProvider::new_from_str("\"a/b\"");
+        assert!(PathBuf::parse_from(&mut provider, &mut |_| {}) == Ok(PathBuf::from("a/b")));
+
+        let mut provider = ConfigProvider::new_from_str("[\"a/b\", \"/c\", \"~/d\"]");
+        provider.set_base_dir("/etc/app");
+        assert!(Vec::<PathBuf>::parse_from(&mut provider, &mut |_| {}) == Ok(vec![PathBuf::from("/etc/app/a/b"), PathBuf::from("/c"), PathBuf::from("~/d")]));
+
+        let mut provider = ConfigProvider::new_from_str("\"\"");
+        assert!(PathBuf::parse_from(&mut provider, &mut |_| {}).is_err());
+    }
+
+    #[test]
+    fn test_path_write() {
+        let val = PathBuf::from("/tmp/with \"quote\"");
+        assert!(val.to_config_string() == "\"/tmp/with \\\"quote\\\"\"");
+
+        let mut provider = ConfigProvider::new_from_str(val.to_config_string());
+        assert!(PathBuf::parse_from(&mut provider, &mut |_| {}) == Ok(val));
+    }
+
+    #[test]
+    fn test_path_checks() {
+        let provider = ConfigProvider::new_from_str("\"x\"");
+        let mark = provider.mark(0, 3);
+        let dir = std::env::temp_dir();
+
+        let mut errors = Vec::new();
+        let mut val = vec![dir.clone(), dir.join("rs-config-test-does-not-exist"), PathBuf::from("relative")];
+        let checks = PathChecks { is_dir: true, absolute: true, ..PathChecks::default() };
+        assert!(check_paths(&mut val, &checks, &mark, &mut |x: Diagnostic| errors.push(x.message)).is_err());
+
+        assert!(errors.len() == 2);
+        assert!(errors[0].ends_with("rs-config-test-does-not-exist doesn't exist"));
+        assert!(errors[1] == "Path relative must be absolute");
+
+        let mut val = Some(PathBuf::from("~/x"));
+        let checks = PathChecks { expand_tilde: true, ..PathChecks::default() };
+        if let Some(home) = std::env::var_os("HOME") {
+            assert!(check_paths(&mut val, &checks, &mark, &mut |_| {}).is_ok());
+            assert!(val == Some(PathBuf::from(home).join("x")));
+        }
+    }
+}
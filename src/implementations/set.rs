@@ -0,0 +1,162 @@
+use std;
+use ::provider::ConfigProvider;
+use ::ConfigAble;
+use ParseError;
+use Diagnostic;
+use ::schema::{SchemaDefinitions, SchemaNode};
+
+use std::collections::{BTreeSet, HashSet};
+use std::hash::Hash;
+
+/// Parse the elements of a set, in the list syntax: `[ value, ... ]`.
+///
+/// `insert` is called for every element. It gives back duplicates, which are reported.
+fn parse_set<T, F, I>(provider: &mut ConfigProvider, fun: &mut F, mut insert: I) -> Result<(), ParseError>
+    where T: ConfigAble,
+          F: FnMut(Diagnostic),
+          I: FnMut(T) -> Result<(), T> {
+
+    let mut first = true;
+    let mut failed = false;
+
+    provider.consume_char('[', fun)?;
+    loop {
+        if provider.is_at_end() {
+            fun(provider.error(0, 0, "Reached end of file while reading set :("));
+            return Err(ParseError::Final);
+        }
+
+        if provider.peek_char() == Some(']') {
+            provider.consume(1, fun)?;
+            return if failed { Err(ParseError::Recoverable) } else { Ok(()) };
+        }
+
+        if first {
+            first = false;
+        } else {
+            provider.consume_char(',', fun)?;
+        }
+
+        let mark = provider.mark(0, 1);
+        match T::parse_from(provider, fun) {
+            Ok(x) => {
+                if let Err(x) = insert(x) {
+                    fun(mark.error(format!("Duplicate value {} in set", x.to_config_string())));
+                    failed = true;
+                }
+            },
+            Err(ParseError::Recoverable) => {
+                failed = true;
+            },
+            Err(x) => {
+                return Err(x);
+            },
+        }
+    }
+}
+
+/// Write a set in the list syntax. The elements are sorted by their config text, to get a stable
+/// output
+fn write_set<'a, T, I, F>(it: I, fun: &mut F)
+    where T: ConfigAble + 'a,
+          I: std::iter::Iterator<Item=&'a T>,
+          F: FnMut(&str) {
+    let mut elements: Vec<String> = it.map(|x| x.to_config_string()).collect();
+    elements.sort();
+
+    fun("[");
+    for (i, x) in elements.iter().enumerate() {
+        if i > 0 {
+            fun(", ");
+        }
+        fun(x);
+    }
+    fun("]");
+}
+
+macro_rules! impl_set {
+    ($set:ident, $name:expr, $($bound:path),+) => {
+        impl<T> ConfigAble for $set<T>
+            where T: ConfigAble $(+ $bound)+ {
+            fn get_format<F>(set: &mut std::collections::HashSet<String>, fun: &mut F)
+                where F: FnMut(&str) {
+                fun(format!("{}<{}>: [ {}, {}, ... ] without duplicates", $name, T::get_name(), T::get_name(), T::get_name()).as_str());
+
+                let key = T::get_format_key();
+                if !set.contains(&key) {
+                    set.insert(key);
+
+                    fun("\n");
+                    T::get_format(set, fun);
+                }
+            }
+
+            fn get_name() -> &'static str {
+                concat!($name, "<>")
+            }
+
+            fn get_schema(defs: &mut SchemaDefinitions) -> SchemaNode {
+                SchemaNode::Set(Box::new(T::get_schema(defs)))
+            }
+
+            fn parse_from<F>(provider: &mut ConfigProvider, fun: &mut F) -> Result<Self, ParseError>
+                where F: FnMut(Diagnostic) {
+                let mut ret = $set::new();
+                parse_set(provider, fun, |x: T| if ret.contains(&x) { Err(x) } else { ret.insert(x); Ok(()) })?;
+
+                return Ok(ret);
+            }
+
+            fn get_default() -> Result<Self, ()> { Ok(Self::new()) }
+
+            fn write_to<F>(&self, fun: &mut F)
+                where F: FnMut(&str) {
+                write_set(self.iter(), fun);
+            }
+
+            /// Sets are merged into their union
+            fn merge(&mut self, rhs: Self) -> Result<(), ()> {
+                self.extend(rhs);
+                return Ok(());
+            }
+        }
+    };
+}
+
+impl_set!(HashSet, "HashSet", Eq, Hash);
+impl_set!(BTreeSet, "BTreeSet", Ord);
+
+#[cfg(test)]
+mod test {
+    use ConfigProvider;
+    use Diagnostic;
+    use ConfigAble;
+
+    use std::collections::{BTreeSet, HashSet};
+
+    #[test]
+    fn test_set_parse() {
+        let mut provider = ConfigProvider::new_from_str("[3, 1, 2]");
+        let val = <BTreeSet<u8> as ConfigAble>::parse_from(&mut provider, &mut |_| {});
+        assert!(val.map(|x| x.into_iter().collect::<Vec<_>>()) == Ok(vec![1, 2, 3]));
+
+        let mut errors = Vec::new();
+        let mut provider = ConfigProvider::new_from_str("[\"a\", \"b\", \"a\"]");
+        assert!(<HashSet<String> as ConfigAble>::parse_from(&mut provider, &mut |x: Diagnostic| errors.push(x)).is_err());
+        assert!(errors.len() == 1);
+        assert!(errors[0].message == "Duplicate value \"a\" in set");
+        assert!(errors[0].span.as_ref().map(|x| x.start_column) == Some(12));
+    }
+
+    #[test]
+    fn test_set_write_merge() {
+        let mut val: HashSet<char> = ['b', 'a'].iter().cloned().collect();
+        assert!(val.to_config_string() == "['a', 'b']");
+
+        assert!(val.merge(['c', 'a'].iter().cloned().collect()).is_ok());
+        assert!(val.len() == 3);
+
+        let mut provider = ConfigProvider::new_from_str(val.to_config_string());
+        assert!(<HashSet<char> as ConfigAble>::parse_from(&mut provider, &mut |_| {}) == Ok(val));
+    }
+}
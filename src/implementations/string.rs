@@ -2,8 +2,10 @@ use std;
 use ::provider::ConfigProvider;
 use ::ConfigAble;
 use ParseError;
+use Diagnostic;
+use ::schema::{SchemaDefinitions, SchemaNode};
 
-use implementations::literals::str_lit;
+use implementations::literals::{read_str_lit, write_str};
 
 impl ConfigAble for String {
     fn get_format<F>(_: &mut std::collections::HashSet<String>, fun: &mut F) 
@@ -13,29 +15,43 @@ impl ConfigAble for String {
 
     fn get_name() -> &'static str { "String" }
 
+    fn get_schema(_: &mut SchemaDefinitions) -> SchemaNode {
+        SchemaNode::String { format: None, min_length: None, max_length: None }
+    }
+
     fn parse_from<F>(provider: &mut ConfigProvider, fun: &mut F) -> Result<String, ParseError>
-        where F: FnMut(String) {
+        where F: FnMut(Diagnostic) {
 
         if let Some(content) = provider.get_next() {
-            match  str_lit(content.as_str()) {
-                Ok((count, ret)) => {
+            match read_str_lit(provider, content.as_str()) {
+                Ok((count, Ok(ret))) => {
                     provider.consume(count, fun)?;
                     return Ok(ret);
                 },
-                Err(x) => {
+                Ok((count, Err(x))) => {
                     fun(x);
+                    provider.consume(count, fun)?;
+                    return Err(ParseError::Recoverable);
+                },
+                Err(x) => {
+                    fun(provider.error(0, 1, x).expected("string literal"));
                     return Err(ParseError::Recoverable);
                 }
             }
         }
 
 
-        fun("At end of file while parsing String :(".to_string());
+        fun(provider.error(0, 0, "At end of file while parsing String :("));
         return Err(ParseError::Final);
     }
 
     fn get_default() -> Result<Self, ()> { Err(()) }
 
+    fn write_to<F>(&self, fun: &mut F)
+        where F: FnMut(&str) {
+        fun(write_str(self).as_str());
+    }
+
     fn merge(&mut self, rhs: Self) -> Result<(), ()> { 
         self.push_str(rhs.as_str());
         return Ok(());
@@ -45,6 +61,7 @@ impl ConfigAble for String {
 #[cfg(test)]
 mod test {
     use ConfigProvider;
+    use Diagnostic;
     use ConfigAble;
 
     #[test]
@@ -55,7 +72,7 @@ mod test {
     #[test]
     fn test_string_parse() {
         let mut builder = String::new();
-        let mut fun = |x: String| builder.push_str(x.as_str());
+        let mut fun = |x: Diagnostic| builder.push_str(&x.to_string());
         let mut provider = ConfigProvider::new_from_str("\"This is\\n \\\"a line\"var");
 
         let val = String::parse_from(&mut provider, &mut fun);
@@ -64,4 +81,14 @@ mod test {
         let nxt = provider.get_next();
         assert!(nxt == Some("var".to_string()));
     }
+
+    #[test]
+    fn test_string_write() {
+        let val = "Tab\t \"quoted\" \\ \u{1b}[0m\n'ü'".to_string();
+        assert!(val.to_config_string() == "\"Tab\\t \\\"quoted\\\" \\\\ \\u{1b}[0m\\n'ü'\"");
+
+        let mut provider = ConfigProvider::new_from_str(val.to_config_string());
+        assert!(String::parse_from(&mut provider, &mut |_| {}) == Ok(val));
+        assert!(provider.get_next().is_none());
+    }
 }
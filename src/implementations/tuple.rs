@@ -0,0 +1,237 @@
+use std;
+use ::provider::ConfigProvider;
+use ::ConfigAble;
+use ParseError;
+use Diagnostic;
+use ::schema::{SchemaDefinitions, SchemaNode};
+use overrides::{apply_value, OverridePath, OverridePolicy};
+use diff::{field_path, Change};
+
+/// Parse the element at `index` of a tuple, including the separator in front of it
+fn parse_element<T, F>(provider: &mut ConfigProvider, index: usize, fun: &mut F) -> Result<T, ParseError>
+    where T: ConfigAble,
+          F: FnMut(Diagnostic) {
+    if index > 0 {
+        provider.consume_char(',', fun)?;
+    }
+
+    return T::parse_from(provider, fun);
+}
+
+/// Write the element at `index` of a tuple, including the separator in front of it
+fn write_element<T, F>(value: &T, index: usize, fun: &mut F)
+    where T: ConfigAble,
+          F: FnMut(&str) {
+    if index > 0 {
+        fun(", ");
+    }
+
+    value.write_to(fun);
+}
+
+/// The unit type is written as `()`, or as an empty struct `{}`
+impl ConfigAble for () {
+    fn get_format<F>(_: &mut std::collections::HashSet<String>, fun: &mut F)
+        where F: FnMut(&str) {
+        fun("(): () | {}");
+    }
+
+    fn get_name() -> &'static str { "()" }
+
+    fn get_schema(_: &mut SchemaDefinitions) -> SchemaNode {
+        SchemaNode::Tuple(Vec::new())
+    }
+
+    fn parse_from<F>(provider: &mut ConfigProvider, fun: &mut F) -> Result<Self, ParseError>
+        where F: FnMut(Diagnostic) {
+        match provider.peek_char() {
+            Some('(') => {
+                provider.consume_char('(', fun)?;
+                return provider.consume_char(')', fun);
+            },
+            Some('{') => {
+                provider.consume_char('{', fun)?;
+                return provider.consume_char('}', fun);
+            },
+            Some(x) => {
+                fun(provider.error(0, 1, "Tried to parse (), found something which I couldn't handle").expected("() or {}").found(x.to_string()));
+                return Err(ParseError::Final);
+            },
+            None => {
+                fun(provider.error(0, 0, "At end of file :("));
+                return Err(ParseError::Final);
+            },
+        }
+    }
+
+    fn get_default() -> Result<Self, ()> { Ok(()) }
+
+    fn write_to<F>(&self, fun: &mut F)
+        where F: FnMut(&str) {
+        fun("()");
+    }
+
+    fn merge(&mut self, _: Self) -> Result<(), ()> { Ok(()) }
+}
+
+/// Tuples are written like tuple structs: `( a, b )`
+macro_rules! impl_tuple {
+    ($name:expr, $($T:ident $i:tt),+) => {
+        impl<$($T),+> ConfigAble for ($($T,)+)
+            where $($T: ConfigAble),+ {
+            fn get_format<F>(set: &mut std::collections::HashSet<String>, fun: &mut F)
+                where F: FnMut(&str) {
+                let names = [$($T::get_name()),+].join(", ");
+                fun(format!("({}): ( {} )", names, names).as_str());
+
+                $({
+                    let key = $T::get_format_key();
+                    if !set.contains(&key) {
+                        set.insert(key);
+
+                        fun("\n");
+                        $T::get_format(set, fun);
+                    }
+                })+
+            }
+
+            fn get_name() -> &'static str { $name }
+
+            /// All tuples of a length share a name, so the key names the element types
+            fn get_format_key() -> String {
+                format!("({})", [$($T::get_format_key()),+].join(", "))
+            }
+
+            fn get_schema(defs: &mut SchemaDefinitions) -> SchemaNode {
+                SchemaNode::Tuple(vec![$($T::get_schema(defs)),+])
+            }
+
+            fn parse_from<F>(provider: &mut ConfigProvider, fun: &mut F) -> Result<Self, ParseError>
+                where F: FnMut(Diagnostic) {
+                provider.consume_char('(', fun)?;
+                let ret = ($(parse_element::<$T, F>(provider, $i, fun)?,)+);
+                provider.consume_char(')', fun)?;
+
+                return Ok(ret);
+            }
+
+            fn get_default() -> Result<Self, ()> {
+                Ok(($($T::get_default()?,)+))
+            }
+
+            fn write_to<F>(&self, fun: &mut F)
+                where F: FnMut(&str) {
+                fun("(");
+                $(write_element(&self.$i, $i, fun);)+
+                fun(")");
+            }
+
+            /// Tuples are merged element-wise, in order. If an element can't be merged, the elements in
+            /// front of it are merged already (see ConfigAble::merge)
+            fn merge(&mut self, rhs: Self) -> Result<(), ()> {
+                $(self.$i.merge(rhs.$i)?;)+
+                return Ok(());
+            }
+
+            fn apply_override<F>(&mut self, path: &mut OverridePath, value: &str, policy: OverridePolicy, fun: &mut F) -> Result<(), ParseError>
+                where F: FnMut(Diagnostic) {
+                if path.is_empty() {
+                    return apply_value(self, path, value, policy, fun);
+                }
+
+                let len = [$($i),+].len();
+                match path.take_index(len) {
+                    $(Some($i) => return self.$i.apply_override(path, value, policy, fun),)+
+                    _ => {
+                        fun(path.unknown_index(len));
+                        return Err(ParseError::Recoverable);
+                    },
+                }
+            }
+
+            fn diff_at<F>(&self, new: &Self, path: &str, fun: &mut F)
+                where F: FnMut(Change) {
+                $(self.$i.diff_at(&new.$i, &field_path(path, stringify!($i)), fun);)+
+            }
+        }
+    };
+}
+
+impl_tuple!("(_)", T0 0);
+impl_tuple!("(_, _)", T0 0, T1 1);
+impl_tuple!("(_, _, _)", T0 0, T1 1, T2 2);
+impl_tuple!("(_, _, _, _)", T0 0, T1 1, T2 2, T3 3);
+impl_tuple!("(_, _, _, _, _)", T0 0, T1 1, T2 2, T3 3, T4 4);
+impl_tuple!("(_, _, _, _, _, _)", T0 0, T1 1, T2 2, T3 3, T4 4, T5 5);
+impl_tuple!("(_, _, _, _, _, _, _)", T0 0, T1 1, T2 2, T3 3, T4 4, T5 5, T6 6);
+impl_tuple!("(_, _, _, _, _, _, _, _)", T0 0, T1 1, T2 2, T3 3, T4 4, T5 5, T6 6, T7 7);
+impl_tuple!("(_, _, _, _, _, _, _, _, _)", T0 0, T1 1, T2 2, T3 3, T4 4, T5 5, T6 6, T7 7, T8 8);
+impl_tuple!("(_, _, _, _, _, _, _, _, _, _)", T0 0, T1 1, T2 2, T3 3, T4 4, T5 5, T6 6, T7 7, T8 8, T9 9);
+impl_tuple!("(_, _, _, _, _, _, _, _, _, _, _)", T0 0, T1 1, T2 2, T3 3, T4 4, T5 5, T6 6, T7 7, T8 8, T9 9, T10 10);
+impl_tuple!("(_, _, _, _, _, _, _, _, _, _, _, _)", T0 0, T1 1, T2 2, T3 3, T4 4, T5 5, T6 6, T7 7, T8 8, T9 9, T10 10, T11 11);
+
+#[cfg(test)]
+mod test {
+    use ConfigProvider;
+    use ConfigAble;
+    use ParseError;
+
+    #[test]
+    fn test_tuple_parse() {
+        let mut provider = ConfigProvider::new_from_str("( \"a\", -3 )");
+        assert!(<(String, i32)>::parse_from(&mut provider, &mut |_| {}) == Ok(("a".to_string(), -3)));
+        assert!(provider.get_next().is_none());
+
+        let mut provider = ConfigProvider::new_from_str("[(1, 'x', true), (2, 'y', false)]");
+        let val = Vec::<(u8, char, bool)>::parse_from(&mut provider, &mut |_| {});
+        assert!(val == Ok(vec![(1, 'x', true), (2, 'y', false)]));
+
+        let mut provider = ConfigProvider::new_from_str("(1 2)");
+        assert!(<(u8, u8)>::parse_from(&mut provider, &mut |_| {}) == Err(ParseError::Final));
+    }
+
+    #[test]
+    fn test_tuple_write_format() {
+        let val = (1u8, "b".to_string(), Some('c'));
+        assert!(val.to_config_string() == "(1, \"b\", Some('c'))");
+
+        let mut provider = ConfigProvider::new_from_str(val.to_config_string());
+        assert!(<(u8, String, Option<char>)>::parse_from(&mut provider, &mut |_| {}) == Ok(val));
+
+        assert!(<(String, i32)>::get_format_str().starts_with("(String, i32): ( String, i32 )\n"));
+
+        /* Tuples of the same length are different types in the format */
+        let format = <((String, i32), (u8, u8))>::get_format_str();
+        assert!(format.contains("\n(String, i32): ( String, i32 )\n"));
+        assert!(format.contains("\n(u8, u8): ( u8, u8 )\n"));
+    }
+
+    #[test]
+    fn test_tuple_default_merge() {
+        assert!(<(Vec<u8>, Option<char>)>::get_default() == Ok((vec![], None)));
+        assert!(<(Vec<u8>, char)>::get_default().is_err());
+
+        let mut val = (vec![1u8], None);
+        assert!(val.merge((vec![2], Some('x'))).is_ok());
+        assert!(val == (vec![1, 2], Some('x')));
+        assert!((1u8, 2u8).merge((1, 3)).is_err());
+
+        /* The elements in front of the one that can't be merged are merged */
+        let mut val = (vec![1u8], 'a');
+        assert!(val.merge((vec![2], 'b')).is_err());
+        assert!(val == (vec![1, 2], 'a'));
+    }
+
+    #[test]
+    fn test_unit() {
+        let mut provider = ConfigProvider::new_from_str("[(), {}, ( )]");
+        assert!(Vec::<()>::parse_from(&mut provider, &mut |_| {}) == Ok(vec![(), (), ()]));
+
+        let mut provider = ConfigProvider::new_from_str("x");
+        assert!(<()>::parse_from(&mut provider, &mut |_| {}) == Err(ParseError::Final));
+
+        assert!(().to_config_string() == "()");
+        assert!(<()>::get_default() == Ok(()));
+        assert!(().merge(()).is_ok());
+    }
+}
@@ -0,0 +1,315 @@
+use std;
+use ::provider::ConfigProvider;
+use ::ConfigAble;
+use ParseError;
+use Diagnostic;
+use ::schema::{SchemaDefinitions, SchemaNode};
+
+use std::fmt;
+use std::time::Duration;
+
+use implementations::ints::token_len;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+/// A number of bytes, written with a unit in the config, e.g. `4KiB`, `10MB` or `1.5G`
+pub struct ByteSize(pub u64);
+
+impl ByteSize {
+    /// The number of bytes
+    pub fn as_u64(&self) -> u64 {
+        return self.0;
+    }
+}
+
+/// The units of ByteSize. Single letters are binary, like in dd
+const BYTE_UNITS: &[(&str, u128)] = &[
+    ("B", 1),
+    ("kB", 1000), ("KB", 1000), ("K", 1 << 10), ("k", 1 << 10), ("KiB", 1 << 10),
+    ("MB", 1_000_000), ("M", 1 << 20), ("MiB", 1 << 20),
+    ("GB", 1_000_000_000), ("G", 1 << 30), ("GiB", 1 << 30),
+    ("TB", 1_000_000_000_000), ("T", 1 << 40), ("TiB", 1 << 40),
+    ("PB", 1_000_000_000_000_000), ("P", 1 << 50), ("PiB", 1 << 50),
+    ("EB", 1_000_000_000_000_000_000), ("E", 1 << 60), ("EiB", 1 << 60),
+];
+
+/// The units of Duration, in nanoseconds
+const DURATION_UNITS: &[(&str, u128)] = &[
+    ("d", 86_400_000_000_000),
+    ("h", 3_600_000_000_000),
+    ("m", 60_000_000_000),
+    ("s", 1_000_000_000),
+    ("ms", 1_000_000),
+    ("us", 1_000),
+    ("µs", 1_000),
+    ("ns", 1),
+];
+
+/// A problem inside a token. The position is relative to the start of the token
+struct UnitError {
+    start: usize,
+    len: usize,
+    message: String,
+}
+
+fn unit_error<S: Into<String>>(start: usize, len: usize, message: S) -> UnitError {
+    return UnitError { start, len: std::cmp::max(len, 1), message: message.into() };
+}
+
+/// Parse a sequence of `<number><unit>` parts (e.g. `1h30m`) and sum them up in the base unit.
+///
+/// Numbers may have a fraction, which has to result in a whole number of the base unit if `exact`
+/// is set. Otherwise it's rounded down.
+fn parse_units(s: &str, units: &[(&str, u128)], max: u128, name: &str, exact: bool) -> Result<u128, UnitError> {
+    let unit_names = units.iter().map(|x| x.0).collect::<Vec<_>>().join(", ");
+    let mut total: u128 = 0;
+    let mut pos = 0;
+
+    if s.is_empty() {
+        return Err(unit_error(0, 0, format!("Expected a {}, e.g. 10{}", name, units[0].0)));
+    }
+
+    while pos < s.len() {
+        let rest = &s[pos..];
+        let num_len = rest.find(|c: char| !(c.is_ascii_digit() || c == '.' || c == '_')).unwrap_or(rest.len());
+        let num = &rest[..num_len];
+        let unit_len = rest[num_len..].find(|c: char| c.is_ascii_digit()).unwrap_or(rest.len() - num_len);
+        let unit = &rest[num_len..num_len + unit_len];
+
+        if num.is_empty() || num.starts_with('_') || num.matches('.').count() > 1 {
+            return Err(unit_error(pos, std::cmp::max(num_len, unit_len), format!("Expected a number in {} '{}'", name, s)));
+        }
+        if unit.is_empty() {
+            return Err(unit_error(pos, num_len, format!("Missing unit after {} in {} '{}', expected one of: {}", num, name, s, unit_names)));
+        }
+
+        let mult = match units.iter().find(|x| x.0 == unit) {
+            Some(x) => x.1,
+            None => {
+                return Err(unit_error(pos + num_len, unit_len, format!("Unknown unit '{}' in {} '{}', expected one of: {}", unit, name, s, unit_names)));
+            },
+        };
+
+        let digits: String = num.chars().filter(|c| *c != '_').collect();
+        let (int, frac) = match digits.find('.') {
+            Some(i) => (&digits[..i], &digits[i + 1..]),
+            None => (digits.as_str(), ""),
+        };
+
+        let too_large = || unit_error(0, s.len(), format!("{} '{}' is too large, the maximum is about {}", name, s, write_rounded_unit(max, units)));
+        let int: u128 = if int.is_empty() { 0 } else { int.parse().map_err(|_| too_large())? };
+        let mut value = int.checked_mul(mult).ok_or_else(too_large)?;
+
+        if !frac.is_empty() {
+            let scale = 10u128.checked_pow(frac.len() as u32).ok_or_else(too_large)?;
+            let frac_value = frac.parse::<u128>().map_err(|_| too_large())?.checked_mul(mult).ok_or_else(too_large)?;
+            if exact && !frac_value.is_multiple_of(scale) {
+                return Err(unit_error(pos, num_len + unit_len, format!("{}{} isn't a whole number of {}", num, unit, units[0].0)));
+            }
+            value = value.checked_add(frac_value / scale).ok_or_else(too_large)?;
+        }
+
+        total = total.checked_add(value).filter(|x| *x <= max).ok_or_else(too_large)?;
+        pos += num_len + unit_len;
+    }
+
+    return Ok(total);
+}
+
+/// Write `value` with the largest unit that divides it
+fn write_largest_unit(value: u128, units: &[(&str, u128)]) -> String {
+    for &(name, mult) in units.iter().rev() {
+        if value != 0 && value.is_multiple_of(mult) {
+            return format!("{}{}", value / mult, name);
+        }
+    }
+
+    return format!("{}{}", value, units[0].0);
+}
+
+/// Write `value` rounded to the largest unit that isn't larger than it, e.g. for error messages
+fn write_rounded_unit(value: u128, units: &[(&str, u128)]) -> String {
+    let mut largest = units[0];
+    for &unit in units.iter().filter(|x| x.1 <= value) {
+        if unit.1 > largest.1 {
+            largest = unit;
+        }
+    }
+
+    return format!("{}{}", (value + largest.1 / 2) / largest.1, largest.0);
+}
+
+impl fmt::Display for ByteSize {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        /* Prefer whichever of the binary and decimal units gives the shorter number */
+        let binary: Vec<_> = BYTE_UNITS.iter().cloned().filter(|x| x.0 == "B" || x.0.ends_with("iB")).collect();
+        let decimal: Vec<_> = BYTE_UNITS.iter().cloned().filter(|x| x.0 == "B" || (x.0.len() == 2 && x.0 != "kB")).collect();
+
+        let binary = write_largest_unit(self.0 as u128, &binary);
+        let decimal = write_largest_unit(self.0 as u128, &decimal);
+        let digits = |x: &str| x.chars().filter(|c| c.is_ascii_digit()).count();
+
+        f.write_str(if digits(&decimal) < digits(&binary) { &decimal } else { &binary })
+    }
+}
+
+/// Parse a token with `parse` and report problems at the exact location
+fn parse_token<T, F, P>(provider: &mut ConfigProvider, fun: &mut F, name: &str, parse: P) -> Result<T, ParseError>
+    where F: FnMut(Diagnostic),
+          P: Fn(&str) -> Result<T, UnitError> {
+    if let Some(content) = provider.get_next() {
+        let len = token_len(&content);
+        let token = &content[..len];
+
+        let ret = parse(token);
+        if let Err(ref x) = ret {
+            fun(provider.error(x.start, x.len, format!("Failed to parse {}: {}", name, x.message)).expected(name).found(token));
+        }
+
+        provider.consume(len, fun)?;
+        return ret.map_err(|_| ParseError::Recoverable);
+    }
+
+    fun(provider.error(0, 0, "At end of file :("));
+    return Err(ParseError::Final);
+}
+
+impl ConfigAble for ByteSize {
+    fn get_format<F>(_: &mut std::collections::HashSet<String>, fun: &mut F)
+        where F: FnMut(&str) {
+        fun("ByteSize: Number with unit, e.g. 4KiB, 10MB or 1.5G
+    B: bytes
+    kB, MB, GB, TB, PB, EB: powers of 1000
+    K, M, G, T, P, E and KiB, MiB, GiB, TiB, PiB, EiB: powers of 1024");
+    }
+
+    fn get_name() -> &'static str { "ByteSize" }
+
+    fn get_schema(_: &mut SchemaDefinitions) -> SchemaNode {
+        SchemaNode::String { format: None, min_length: Some(2), max_length: None }
+    }
+
+    fn parse_from<F>(provider: &mut ConfigProvider, fun: &mut F) -> Result<Self, ParseError>
+        where F: FnMut(Diagnostic) {
+        parse_token(provider, fun, "ByteSize", |x| parse_units(x, BYTE_UNITS, u64::MAX as u128, "ByteSize", true).map(|x| ByteSize(x as u64)))
+    }
+
+    fn get_default() -> Result<Self, ()> { Err(()) }
+
+    fn write_to<F>(&self, fun: &mut F)
+        where F: FnMut(&str) {
+        fun(self.to_string().as_str());
+    }
+
+    fn merge(&mut self, rhs: Self) -> Result<(), ()> { if *self == rhs { Ok(()) } else { Err(()) } }
+}
+
+impl ConfigAble for Duration {
+    fn get_format<F>(_: &mut std::collections::HashSet<String>, fun: &mut F)
+        where F: FnMut(&str) {
+        fun("Duration: Numbers with units, e.g. 500ms, 30s, 5m or 1h30m
+    d: days, h: hours, m: minutes, s: seconds
+    ms: milliseconds, us or µs: microseconds, ns: nanoseconds");
+    }
+
+    fn get_name() -> &'static str { "Duration" }
+
+    fn get_schema(_: &mut SchemaDefinitions) -> SchemaNode {
+        SchemaNode::String { format: None, min_length: Some(2), max_length: None }
+    }
+
+    fn parse_from<F>(provider: &mut ConfigProvider, fun: &mut F) -> Result<Self, ParseError>
+        where F: FnMut(Diagnostic) {
+        let max = Duration::MAX.as_nanos();
+        parse_token(provider, fun, "Duration", |x| {
+            let nanos = parse_units(x, DURATION_UNITS, max, "Duration", false)?;
+            Ok(Duration::new((nanos / 1_000_000_000) as u64, (nanos % 1_000_000_000) as u32))
+        })
+    }
+
+    fn get_default() -> Result<Self, ()> { Err(()) }
+
+    /// Durations are written as sum of units, e.g. `1h30m`
+    fn write_to<F>(&self, fun: &mut F)
+        where F: FnMut(&str) {
+        let mut rest = self.as_nanos();
+        if rest == 0 {
+            fun("0s");
+            return;
+        }
+
+        for &(name, mult) in DURATION_UNITS.iter().filter(|x| x.0 != "µs") {
+            if rest >= mult {
+                fun(&format!("{}{}", rest / mult, name));
+                rest %= mult;
+            }
+        }
+    }
+
+    fn merge(&mut self, rhs: Self) -> Result<(), ()> { if *self == rhs { Ok(()) } else { Err(()) } }
+}
+
+#[cfg(test)]
+mod test {
+    use ConfigProvider;
+    use Diagnostic;
+    use ConfigAble;
+    use implementations::units::ByteSize;
+
+    use std::time::Duration;
+
+    fn parse<T: ConfigAble>(s: &str) -> (Result<T, ::ParseError>, Vec<Diagnostic>) {
+        let mut errors = Vec::new();
+        let ret = T::parse_from(&mut ConfigProvider::new_from_str(s), &mut |x| errors.push(x));
+        return (ret, errors);
+    }
+
+    #[test]
+    fn test_duration_parse() {
+        assert!(parse::<Duration>("500ms").0 == Ok(Duration::from_millis(500)));
+        assert!(parse::<Duration>("30s").0 == Ok(Duration::from_secs(30)));
+        assert!(parse::<Duration>("5m").0 == Ok(Duration::from_secs(300)));
+        assert!(parse::<Duration>("1h30m").0 == Ok(Duration::from_secs(5400)));
+        assert!(parse::<Duration>("1.5s").0 == Ok(Duration::from_millis(1500)));
+        assert!(parse::<Duration>("2d,").0 == Ok(Duration::from_secs(2 * 86400)));
+
+        let (_, errors) = parse::<Duration>("30");
+        assert!(errors[0].message == "Failed to parse Duration: Missing unit after 30 in Duration '30', expected one of: d, h, m, s, ms, us, µs, ns");
+
+        let (_, errors) = parse::<Duration>("1h30x");
+        assert!(errors[0].message.starts_with("Failed to parse Duration: Unknown unit 'x' in Duration '1h30x'"));
+        assert!(errors[0].span.as_ref().map(|x| x.start_column) == Some(5));
+
+        let (_, errors) = parse::<Duration>("1000000000000000000000d");
+        assert!(errors[0].message == "Failed to parse Duration: Duration '1000000000000000000000d' is too large, the maximum is about 213503982334601d");
+    }
+
+    #[test]
+    fn test_duration_write() {
+        assert!(Duration::from_secs(5400).to_config_string() == "1h30m");
+        assert!(Duration::from_millis(1500).to_config_string() == "1s500ms");
+        assert!(Duration::from_secs(0).to_config_string() == "0s");
+
+        let val = Duration::new(90061, 1_001);
+        assert!(parse::<Duration>(&val.to_config_string()).0 == Ok(val));
+    }
+
+    #[test]
+    fn test_byte_size() {
+        assert!(parse::<ByteSize>("4KiB").0 == Ok(ByteSize(4096)));
+        assert!(parse::<ByteSize>("10MB").0 == Ok(ByteSize(10_000_000)));
+        assert!(parse::<ByteSize>("1.5G").0 == Ok(ByteSize(3 << 29)));
+        let (_, errors) = parse::<ByteSize>("16EiB");
+        assert!(errors[0].message == "Failed to parse ByteSize: ByteSize '16EiB' is too large, the maximum is about 16E");
+
+        let (_, errors) = parse::<ByteSize>("1.5B");
+        assert!(errors[0].message == "Failed to parse ByteSize: 1.5B isn't a whole number of B");
+
+        let (_, errors) = parse::<ByteSize>("10mb");
+        assert!(errors[0].message.starts_with("Failed to parse ByteSize: Unknown unit 'mb' in ByteSize '10mb'"));
+
+        assert!(ByteSize(4096).to_config_string() == "4KiB");
+        assert!(ByteSize(10_000_000).to_config_string() == "10MB");
+        assert!(ByteSize(1500).to_config_string() == "1500B");
+        assert!(ByteSize(0).to_config_string() == "0B");
+    }
+}
@@ -2,6 +2,13 @@ use std;
 use ::provider::ConfigProvider;
 use ::ConfigAble;
 use ParseError;
+use Diagnostic;
+use ::schema::{SchemaDefinitions, SchemaNode};
+
+use std::collections::VecDeque;
+
+use overrides::{apply_to_element, apply_value, OverridePath, OverridePolicy};
+use diff::{diff_elements, Change};
 
 impl<T> ConfigAble for Vec<T>
     where T: ConfigAble {
@@ -10,9 +17,9 @@ impl<T> ConfigAble for Vec<T>
         // TODO: Re-do the newline appending
         fun(format!("Vec<{}>: [ {}, {}, ... ]", T::get_name(), T::get_name(), T::get_name()).as_str());
 
-        let key = T::get_name().to_string();
+        let key = T::get_format_key();
         if !set.contains(&key) {
-            set.insert(T::get_name().to_string());
+            set.insert(key);
 
             fun("\n");
             T::get_format(set, fun);
@@ -23,8 +30,12 @@ impl<T> ConfigAble for Vec<T>
         concat!("Vec<", /*T::get_name(),*/ ">")
     }
 
+    fn get_schema(defs: &mut SchemaDefinitions) -> SchemaNode {
+        SchemaNode::Array { items: Box::new(T::get_schema(defs)), min_items: None, max_items: None }
+    }
+
     fn parse_from<F>(provider: &mut ConfigProvider, fun: &mut F) -> Result<Self, ParseError>
-        where F: FnMut(String) {
+        where F: FnMut(Diagnostic) {
 
         let mut first = true;
         let mut ret = Vec::new();
@@ -32,8 +43,7 @@ impl<T> ConfigAble for Vec<T>
         provider.consume_char('[', fun)?;
         loop {
             if provider.is_at_end() {
-                provider.print_error(0, fun);
-                fun("Reached end of file while reading vector :(".to_string());
+                fun(provider.error(0, 0, "Reached end of file while reading vector :("));
                 return Err(ParseError::Final);
             }
 
@@ -54,10 +64,29 @@ impl<T> ConfigAble for Vec<T>
 
     fn get_default() -> Result<Self, ()> { Ok(Self::new()) }
 
+    fn write_to<F>(&self, fun: &mut F)
+        where F: FnMut(&str) {
+        write_list(self.iter(), fun);
+    }
+
     fn merge(&mut self, rhs: Self) -> Result<(), ()> {
-        self.extend(rhs.into_iter());
+        self.extend(rhs);
         return Ok(());
     }
+
+    fn apply_override<F>(&mut self, path: &mut OverridePath, value: &str, policy: OverridePolicy, fun: &mut F) -> Result<(), ParseError>
+        where F: FnMut(Diagnostic) {
+        if path.is_empty() {
+            return apply_value(self, path, value, policy, fun);
+        }
+
+        return apply_to_element(self, path, value, policy, fun);
+    }
+
+    fn diff_at<F>(&self, new: &Self, path: &str, fun: &mut F)
+        where F: FnMut(Change) {
+        diff_elements(self.iter(), new.iter(), path, fun);
+    }
 }
 
 impl<T> ConfigAble for Box<[T]>
@@ -67,9 +96,9 @@ impl<T> ConfigAble for Box<[T]>
         // TODO: Re-do the newline appending
         fun(format!("Box<[{}]>: [ {}, {}, ... ]", T::get_name(), T::get_name(), T::get_name()).as_str());
 
-        let key = T::get_name().to_string();
+        let key = T::get_format_key();
         if !set.contains(&key) {
-            set.insert(T::get_name().to_string());
+            set.insert(key);
 
             fun("\n");
             T::get_format(set, fun);
@@ -80,8 +109,12 @@ impl<T> ConfigAble for Box<[T]>
         concat!("Box<[", /*T::get_name(),*/ "]>")
     }
 
+    fn get_schema(defs: &mut SchemaDefinitions) -> SchemaNode {
+        SchemaNode::Array { items: Box::new(T::get_schema(defs)), min_items: None, max_items: None }
+    }
+
     fn parse_from<F>(provider: &mut ConfigProvider, fun: &mut F) -> Result<Self, ParseError>
-        where F: FnMut(String) {
+        where F: FnMut(Diagnostic) {
         let ret: Vec<T> = ConfigAble::parse_from(provider, fun)?;
 
         return Ok(ret.into_boxed_slice());
@@ -89,15 +122,109 @@ impl<T> ConfigAble for Box<[T]>
 
     fn get_default() -> Result<Self, ()> { Err(()) }
 
+    fn write_to<F>(&self, fun: &mut F)
+        where F: FnMut(&str) {
+        write_list(self.iter(), fun);
+    }
+
     fn merge(&mut self, _: Self) -> Result<(), ()> { Err(()) }
+
+    fn apply_override<F>(&mut self, path: &mut OverridePath, value: &str, policy: OverridePolicy, fun: &mut F) -> Result<(), ParseError>
+        where F: FnMut(Diagnostic) {
+        if path.is_empty() {
+            return apply_value(self, path, value, policy, fun);
+        }
+
+        return apply_to_element(self, path, value, policy, fun);
+    }
+
+    fn diff_at<F>(&self, new: &Self, path: &str, fun: &mut F)
+        where F: FnMut(Change) {
+        diff_elements(self.iter(), new.iter(), path, fun);
+    }
+}
+
+impl<T> ConfigAble for VecDeque<T>
+    where T: ConfigAble {
+    fn get_format<F>(set: &mut std::collections::HashSet<String>, fun: &mut F)
+        where F: FnMut(&str) {
+        fun(format!("VecDeque<{}>: [ {}, {}, ... ]", T::get_name(), T::get_name(), T::get_name()).as_str());
+
+        let key = T::get_format_key();
+        if !set.contains(&key) {
+            set.insert(key);
+
+            fun("\n");
+            T::get_format(set, fun);
+        }
+    }
+
+    fn get_name() -> &'static str {
+        concat!("VecDeque<", /*T::get_name(),*/ ">")
+    }
+
+    fn get_schema(defs: &mut SchemaDefinitions) -> SchemaNode {
+        SchemaNode::Array { items: Box::new(T::get_schema(defs)), min_items: None, max_items: None }
+    }
+
+    fn parse_from<F>(provider: &mut ConfigProvider, fun: &mut F) -> Result<Self, ParseError>
+        where F: FnMut(Diagnostic) {
+        let ret: Vec<T> = ConfigAble::parse_from(provider, fun)?;
+
+        return Ok(ret.into());
+    }
+
+    fn get_default() -> Result<Self, ()> { Ok(Self::new()) }
+
+    fn write_to<F>(&self, fun: &mut F)
+        where F: FnMut(&str) {
+        write_list(self.iter(), fun);
+    }
+
+    fn merge(&mut self, rhs: Self) -> Result<(), ()> {
+        self.extend(rhs);
+        return Ok(());
+    }
+
+    fn apply_override<F>(&mut self, path: &mut OverridePath, value: &str, policy: OverridePolicy, fun: &mut F) -> Result<(), ParseError>
+        where F: FnMut(Diagnostic) {
+        if path.is_empty() {
+            return apply_value(self, path, value, policy, fun);
+        }
+
+        return apply_to_element(self.make_contiguous(), path, value, policy, fun);
+    }
+
+    fn diff_at<F>(&self, new: &Self, path: &str, fun: &mut F)
+        where F: FnMut(Change) {
+        diff_elements(self.iter(), new.iter(), path, fun);
+    }
+}
+
+/// Write a list of values in the format parsed by Vec
+pub fn write_list<'a, T, I, F>(it: I, fun: &mut F)
+    where T: ConfigAble + 'a,
+          I: std::iter::Iterator<Item=&'a T>,
+          F: FnMut(&str) {
+    fun("[");
+    for (i, x) in it.enumerate() {
+        if i > 0 {
+            fun(", ");
+        }
+        x.write_to(fun);
+    }
+    fun("]");
 }
 
 
 #[cfg(test)]
 mod test {
     use ConfigProvider;
+    use Diagnostic;
     use ConfigAble;
 
+    use std::collections::VecDeque;
+
     #[test]
     fn test_vec_format() {
         assert!(<Vec<char> as ConfigAble>::get_format_str().starts_with("Vec<char>: [ char, char, ... ]\n"));
@@ -106,13 +233,34 @@ mod test {
     #[test]
     fn test_vec_parse() {
         let mut builder = String::new();
-        let mut fun = |x: String| builder.push_str(x.as_str());
+        let mut fun = |x: Diagnostic| builder.push_str(&x.to_string());
         let mut provider = ConfigProvider::new_from_str("[]");
         assert!(<Vec<char> as ConfigAble>::parse_from(&mut provider, &mut fun) == Ok(vec![]));
-        assert!(provider.get_next() == None);
+        assert!(provider.get_next().is_none());
 
         let mut provider2 = ConfigProvider::new_from_str("[ '1', '2', '3' ]");
         assert!(<Vec<char> as ConfigAble>::parse_from(&mut provider2, &mut fun) == Ok(vec!['1', '2', '3']));
-        assert!(provider2.get_next() == None);
+        assert!(provider2.get_next().is_none());
+    }
+
+    #[test]
+    fn test_vec_deque() {
+        let mut provider = ConfigProvider::new_from_str("['a', 'b']");
+        let mut val = <VecDeque<char> as ConfigAble>::parse_from(&mut provider, &mut |_| {}).unwrap();
+        assert!(val == ['a', 'b']);
+
+        assert!(val.merge(vec!['c'].into()).is_ok());
+        assert!(val.to_config_string() == "['a', 'b', 'c']");
+    }
+
+    #[test]
+    fn test_vec_write() {
+        assert!(Vec::<char>::new().to_config_string() == "[]");
+
+        let val = vec![vec!["a".to_string()], vec![], vec!["b".to_string(), "c".to_string()]];
+        assert!(val.to_config_string() == "[[\"a\"], [], [\"b\", \"c\"]]");
+
+        let mut provider = ConfigProvider::new_from_str(val.to_config_string());
+        assert!(<Vec<Vec<String>> as ConfigAble>::parse_from(&mut provider, &mut |_| {}) == Ok(val));
     }
 }
@@ -0,0 +1,302 @@
+use std;
+use ::provider::ConfigProvider;
+use ::ConfigAble;
+use ParseError;
+use Diagnostic;
+use ::schema::{SchemaDefinitions, SchemaNode};
+use overrides::{OverridePath, OverridePolicy};
+use diff::Change;
+
+use std::borrow::{Cow, ToOwned};
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use std::sync::Arc;
+
+/// The wrappers are transparent in the config: `Box<T>` is written just like `T`.
+///
+/// The format still names the wrapper, so the format of `T` is only appended once for recursive
+/// types
+fn wrapper_format<T, F>(wrapper: &str, set: &mut std::collections::HashSet<String>, fun: &mut F)
+    where T: ConfigAble,
+          F: FnMut(&str) {
+    fun(format!("{}<{}>: {}", wrapper, T::get_name(), T::get_name()).as_str());
+
+    let key = T::get_format_key();
+    if !set.contains(&key) {
+        set.insert(key);
+
+        fun("\n");
+        T::get_format(set, fun);
+    }
+}
+
+macro_rules! impl_transparent {
+    ($wrapper:ident) => {
+        impl<T> ConfigAble for $wrapper<T>
+            where T: ConfigAble {
+            fn get_format<F>(set: &mut std::collections::HashSet<String>, fun: &mut F)
+                where F: FnMut(&str) {
+                wrapper_format::<T, F>(stringify!($wrapper), set, fun);
+            }
+
+            fn get_name() -> &'static str { T::get_name() }
+
+            fn get_schema(defs: &mut SchemaDefinitions) -> SchemaNode {
+                T::get_schema(defs)
+            }
+
+            fn parse_from<F>(provider: &mut ConfigProvider, fun: &mut F) -> Result<Self, ParseError>
+                where F: FnMut(Diagnostic) {
+                return T::parse_from(provider, fun).map($wrapper::new);
+            }
+
+            fn get_default() -> Result<Self, ()> { T::get_default().map($wrapper::new) }
+
+            fn write_to<F>(&self, fun: &mut F)
+                where F: FnMut(&str) {
+                (**self).write_to(fun);
+            }
+
+            fn write_sample<F>(level: usize, fun: &mut F) -> bool
+                where F: FnMut(&str) {
+                T::write_sample(level, fun)
+            }
+
+            impl_transparent!(merge $wrapper);
+        }
+    };
+    (merge Box) => {
+        fn merge(&mut self, rhs: Self) -> Result<(), ()> {
+            (**self).merge(*rhs)
+        }
+
+        fn apply_override<F>(&mut self, path: &mut OverridePath, value: &str, policy: OverridePolicy, fun: &mut F) -> Result<(), ParseError>
+            where F: FnMut(Diagnostic) {
+            (**self).apply_override(path, value, policy, fun)
+        }
+
+        fn diff_at<F>(&self, new: &Self, path: &str, fun: &mut F)
+            where F: FnMut(Change) {
+            (**self).diff_at(&**new, path, fun)
+        }
+    };
+    /* Shared values can only be merged while they aren't shared yet, which is the case while
+     * reading a config */
+    (merge $wrapper:ident) => {
+        fn merge(&mut self, rhs: Self) -> Result<(), ()> {
+            match ($wrapper::get_mut(self), $wrapper::try_unwrap(rhs)) {
+                (Some(lhs), Ok(rhs)) => lhs.merge(rhs),
+                _ => Err(()),
+            }
+        }
+
+        fn apply_override<F>(&mut self, path: &mut OverridePath, value: &str, policy: OverridePolicy, fun: &mut F) -> Result<(), ParseError>
+            where F: FnMut(Diagnostic) {
+            match $wrapper::get_mut(self) {
+                Some(x) => x.apply_override(path, value, policy, fun),
+                None => {
+                    fun(path.error(format!("{} is shared, and can't be changed", path.location())));
+                    Err(ParseError::Recoverable)
+                },
+            }
+        }
+
+        fn diff_at<F>(&self, new: &Self, path: &str, fun: &mut F)
+            where F: FnMut(Change) {
+            (**self).diff_at(&**new, path, fun)
+        }
+    };
+}
+
+impl_transparent!(Box);
+impl_transparent!(Rc);
+impl_transparent!(Arc);
+
+impl<'a, T> ConfigAble for Cow<'a, T>
+    where T: ToOwned + ?Sized,
+          T::Owned: ConfigAble {
+    fn get_format<F>(set: &mut std::collections::HashSet<String>, fun: &mut F)
+        where F: FnMut(&str) {
+        wrapper_format::<T::Owned, F>("Cow", set, fun);
+    }
+
+    fn get_name() -> &'static str { T::Owned::get_name() }
+
+    fn get_schema(defs: &mut SchemaDefinitions) -> SchemaNode {
+        T::Owned::get_schema(defs)
+    }
+
+    fn parse_from<F>(provider: &mut ConfigProvider, fun: &mut F) -> Result<Self, ParseError>
+        where F: FnMut(Diagnostic) {
+        return T::Owned::parse_from(provider, fun).map(Cow::Owned);
+    }
+
+    fn get_default() -> Result<Self, ()> { T::Owned::get_default().map(Cow::Owned) }
+
+    fn write_to<F>(&self, fun: &mut F)
+        where F: FnMut(&str) {
+        match *self {
+            Cow::Owned(ref x) => x.write_to(fun),
+            Cow::Borrowed(x) => x.to_owned().write_to(fun),
+        }
+    }
+
+    fn write_sample<F>(level: usize, fun: &mut F) -> bool
+        where F: FnMut(&str) {
+        T::Owned::write_sample(level, fun)
+    }
+
+    fn merge(&mut self, rhs: Self) -> Result<(), ()> {
+        self.to_mut().merge(rhs.into_owned())
+    }
+
+    fn apply_override<F>(&mut self, path: &mut OverridePath, value: &str, policy: OverridePolicy, fun: &mut F) -> Result<(), ParseError>
+        where F: FnMut(Diagnostic) {
+        self.to_mut().apply_override(path, value, policy, fun)
+    }
+
+    fn diff_at<F>(&self, new: &Self, path: &str, fun: &mut F)
+        where F: FnMut(Change) {
+        (**self).to_owned().diff_at(&(**new).to_owned(), path, fun)
+    }
+}
+
+impl<T> ConfigAble for Cell<T>
+    where T: ConfigAble + Copy {
+    fn get_format<F>(set: &mut std::collections::HashSet<String>, fun: &mut F)
+        where F: FnMut(&str) {
+        wrapper_format::<T, F>("Cell", set, fun);
+    }
+
+    fn get_name() -> &'static str { T::get_name() }
+
+    fn get_schema(defs: &mut SchemaDefinitions) -> SchemaNode {
+        T::get_schema(defs)
+    }
+
+    fn parse_from<F>(provider: &mut ConfigProvider, fun: &mut F) -> Result<Self, ParseError>
+        where F: FnMut(Diagnostic) {
+        return T::parse_from(provider, fun).map(Cell::new);
+    }
+
+    fn get_default() -> Result<Self, ()> { T::get_default().map(Cell::new) }
+
+    fn write_to<F>(&self, fun: &mut F)
+        where F: FnMut(&str) {
+        self.get().write_to(fun);
+    }
+
+    fn write_sample<F>(level: usize, fun: &mut F) -> bool
+        where F: FnMut(&str) {
+        T::write_sample(level, fun)
+    }
+
+    fn merge(&mut self, rhs: Self) -> Result<(), ()> {
+        self.get_mut().merge(rhs.into_inner())
+    }
+
+    fn apply_override<F>(&mut self, path: &mut OverridePath, value: &str, policy: OverridePolicy, fun: &mut F) -> Result<(), ParseError>
+        where F: FnMut(Diagnostic) {
+        self.get_mut().apply_override(path, value, policy, fun)
+    }
+
+    fn diff_at<F>(&self, new: &Self, path: &str, fun: &mut F)
+        where F: FnMut(Change) {
+        self.get().diff_at(&new.get(), path, fun)
+    }
+}
+
+impl<T> ConfigAble for RefCell<T>
+    where T: ConfigAble {
+    fn get_format<F>(set: &mut std::collections::HashSet<String>, fun: &mut F)
+        where F: FnMut(&str) {
+        wrapper_format::<T, F>("RefCell", set, fun);
+    }
+
+    fn get_name() -> &'static str { T::get_name() }
+
+    fn get_schema(defs: &mut SchemaDefinitions) -> SchemaNode {
+        T::get_schema(defs)
+    }
+
+    fn parse_from<F>(provider: &mut ConfigProvider, fun: &mut F) -> Result<Self, ParseError>
+        where F: FnMut(Diagnostic) {
+        return T::parse_from(provider, fun).map(RefCell::new);
+    }
+
+    fn get_default() -> Result<Self, ()> { T::get_default().map(RefCell::new) }
+
+    fn write_to<F>(&self, fun: &mut F)
+        where F: FnMut(&str) {
+        self.borrow().write_to(fun);
+    }
+
+    fn write_sample<F>(level: usize, fun: &mut F) -> bool
+        where F: FnMut(&str) {
+        T::write_sample(level, fun)
+    }
+
+    fn merge(&mut self, rhs: Self) -> Result<(), ()> {
+        self.get_mut().merge(rhs.into_inner())
+    }
+
+    fn apply_override<F>(&mut self, path: &mut OverridePath, value: &str, policy: OverridePolicy, fun: &mut F) -> Result<(), ParseError>
+        where F: FnMut(Diagnostic) {
+        self.get_mut().apply_override(path, value, policy, fun)
+    }
+
+    fn diff_at<F>(&self, new: &Self, path: &str, fun: &mut F)
+        where F: FnMut(Change) {
+        self.borrow().diff_at(&new.borrow(), path, fun)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ConfigProvider;
+    use ConfigAble;
+
+    use std::borrow::Cow;
+    use std::cell::{Cell, RefCell};
+    use std::rc::Rc;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_transparent() {
+        let mut provider = ConfigProvider::new_from_str("[1, 2]");
+        let val = <Box<Vec<u8>> as ConfigAble>::parse_from(&mut provider, &mut |_| {});
+        assert!(val == Ok(Box::new(vec![1, 2])));
+        assert!(Box::<u8>::get_format_str() == format!("Box<u8>: u8\n{}", u8::get_format_str()));
+
+        let mut provider = ConfigProvider::new_from_str("\"shared\"");
+        let val = <Arc<String> as ConfigAble>::parse_from(&mut provider, &mut |_| {}).unwrap();
+        assert!(val.to_config_string() == "\"shared\"");
+
+        let mut provider = ConfigProvider::new_from_str("\"text\"");
+        let val = <Cow<str> as ConfigAble>::parse_from(&mut provider, &mut |_| {});
+        assert!(val == Ok(Cow::Borrowed("text")));
+        assert!(Cow::Borrowed("text").to_config_string() == "\"text\"");
+
+        assert!(Cell::new(3u8).to_config_string() == "3");
+        assert!(RefCell::new(Some('x')).to_config_string() == "Some('x')");
+    }
+
+    #[test]
+    fn test_merge() {
+        let mut val = Rc::new(vec![1u8]);
+        assert!(val.merge(Rc::new(vec![2])).is_ok());
+        assert!(*val == vec![1, 2]);
+
+        let shared = val.clone();
+        assert!(val.merge(Rc::new(vec![3])).is_err());
+        drop(shared);
+
+        let mut val = RefCell::new(vec![1u8]);
+        assert!(val.merge(RefCell::new(vec![2])).is_ok());
+        assert!(*val.borrow() == vec![1, 2]);
+
+        let mut val: Cow<[u8]> = Cow::Borrowed(&[1]);
+        assert!(val.merge(Cow::Owned(vec![2])).is_ok());
+        assert!(val == Cow::Borrowed(&[1, 2][..]));
+    }
+}
@@ -0,0 +1,233 @@
+//! Reading a config from multiple files, e.g. system defaults, per-user and per-project settings.
+//!
+//! The layers are read in order, as if they were one file. For fields that are given in multiple
+//! layers the last layer wins, or the values are merged (see OverridePolicy).
+use std;
+use ConfigAble;
+use ConfigError;
+use ConfigProvider;
+use OverridePolicy;
+use read_from_provider;
+use diff::{field_path, index_path};
+use formatter::{Delimiter, Entry, Group, Node, SyntaxTree};
+use provider::read_lines;
+use schema::{SchemaDefinitions, SchemaField, SchemaNode, VariantFields};
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Builder for reading a config from multiple files
+pub struct LayeredConfig {
+    layers: Vec<PathBuf>,
+    policy: OverridePolicy,
+}
+
+#[derive(Debug)]
+/// A config read from multiple files, with the layer every value came from
+pub struct Layered<T> {
+    /// The final value
+    pub value: T,
+    /// The files that were read, in order
+    pub layers: Vec<PathBuf>,
+    /// The files that were skipped, because they don't exist
+    pub skipped: Vec<PathBuf>,
+    /// The indices into layers for every field path, see sources
+    sources: BTreeMap<String, Vec<usize>>,
+}
+
+impl LayeredConfig {
+    /// A builder without any layers. Fields in multiple layers are taken from the last one by
+    /// default
+    pub fn new() -> Self {
+        return LayeredConfig { layers: Vec::new(), policy: OverridePolicy::Replace };
+    }
+
+    /// Add a layer. Later layers take precedence over earlier ones. Files that don't exist are
+    /// skipped
+    pub fn layer<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.layers.push(path.into());
+        return self;
+    }
+
+    /// Set how fields that are given in multiple layers are combined. With OverridePolicy::Merge
+    /// e.g. lists are concatenated, but strings are too, and numbers fail to merge
+    pub fn policy(mut self, policy: OverridePolicy) -> Self {
+        self.policy = policy;
+        return self;
+    }
+
+    /// Read the config from all existing layers
+    pub fn read<T>(&self) -> Result<Layered<T>, ConfigError>
+        where T: ConfigAble {
+        let (layers, skipped): (Vec<PathBuf>, Vec<PathBuf>) = self.layers.iter().cloned().partition(|x| {
+            match std::fs::metadata(x) {
+                Err(ref e) if e.kind() == std::io::ErrorKind::NotFound => false,
+                /* Other errors are reported while reading the file */
+                _ => true,
+            }
+        });
+
+        /* The layers are read like a single file that includes all of them */
+        let mut lines = vec![(0, "{".to_string())];
+        lines.extend(layers.iter().map(|x| (0, format!("!include {}", x.display()))));
+        lines.push((usize::MAX, "}".to_string()));
+
+        let mut provider = match ConfigProvider::try_new_with_provider(lines.into_iter(), "layers".to_string()) {
+            Ok(x) => x,
+            Err(x) => {
+                return Err(ConfigError::Parse { diagnostics: vec![*x], format: Some(T::get_format_str()) });
+            },
+        };
+        provider.set_duplicate_policy(self.policy);
+        let value = read_from_provider(&mut provider)?;
+
+        let mut sources = BTreeMap::new();
+        let mut defs = SchemaDefinitions::new();
+        let schema = T::get_schema(&mut defs);
+        for (i, layer) in layers.iter().enumerate() {
+            let tree = read_lines(layer).ok().and_then(|x| SyntaxTree::parse(&x, &layer.to_string_lossy()).ok());
+            if let Some(tree) = tree {
+                /* A file is implicitly wrapped in {} */
+                let root = Node::Group(Group { delimiter: Delimiter::Brace, entries: tree.entries, trailing: tree.trailing });
+                SourceRecorder { defs: &defs, layer: i, policy: self.policy, sources: &mut sources }.value(&[&root], &schema, "");
+            }
+        }
+
+        return Ok(Layered { value, layers, skipped, sources });
+    }
+}
+
+impl Default for LayeredConfig {
+    fn default() -> Self {
+        return LayeredConfig::new();
+    }
+}
+
+/// The nodes of an entry, without comments
+fn entry_nodes(entry: &Entry) -> Vec<&Node> {
+    return entry.nodes.iter().filter(|x| !matches!(**x, Node::Trivia(_))).collect();
+}
+
+/// Records the layer of every value in a layer, see Layered::sources
+struct SourceRecorder<'a> {
+    defs: &'a SchemaDefinitions,
+    layer: usize,
+    policy: OverridePolicy,
+    sources: &'a mut BTreeMap<String, Vec<usize>>,
+}
+
+impl<'a> SourceRecorder<'a> {
+    /// Record the layer for `path`. A replaced value doesn't contain anything from earlier layers
+    fn record(&mut self, path: &str) {
+        if self.policy == OverridePolicy::Replace {
+            let fields = format!("{}.", path);
+            let elements = format!("{}[", path);
+            self.sources.retain(|k, _| k != path && !k.starts_with(&fields) && !k.starts_with(&elements));
+        }
+
+        let layers = self.sources.entry(path.to_string()).or_default();
+        if layers.last() != Some(&self.layer) {
+            layers.push(self.layer);
+        }
+    }
+
+    /// Record every `key: value` in `entries`, the content of a `{}` at `path`.
+    ///
+    /// Keys are recorded by the name of their field in `fields`, also when an alias is used. Other
+    /// keys are recorded as they are written,
+    /// with `values` as schema of their value
+    fn entries(&mut self, entries: &[Entry], fields: &[SchemaField], values: &SchemaNode, path: &str) {
+        for entry in entries {
+            let nodes = entry_nodes(entry);
+            let key = match (nodes.first(), nodes.get(1)) {
+                (Some(Node::Atom(key)), Some(Node::Colon)) => key,
+                _ => { continue; },
+            };
+
+            let (name, schema) = match fields.iter().find(|x| x.name == *key || x.aliases.contains(key)) {
+                Some(field) => (field.name.as_str(), &field.schema),
+                None => (key.as_str(), values),
+            };
+
+            let path = field_path(path, name);
+            self.record(&path);
+            self.value(&nodes[2..], schema, &path);
+        }
+    }
+
+    /// Record the fields and list elements inside a value at `path`, e.g. `output.path` or
+    /// `plugins[0]`
+    fn value(&mut self, nodes: &[&Node], schema: &SchemaNode, path: &str) {
+        let group = |index: usize, delim: Delimiter| match nodes.get(index) {
+            Some(Node::Group(g)) if g.delimiter == delim && nodes.len() == index + 1 => Some(g),
+            _ => None,
+        };
+
+        match *schema {
+            SchemaNode::Optional(ref inner) => {
+                if let Some(entry) = group(1, Delimiter::Paren).and_then(|x| x.entries.first()) {
+                    self.value(&entry_nodes(entry), inner, path);
+                }
+            },
+            SchemaNode::Array { ref items, .. } => {
+                if let Some(g) = group(0, Delimiter::Bracket) {
+                    /* Merged lists are concatenated, so the elements come after those of earlier layers */
+                    let offset = (0..).take_while(|i| self.sources.contains_key(&index_path(path, *i))).count();
+                    for (i, entry) in g.entries.iter().enumerate() {
+                        let path = index_path(path, offset + i);
+                        self.record(&path);
+                        self.value(&entry_nodes(entry), items, &path);
+                    }
+                }
+            },
+            SchemaNode::Struct { ref fields, .. } => {
+                if let Some(g) = group(0, Delimiter::Brace) {
+                    self.entries(&g.entries, fields, &SchemaNode::Any, path);
+                }
+            },
+            SchemaNode::Map { ref value, .. } => {
+                if let Some(g) = group(0, Delimiter::Brace) {
+                    self.entries(&g.entries, &[], value, path);
+                }
+            },
+            SchemaNode::Enum { ref variants, .. } => {
+                let variant = match nodes.first() {
+                    Some(Node::Atom(name)) => variants.iter().find(|x| x.name == *name),
+                    _ => None,
+                };
+                if let (Some(VariantFields::Struct(fields)), Some(g)) = (variant.map(|x| &x.fields), group(1, Delimiter::Brace)) {
+                    self.entries(&g.entries, fields, &SchemaNode::Any, path);
+                }
+            },
+            SchemaNode::Ref(ref name) => {
+                let defs = self.defs;
+                if let Some(schema) = defs.get(name) {
+                    self.value(nodes, schema, path);
+                }
+            },
+            _ => {
+                /* Without a schema, keys are recorded as they are written */
+                if let Some(g) = group(0, Delimiter::Brace) {
+                    self.entries(&g.entries, &[], &SchemaNode::Any, path);
+                }
+            },
+        }
+    }
+}
+
+impl<T> Layered<T> {
+    /// The layers a field was set in, e.g. `output.path` or `plugins[0]`. This is more than one
+    /// for merged values, and empty for fields that weren't set (and use their default)
+    pub fn sources(&self, path: &str) -> Vec<&Path> {
+        return match self.sources.get(path) {
+            Some(x) => x.iter().map(|i| self.layers[*i].as_path()).collect(),
+            None => Vec::new(),
+        };
+    }
+
+    /// The last layer a field was set in, see sources
+    pub fn source(&self, path: &str) -> Option<&Path> {
+        return self.sources(path).pop();
+    }
+}
@@ -4,16 +4,34 @@ extern crate rs_config_derive;
 
 mod provider;
 mod parsetmp;
+mod diagnostic;
 mod implementations;
+pub mod formatter;
+pub mod schema;
+pub mod overrides;
+pub mod layers;
+pub mod watch;
+pub mod diff;
+pub mod validate;
 
 use std::collections::HashSet;
 
 pub use provider::ConfigProvider;
-pub use provider::provider_from_file;
+pub use provider::{provider_from_file, provider_from_file_wrap};
 pub use parsetmp::ParseTmp;
+pub use diagnostic::{Diagnostic, Mark, Severity, Span};
+pub use schema::{Schema, SchemaDefinitions, SchemaNode};
+pub use implementations::ip::{IpNet, Ipv4Net, Ipv6Net};
+pub use implementations::path::{check_paths, PathChecks, PathValue};
+pub use implementations::units::ByteSize;
+pub use layers::{Layered, LayeredConfig};
+pub use watch::ConfigWatcher;
+pub use diff::Change;
+pub use overrides::{apply_checked, apply_env_overrides, apply_set_overrides, apply_value, OverridePath, OverridePolicy};
 
+use std::fmt;
 use std::io::Write;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 #[derive(Debug, PartialEq, Eq)]
 /// The error type used by config-rs.
@@ -35,6 +53,53 @@ impl std::convert::From<String> for ParseError {
     fn from(_: String) -> Self { ParseError::Final }
 }
 
+#[derive(Debug)]
+/// The error returned when reading an entire config fails.
+pub enum ConfigError {
+    /// The config file (or an included file) couldn't be read
+    Io {
+        path: PathBuf,
+        error: std::io::Error,
+    },
+    /// The config was read, but didn't follow the expected format
+    Parse {
+        /// Everything reported while parsing, in order
+        diagnostics: Vec<Diagnostic>,
+        /// The expected format (see ConfigAble::get_format_str), if known
+        format: Option<String>,
+    },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ConfigError::Io { ref path, ref error } => {
+                write!(f, "Couldn't read {}: {}", path.display(), error)
+            },
+            ConfigError::Parse { ref diagnostics, ref format } => {
+                for diag in diagnostics {
+                    writeln!(f, "{}", diag)?;
+                }
+
+                if let Some(ref format) = *format {
+                    write!(f, "Expected format:\n{}", format)?;
+                }
+
+                return Ok(());
+            },
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match *self {
+            ConfigError::Io { ref error, .. } => Some(error),
+            ConfigError::Parse { .. } => None,
+        }
+    }
+}
+
 pub trait ConfigAble
     where Self: std::marker::Sized {
 
@@ -52,6 +117,12 @@ pub trait ConfigAble
     /// Get a name of this type
     fn get_name() -> &'static str;
 
+    /* Internal function. Used by get_format to only append the format of a type once */
+    /// Get the key the format of this type is deduplicated by
+    ///
+    /// This is the name of the type, unless several types share that name.
+    fn get_format_key() -> String { Self::get_name().to_string() }
+
     /// Get the format that will be parsed as String.
     ///
     /// See get_format
@@ -62,39 +133,272 @@ pub trait ConfigAble
         return ret;
     }
 
+    /// Internal function for building the schema
+    ///
+    /// Derived types add their definition to `defs` (once) and return a reference to it.
+    /// Types that don't describe themselves match anything.
+    /// # Arguments
+    /// * `defs`: The definitions of all named types used so far
+    fn get_schema(_defs: &mut SchemaDefinitions) -> SchemaNode { SchemaNode::Any }
+
+    /// Get the machine readable schema of this type.
+    ///
+    /// See Schema::to_json_schema to export it as JSON Schema
+    fn schema() -> Schema {
+        let mut definitions = SchemaDefinitions::new();
+        let root = Self::get_schema(&mut definitions);
+        return Schema { name: Self::get_name().to_string(), root, definitions };
+    }
+
     /// Parse an object from a ConfigProvider.
     ///
     /// # Arguments
     /// * `provider`: The ConfigProvider providing the config lines
-    /// * `fun`: The error reporting function
+    /// * `fun`: The error reporting function. Every problem is reported as one Diagnostic
     fn parse_from<F>(provider: &mut ConfigProvider, fun: &mut F) -> Result<Self, ParseError>
-       where  F: FnMut(String);
+       where  F: FnMut(Diagnostic);
 
     /// Get a default value for this type
     fn get_default() -> Result<Self, ()>;
 
+    /// Write this value in the config syntax.
+    ///
+    /// The output is accepted by parse_from and parses back into an equal value. The default
+    /// implementation only writes the name of the type, e.g. `<u32>`, which doesn't parse. It is
+    /// there so implementations from before write_to was added keep compiling.
+    /// # Arguments
+    /// * `fun`: The function to print/append the output with
+    fn write_to<F>(&self, fun: &mut F)
+       where  F: FnMut(&str) {
+        fun("<");
+        fun(Self::get_name());
+        fun(">");
+    }
+
+    /// Write a sample value of this type for a config file, see default_config_text.
+    ///
+    /// The default implementation writes the default value. Derived structs write every field on
+    /// its own line, and comment out fields that don't have a default.
+    /// # Arguments
+    /// * `level`: The indentation level of the line the value starts on
+    /// * `fun`: The function to print/append the output with
+    ///
+    /// Returns false if nothing was written, because there is no sensible sample
+    fn write_sample<F>(_level: usize, fun: &mut F) -> bool
+       where  F: FnMut(&str) {
+        match Self::get_default() {
+            Ok(x) => {
+                x.write_to(fun);
+                return true;
+            },
+            Err(()) => {
+                return false;
+            },
+        }
+    }
+
+    /// Get a complete sample config file for this type.
+    ///
+    /// Every field is present and filled with its default. Fields without a default are commented
+    /// out, and annotated with their format.
+    fn default_config_text() -> String {
+        let mut ret = String::new();
+        if !Self::write_sample(0, &mut |x| ret.push_str(x)) {
+            write_placeholder(0, Self::get_name(), &Self::get_format_str(), &mut |x| ret.push_str(x));
+            return ret;
+        }
+
+        /* Files are wrapped in {} while reading, so don't write them for a struct */
+        if ret.starts_with("{\n") && ret.ends_with('}') {
+            let inner: Vec<&str> = ret[2 .. ret.len() - 1].lines().map(|x| x.strip_prefix(INDENT).unwrap_or(x)).collect();
+            return inner.join("\n") + "\n";
+        }
+
+        return ret + "\n";
+    }
+
+    /// Get this value in the config syntax as String.
+    ///
+    /// See write_to
+    fn to_config_string(&self) -> String {
+        let mut ret = String::new();
+        self.write_to(&mut |x| ret.push_str(x));
+        return ret;
+    }
+
     /// Try to merge an object of this type with another (in case multiple are specified in the
     /// config)
+    ///
+    /// Merges of containers go element by element, so if this fails `self` may be merged
+    /// partially. The value has to be discarded then, like parse_from and the overrides do.
     /* TODO: Add the error reporting function */
     fn merge(&mut self, _rhs: Self) -> Result<(), ()> { Err(()) }
+
+    /// Apply an override to the value at `path` inside this value, see overrides.
+    ///
+    /// The default implementation handles values without fields, and applies the override to the
+    /// entire value. Derived types and containers walk down the path.
+    /// # Arguments
+    /// * `path`: The path to the value, consumed while walking down
+    /// * `value`: The new value in the config syntax
+    /// * `policy`: Whether the new value replaces the old one or is merged into it
+    /// * `fun`: The error reporting function
+    fn apply_override<F>(&mut self, path: &mut OverridePath, value: &str, policy: OverridePolicy, fun: &mut F) -> Result<(), ParseError>
+        where F: FnMut(Diagnostic) {
+        return apply_value(self, path, value, policy, fun);
+    }
+
+    /// Report the values at `path` and below that differ between this and `new`, see diff.
+    ///
+    /// The default implementation compares the entire values by their config text. Derived types
+    /// and containers compare their fields and elements.
+    /// # Arguments
+    /// * `new`: The value to compare against
+    /// * `path`: The path to this value, see diff::field_path
+    /// * `fun`: Called for every changed value
+    fn diff_at<F>(&self, new: &Self, path: &str, fun: &mut F)
+        where F: FnMut(Change) {
+        diff::diff_value(self, new, path, fun);
+    }
+
+    /// Get the values that differ between this and `new`, e.g. `inputs[1].layer`, with their old
+    /// and new value in config syntax
+    fn diff(&self, new: &Self) -> Vec<Change> {
+        let mut ret = Vec::new();
+        self.diff_at(new, "", &mut |x| ret.push(x));
+        return ret;
+    }
 }
 
-pub fn read_or_exit<T, P: AsRef<Path>>(path: P) -> T
+/// The indentation used per level in generated config files
+const INDENT: &str = "    ";
+
+/// Get the name of a field or variant at the start of `text`. Used by derived parse_from.
+///
+/// Names are identifiers, but may contain `-` (`max-connections`)
+pub fn key_token(text: &str) -> &str {
+    let len = text.find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '-')).unwrap_or(text.len());
+    return &text[..len];
+}
+
+/// Write indentation for generated config files. Used by derived write_sample
+pub fn write_indent<F>(level: usize, fun: &mut F)
+    where F: FnMut(&str) {
+    for _ in 0..level {
+        fun(INDENT);
+    }
+}
+
+/// Write a commented out placeholder for a value without default. Used by derived write_sample
+/// # Arguments
+/// * `level`: The indentation level
+/// * `name`: What to write in front of the format. A field name and type, or just a type
+/// * `format`: The format of the type, see ConfigAble::get_format_str
+/// * `fun`: The function to print/append the output with
+pub fn write_placeholder<F>(level: usize, name: &str, format: &str, fun: &mut F)
+    where F: FnMut(&str) {
+    write_indent(level, fun);
+    fun("# ");
+    fun(name);
+    fun("\n");
+
+    for line in format.lines() {
+        write_indent(level, fun);
+        fun("#   ");
+        fun(line);
+        fun("\n");
+    }
+}
+
+/// Parse a config from an already set up ConfigProvider.
+///
+/// This makes sure the entire input was used and collects everything reported while parsing.
+/// # Arguments
+/// * `provider`: The ConfigProvider providing the config lines
+pub fn read_from_provider<T>(provider: &mut ConfigProvider) -> Result<T, ConfigError>
+    where T: ConfigAble {
+    let mut diagnostics = Vec::new();
+    let ret = T::parse_from(provider, &mut |x| diagnostics.push(x));
+
+    if let Ok(x) = ret {
+        match provider.get_next() {
+            None => { return Ok(x); },
+            Some(rest) => {
+                let len = rest.len();
+                diagnostics.push(provider.error(0, len, "Unexpected content after the config").found(rest));
+            },
+        }
+    }
+
+    return Err(ConfigError::Parse { diagnostics, format: Some(T::get_format_str()) });
+}
+
+/// Read a config file.
+///
+/// The file content doesn't need to be wrapped in {}.
+/// Unlike read_or_exit, this never exits or panics, but returns every problem to the caller.
+pub fn read_config<T, P: AsRef<Path>>(path: P) -> Result<T, ConfigError>
+    where T: ConfigAble {
+    return read_config_files(path.as_ref()).0;
+}
+
+/// Read a config file like read_config, and get every file that was read for it
+pub(crate) fn read_config_files<T>(path: &Path) -> (Result<T, ConfigError>, Vec<PathBuf>)
     where T: ConfigAble {
-    let mut provider = provider::provider_from_file_wrap(path);
+    let mut provider = match provider::provider_from_file_wrap(path) {
+        Ok(x) => x,
+        Err(ConfigError::Parse { diagnostics, .. }) => {
+            let err = ConfigError::Parse { diagnostics, format: Some(T::get_format_str()) };
+            return (Err(err), vec![path.to_path_buf()]);
+        },
+        Err(x) => { return (Err(x), vec![path.to_path_buf()]); },
+    };
+
+    let ret = read_from_provider(&mut provider);
+    return (ret, provider.files());
+}
 
-    let ret = T::parse_from(&mut provider, &mut |x| writeln!(&mut std::io::stderr(), "{}", x).unwrap());
+/// Get the content of a config file for a value.
+///
+/// This is the counterpart of read_config, so the outermost {} of a struct are left out.
+pub fn to_config_file_string<T>(val: &T) -> String
+    where T: ConfigAble {
+    let ret = val.to_config_string();
 
-    match ret {
+    /* Files are wrapped in {} while reading, so don't write them */
+    if ret.starts_with('{') && ret.ends_with('}') {
+        return format!("{}\n", &ret[1 .. ret.len() - 1]);
+    }
+
+    return ret;
+}
+
+/// Write a value into a config file that can be read back with read_config.
+pub fn write_config<T, P: AsRef<Path>>(val: &T, path: P) -> std::io::Result<()>
+    where T: ConfigAble {
+    return std::fs::write(path, to_config_file_string(val));
+}
+
+/// Read a config file, or print the problems to stderr and exit the process.
+///
+/// See read_config for a version that doesn't exit.
+pub fn read_or_exit<T, P: AsRef<Path>>(path: P) -> T
+    where T: ConfigAble {
+    match read_config(path) {
         Ok(x) => {return x;},
-        Err(_) => {
+        Err(ConfigError::Io { path, error }) => {
+            writeln!(&mut std::io::stderr(), "Couldn't read {}: {}", path.display(), error).unwrap();
+        },
+        Err(ConfigError::Parse { diagnostics, format }) => {
+            for diag in diagnostics {
+                writeln!(&mut std::io::stderr(), "{}", diag).unwrap();
+            }
             writeln!(&mut std::io::stderr(), "Failed to parse {}", T::get_name()).unwrap();
-            let mut set = std::collections::HashSet::new();
-            T::get_format(&mut set, &mut |x| write!(&mut std::io::stderr(), "{}", x).unwrap());
-
-            std::process::exit(-1);
-        }
+            write!(&mut std::io::stderr(), "{}", format.unwrap_or_default()).unwrap();
+        },
     }
+
+    std::process::exit(-1);
 }
 
 #[cfg(test)]
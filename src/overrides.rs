@@ -0,0 +1,372 @@
+//! Overrides for single values inside a parsed config, e.g. from environment variables or
+//! `--set path=value` command line arguments.
+//!
+//! An override consists of a path to the value, and the new value in the config syntax. The path
+//! follows the structure of the types: struct fields by name, elements of lists and tuples by
+//! index.
+use std;
+use ConfigAble;
+use ConfigError;
+use ConfigProvider;
+use Diagnostic;
+use Mark;
+use ParseError;
+use Span;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// How an override is applied to the existing value
+pub enum OverridePolicy {
+    /// Merge the new value into the existing one, see ConfigAble::merge
+    Merge,
+    /// Replace the existing value
+    Replace,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PathStyle {
+    /// `INPUTS_0_LAYER`, matched case insensitive
+    Env,
+    /// `inputs[0].layer` or `inputs.0.layer`
+    Dotted,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// The path to the value an override is applied to.
+///
+/// This is consumed while walking down the value, see ConfigAble::apply_override
+pub struct OverridePath {
+    /// The name of the override, e.g. the environment variable. Used for error reporting
+    source: String,
+    /// The path, without any prefix
+    path: String,
+    /// The offset of the part of the path that's not consumed yet
+    pos: usize,
+    style: PathStyle,
+}
+
+impl OverridePath {
+    /// A path in the style of environment variables, e.g. `INPUTS_0_LAYER` for `inputs[0].layer`.
+    ///
+    /// Fields are matched case insensitive, with `_` for the `-` in renamed fields. If the path
+    /// matches multiple field names (`MAX_SIZE` could be `max` or `max_size`), the longest name wins.
+    /// # Arguments
+    /// * `source`: The name of the environment variable, for error reporting
+    /// * `path`: The path, after the prefix of the application
+    pub fn env<S: Into<String>, P: Into<String>>(source: S, path: P) -> Self {
+        return OverridePath { source: source.into(), path: path.into(), pos: 0, style: PathStyle::Env };
+    }
+
+    /// A path in the style of field accesses, e.g. `inputs[0].layer` (or `inputs.0.layer`)
+    pub fn dotted<P: Into<String>>(path: P) -> Self {
+        let path = path.into();
+        return OverridePath { source: path.clone(), path, pos: 0, style: PathStyle::Dotted };
+    }
+
+    /// The name of the override, e.g. the environment variable
+    pub fn source(&self) -> &str {
+        return &self.source;
+    }
+
+    /// Whether the path is consumed completely, so the override applies to the current value
+    pub fn is_empty(&self) -> bool {
+        return self.pos == self.path.len();
+    }
+
+    /// The part of the path that was consumed already
+    pub fn consumed(&self) -> &str {
+        return &self.path[..self.pos];
+    }
+
+    /// The part of the path that's not consumed yet
+    fn rest(&self) -> &str {
+        let rest = &self.path[self.pos..];
+        return match self.style {
+            PathStyle::Dotted if self.pos > 0 => rest.strip_prefix('.').unwrap_or(rest),
+            _ => rest,
+        };
+    }
+
+    /// Consume `len` bytes of the rest, and the separator after them
+    fn advance(&mut self, len: usize) {
+        self.pos = self.path.len() - self.rest().len() + len;
+        if self.style == PathStyle::Env && self.path[self.pos..].starts_with('_') {
+            self.pos += 1;
+        }
+    }
+
+    /// Check whether the rest starts with a segment of length `len`
+    fn ends_segment(&self, len: usize) -> bool {
+        let after = &self.rest()[len..];
+        return match self.style {
+            PathStyle::Env => after.is_empty() || after.starts_with('_'),
+            PathStyle::Dotted => after.is_empty() || after.starts_with('.') || after.starts_with('['),
+        };
+    }
+
+    /// Consume a field name. Returns the index of the name in `names`, or None if the path doesn't
+    /// continue with one of them (nothing is consumed then)
+    pub fn take_field(&mut self, names: &[&str]) -> Option<usize> {
+        let mut found: Option<usize> = None;
+
+        for (i, name) in names.iter().enumerate() {
+            let matches = match self.style {
+                PathStyle::Env => self.rest().get(..name.len()).map(|x| x.eq_ignore_ascii_case(&name.replace('-', "_"))).unwrap_or(false),
+                PathStyle::Dotted => self.rest().starts_with(name),
+            };
+
+            if matches && self.ends_segment(name.len()) && found.map(|x| names[x].len() < name.len()).unwrap_or(true) {
+                found = Some(i);
+            }
+        }
+
+        if let Some(i) = found {
+            self.advance(names[i].len());
+        }
+        return found;
+    }
+
+    /// Consume an index below `len`, e.g. `0` or `[0]`. Returns None if the path doesn't continue
+    /// with such an index (nothing is consumed then)
+    pub fn take_index(&mut self, len: usize) -> Option<usize> {
+        let (skip, rest) = match self.rest().strip_prefix('[') {
+            Some(x) if self.style == PathStyle::Dotted => (1, x),
+            _ => (0, self.rest()),
+        };
+
+        let digits = rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len());
+        let index = rest[..digits].parse().ok().filter(|x| *x < len)?;
+
+        let len = match skip {
+            0 if self.ends_segment(digits) => digits,
+            1 if rest[digits..].starts_with(']') => digits + 2,
+            _ => { return None; },
+        };
+
+        self.advance(len);
+        return Some(index);
+    }
+
+    /// Create an error for this override
+    pub fn error<S: Into<String>>(&self, message: S) -> Diagnostic {
+        return Diagnostic::error(format!("Invalid override {}: {}", self.source, message.into()));
+    }
+
+    /// The location in the path, for error messages
+    pub(crate) fn location(&self) -> String {
+        return match self.consumed().trim_end_matches('_') {
+            "" => "the config".to_string(),
+            x => format!("'{}'", x),
+        };
+    }
+
+    /// Create the error for a path that doesn't continue with any of `names`
+    pub fn unknown_field(&self, names: &[&str]) -> Diagnostic {
+        let names: Vec<String> = names.iter().map(|x| match self.style {
+            PathStyle::Env => x.to_uppercase().replace('-', "_"),
+            PathStyle::Dotted => x.to_string(),
+        }).collect();
+
+        return self.error(format!("Unknown field '{}' in {}", self.rest(), self.location()))
+            .expected(format!("one of: {}", names.join(", ")))
+            .found(self.rest().to_string());
+    }
+
+    /// Create the error for a path that doesn't continue with an index below `len`
+    pub fn unknown_index(&self, len: usize) -> Diagnostic {
+        let expected = match len {
+            0 => "nothing, it's empty".to_string(),
+            x => format!("an index in 0..{}", x),
+        };
+
+        return self.error(format!("Unknown element '{}' in {}", self.rest(), self.location()))
+            .expected(expected)
+            .found(self.rest().to_string());
+    }
+
+    /// Create the error for a path that continues into a value without fields
+    pub fn no_fields(&self, name: &str) -> Diagnostic {
+        return self.error(format!("{} is a {} without fields, but the path continues with '{}'", self.location(), name, self.rest()));
+    }
+}
+
+/// Apply an override to `target`, at the end of the path. This is the default for
+/// ConfigAble::apply_override, and what derived implementations do for an empty path.
+///
+/// The value is parsed with T::parse_from, errors in it are reported with the source of the
+/// override as file name.
+pub fn apply_value<T, F>(target: &mut T, path: &OverridePath, value: &str, policy: OverridePolicy, fun: &mut F) -> Result<(), ParseError>
+    where T: ConfigAble,
+          F: FnMut(Diagnostic) {
+    if !path.is_empty() {
+        fun(path.no_fields(T::get_name()));
+        return Err(ParseError::Recoverable);
+    }
+
+    let mut provider = match ConfigProvider::try_new_with_provider(std::iter::once((1, value.to_string())), path.source().to_string()) {
+        Ok(x) => x,
+        Err(x) => {
+            fun(*x);
+            return Err(ParseError::Recoverable);
+        },
+    };
+
+    let val = T::parse_from(&mut provider, fun)?;
+    if let Some(rest) = provider.get_next() {
+        fun(provider.error(0, rest.len(), "Unexpected content after the value").found(rest));
+        return Err(ParseError::Recoverable);
+    }
+
+    match policy {
+        OverridePolicy::Replace => {
+            *target = val;
+        },
+        OverridePolicy::Merge => {
+            if target.merge(val).is_err() {
+                fun(path.error(format!("Couldn't merge {} into the value from the config", value)));
+                return Err(ParseError::Recoverable);
+            }
+        },
+    }
+
+    return Ok(());
+}
+
+/// Apply an override to a clone of `target`, which only replaces `target` if `apply` succeeds.
+///
+/// Derived implementations use this to run the checks of a field (see validate) on the result of
+/// an override, like parse_from does on the parsed value. `apply` gets the clone, the path and the
+/// location of the override to report problems at.
+pub fn apply_checked<T, F, A>(target: &mut T, path: &mut OverridePath, value: &str, fun: &mut F, apply: A) -> Result<(), ParseError>
+    where T: ConfigAble + Clone,
+          F: FnMut(Diagnostic),
+          A: FnOnce(T, &mut OverridePath, &Mark, &mut F) -> Result<T, ParseError> {
+    let copy = target.clone();
+    let span = Span { file: path.source().to_string(), line: 1, start_column: 1, end_column: value.len() + 1 };
+    *target = apply(copy, path, &Mark::new(span, Vec::new()), fun)?;
+    return Ok(());
+}
+
+/// Apply an override to the element the path continues with. Used by lists and arrays, for a path
+/// that isn't empty.
+pub fn apply_to_element<T, F>(elements: &mut [T], path: &mut OverridePath, value: &str, policy: OverridePolicy, fun: &mut F) -> Result<(), ParseError>
+    where T: ConfigAble,
+          F: FnMut(Diagnostic) {
+    match path.take_index(elements.len()) {
+        Some(i) => {
+            return elements[i].apply_override(path, value, policy, fun);
+        },
+        _ => {
+            fun(path.unknown_index(elements.len()));
+            return Err(ParseError::Recoverable);
+        },
+    }
+}
+
+/// Apply overrides from the environment. Every variable starting with `{prefix}_` overrides a
+/// value, e.g. `MYAPP_INPUTS_0_LAYER=3` sets `inputs[0].layer` for the prefix `MYAPP`.
+///
+/// The values are in the config syntax, so strings need quotes: `MYAPP_TITLE='"x"'`.
+/// Variables are applied sorted by name, every problem is reported. `value` is only changed if
+/// every variable could be applied.
+/// # Arguments
+/// * `value`: The value, usually read from a config file
+/// * `prefix`: The prefix of the variables, without the trailing `_`
+/// * `policy`: Whether the overrides replace the values or are merged into them
+pub fn apply_env_overrides<T>(value: &mut T, prefix: &str, policy: OverridePolicy) -> Result<(), ConfigError>
+    where T: ConfigAble + Clone {
+    let start = format!("{}_", prefix);
+    let mut vars: Vec<(String, String)> = std::env::vars_os()
+        .filter_map(|(k, v)| Some((k.into_string().ok()?, v.into_string().ok()?)))
+        .filter(|x| x.0.starts_with(&start))
+        .collect();
+    vars.sort();
+
+    let mut copy = value.clone();
+    let mut diagnostics = Vec::new();
+    for (name, val) in vars.iter() {
+        let mut path = OverridePath::env(name.as_str(), &name[start.len()..]);
+        let _ = copy.apply_override(&mut path, val, policy, &mut |x| diagnostics.push(x));
+    }
+
+    return overrides_result(value, copy, diagnostics);
+}
+
+/// Apply overrides in the form `path=value`, e.g. from `--set` command line arguments.
+///
+/// The path is written like field accesses: `inputs[0].layer=2` (or `inputs.0.layer=2`), the value
+/// is in the config syntax: `position=Global(Left)`. The overrides are applied in order, every
+/// problem is reported. `value` is only changed if every override could be applied.
+/// # Arguments
+/// * `value`: The value, usually read from a config file
+/// * `overrides`: The overrides
+/// * `policy`: Whether the overrides replace the values or are merged into them
+pub fn apply_set_overrides<T, I, S>(value: &mut T, overrides: I, policy: OverridePolicy) -> Result<(), ConfigError>
+    where T: ConfigAble + Clone,
+          I: IntoIterator<Item=S>,
+          S: AsRef<str> {
+    let mut copy = value.clone();
+    let mut diagnostics = Vec::new();
+    for x in overrides {
+        let x = x.as_ref();
+        match x.find('=') {
+            Some(i) => {
+                let mut path = OverridePath::dotted(x[..i].trim());
+                let _ = copy.apply_override(&mut path, &x[i + 1..], policy, &mut |x| diagnostics.push(x));
+            },
+            None => {
+                diagnostics.push(Diagnostic::error(format!("Invalid override {}: missing '='", x)).expected("path=value").found(x));
+            },
+        }
+    }
+
+    return overrides_result(value, copy, diagnostics);
+}
+
+/// Turn the problems of applying overrides to `copy` into the result, `value` is replaced by `copy`
+/// if there are none
+fn overrides_result<T: ConfigAble>(value: &mut T, copy: T, diagnostics: Vec<Diagnostic>) -> Result<(), ConfigError> {
+    if diagnostics.iter().any(|x| x.is_error()) {
+        return Err(ConfigError::Parse { diagnostics, format: Some(T::get_format_str()) });
+    }
+
+    *value = copy;
+    return Ok(());
+}
+
+#[cfg(test)]
+mod test {
+    use overrides::OverridePath;
+
+    #[test]
+    fn test_env_path() {
+        let mut path = OverridePath::env("APP_INPUTS_0_MAX_SIZE", "INPUTS_0_MAX_SIZE");
+        assert!(path.take_field(&["input", "inputs"]) == Some(1));
+        assert!(path.take_field(&["x"]).is_none());
+        assert!(path.take_index(1) == Some(0));
+        assert!(path.take_field(&["max", "max_size"]) == Some(1));
+        assert!(path.is_empty());
+    }
+
+    #[test]
+    fn test_env_path_renamed() {
+        let mut path = OverridePath::env("APP_MAX_CONNECTIONS", "MAX_CONNECTIONS_X");
+        assert!(path.take_field(&["max-connections"]) == Some(0));
+        assert!(path.unknown_field(&["max-connections"]).to_string().contains("expected one of: MAX_CONNECTIONS"));
+    }
+
+    #[test]
+    fn test_dotted_path() {
+        let mut path = OverridePath::dotted("inputs[12].layer");
+        assert!(path.take_field(&["inputs"]) == Some(0));
+        assert!(path.take_index(12).is_none());
+        assert!(path.take_index(13) == Some(12));
+        assert!(path.unknown_field(&["name"]).message == "Invalid override inputs[12].layer: Unknown field 'layer' in 'inputs[12]'");
+        assert!(path.take_field(&["lay", "layer"]) == Some(1));
+        assert!(path.is_empty());
+
+        let mut path = OverridePath::dotted("a.1");
+        assert!(path.take_field(&["a"]) == Some(0));
+        assert!(path.take_index(2) == Some(1));
+        assert!(path.is_empty());
+    }
+}
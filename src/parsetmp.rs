@@ -1,6 +1,7 @@
 use ::ConfigAble;
 use ::ConfigProvider;
 use ::ParseError;
+use ::Diagnostic;
 
 #[derive(Debug, PartialEq, Eq)]
 /// Helper enum for saving named field parser state
@@ -30,7 +31,7 @@ impl<T> ParseTmp<T> {
     /// # Arguments
     /// * `name`: The name of the field
     pub fn new(name: String) -> Self {
-        return Self { value: ParseTmpI::Empty, name: name };
+        return Self { value: ParseTmpI::Empty, name };
     }
 
     /// Set the default value from the attribute
@@ -47,9 +48,9 @@ impl<T> ParseTmp<T>
     /// # Arguments
     /// * `rhs`: The value found while parsing
     /// * `provider`: The ConfigProvider currently in use. This is required for error reporting
-    /// * `fun`: The error reporting function. Most likely either printing, or appending to string.
+    /// * `fun`: The error reporting function. Most likely either printing, or collecting.
     pub fn push_found<F>(&mut self, rhs: Result<T, ParseError>, provider: &ConfigProvider, fun: &mut F) -> Result<(), ParseError>
-        where F: FnMut(String) {
+        where F: FnMut(Diagnostic) {
         match rhs {
             Ok(val) => {
                 /* ARGH! Borrow checker be smarter ! */
@@ -60,11 +61,13 @@ impl<T> ParseTmp<T>
                     &mut ParseTmpI::Failed => {
                         return Ok(());
                     },
+                    &mut ParseTmpI::Found(_) if provider.duplicate_policy() == ::OverridePolicy::Replace => {
+                        ParseTmpI::Found(val)
+                    },
                     &mut ParseTmpI::Found(ref mut x) => {
                         match x.merge(val) {
                             Err(()) => {
-                                provider.print_error(0, fun);
-                                fun(format!("Couldn't merge {}.", self.name));
+                                fun(provider.error(0, 0, format!("Couldn't merge {}.", self.name)));
                                 ParseTmpI::Failed
                             },
                             _ => { return Ok(()); },
@@ -77,7 +80,7 @@ impl<T> ParseTmp<T>
                 return Ok(());
             },
             Err(ParseError::Recoverable) => {
-                fun(format!("Tried to push Recoverable error for {}. Will continue", self.name));
+                fun(provider.error(0, 0, format!("Tried to push Recoverable error for {}. Will continue", self.name)).with_severity(::Severity::Note));
                 self.value = ParseTmpI::Failed;
                 return Ok(());
             },
@@ -95,9 +98,10 @@ impl<T> ParseTmp<T>
     /// * Type default
     /// * ParseError
     /// # Arguments
+    /// * `provider`: The ConfigProvider currently in use. This is required for error reporting
     /// * `fun`: The error reporting function
-    pub fn get_value<F>(self, fun: &mut F) -> Result<T, ParseError>
-        where F: FnMut(String) {
+    pub fn get_value<F>(self, provider: &ConfigProvider, fun: &mut F) -> Result<T, ParseError>
+        where F: FnMut(Diagnostic) {
         match self.value {
             ParseTmpI::Found(x) => Ok(x),
             ParseTmpI::Default(x) => Ok(x),
@@ -105,13 +109,13 @@ impl<T> ParseTmp<T>
                 match T::get_default() {
                     Ok(x) => Ok(x),
                     Err(_) => {
-                        fun(format!("Couldn't default {}. You need to provide a value", self.name));
+                        fun(provider.error(0, 0, format!("Couldn't default {}. You need to provide a value", self.name)));
                         return Err(ParseError::Recoverable);
                     }
                 }
             },
             ParseTmpI::Failed => {
-                fun(format!("Can't get a value for {} since something failed.", self.name));
+                fun(provider.error(0, 0, format!("Can't get a value for {} since something failed.", self.name)).with_severity(::Severity::Note));
                 return Err(ParseError::Recoverable);
             },
         }
@@ -121,24 +125,54 @@ impl<T> ParseTmp<T>
 
 #[cfg(test)]
 mod test {
+    use ConfigProvider;
     use ParseError;
     use ParseTmp;
 
     #[test]
     fn parsetmp_get_error() {
-        assert!(ParseTmp::<String>::new("TestField".into()).get_value(&mut |_| {}) == Err(ParseError::Recoverable));
+        let provider = ConfigProvider::new_from_str("");
+        let mut errors = Vec::new();
+        assert!(ParseTmp::<String>::new("TestField".into()).get_value(&provider, &mut |x| errors.push(x)) == Err(ParseError::Recoverable));
+        assert!(errors.len() == 1 && errors[0].is_error());
+        assert!(errors[0].span.as_ref().map(|x| x.file.as_str()) == Some("memory"));
     }
 
     #[test]
     fn parsetmp_get_none() {
-        assert!(ParseTmp::<Option<String>>::new("TestField".into()).get_value(&mut |_| {}) == Ok(None));
+        let provider = ConfigProvider::new_from_str("");
+        assert!(ParseTmp::<Option<String>>::new("TestField".into()).get_value(&provider, &mut |_| {}) == Ok(None));
     }
 
     #[test]
     fn parsetmp_get_default() {
+        let provider = ConfigProvider::new_from_str("");
         let mut field = ParseTmp::<String>::new("TestField".into());
         field.set_default("TestStr".into());
-        assert!(field.get_value(&mut |_| {}) == Ok("TestStr".into()));
+        assert!(field.get_value(&provider, &mut |_| {}) == Ok("TestStr".into()));
+    }
+
+    #[test]
+    fn parsetmp_merge_error() {
+        let provider = ConfigProvider::new_from_str("");
+        let mut errors = Vec::new();
+        let mut field = ParseTmp::<char>::new("TestField".into());
+        field.push_found(Ok('a'), &provider, &mut |x| errors.push(x)).unwrap();
+        field.push_found(Ok('b'), &provider, &mut |x| errors.push(x)).unwrap();
+
+        assert!(errors.len() == 1);
+        assert!(errors[0].message == "Couldn't merge TestField.");
+    }
+
+    #[test]
+    fn parsetmp_replace() {
+        let mut provider = ConfigProvider::new_from_str("");
+        provider.set_duplicate_policy(::OverridePolicy::Replace);
+
+        let mut field = ParseTmp::<char>::new("TestField".into());
+        field.push_found(Ok('a'), &provider, &mut |_| {}).unwrap();
+        field.push_found(Ok('b'), &provider, &mut |_| {}).unwrap();
+        assert!(field.get_value(&provider, &mut |_| {}) == Ok('b'));
     }
 
     //TODO: MAke more tests!
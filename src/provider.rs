@@ -1,10 +1,12 @@
 use std;
 use ParseError;
+use ConfigError;
+use diagnostic::{Diagnostic, Mark, Span};
+use overrides::OverridePolicy;
 
 use std::fs::File;
-use std::io::BufRead;
-use std::io::BufReader;
-use std::path::Path;
+use std::io::Read;
+use std::path::{Path, PathBuf};
 
 //#[derive(Debug)]
 /// The main struct that will provide the config lines.
@@ -15,8 +17,17 @@ pub struct ConfigProvider {
     line: usize,
     column: usize,
     line_str: String,
-    line_it: Box<std::iter::Iterator<Item=(usize, String)>>,
+    line_it: Box<dyn std::iter::Iterator<Item=(usize, String)>>,
     child: Option<Box<ConfigProvider>>,
+    /// The directory of the file, see base_dir
+    dir: Option<PathBuf>,
+    resolve_paths: bool,
+    /// Whether `${VAR}` in string literals is replaced, see set_interpolation
+    interpolate: bool,
+    /// How fields given multiple times are combined, see set_duplicate_policy
+    duplicates: OverridePolicy,
+    /// The files read so far, see files
+    files: Vec<PathBuf>,
 }
 
 impl ConfigProvider {
@@ -32,88 +43,223 @@ impl ConfigProvider {
         }
 
         /* TODO: Avoid the copy here */
-        unsafe {
-            let slice = self.line_str.slice_unchecked(self.column, self.line_str.len());
-            return Some(String::from(slice));
-        }
+        return Some(String::from(&self.line_str[self.column..]));
     }
 
     /// Get the next char of the config
     pub fn peek_char(&self) -> Option<char> {
-        self.line_str.chars().nth(self.column)
+        if let Some(ref child) = self.child {
+            return child.peek_char();
+        }
+
+        self.line_str[self.column..].chars().next()
     }
 
 
-    /// Print an error with current file, line and offset
+    /// Get the location of the upcoming text
     /// # Arguments
     /// * `index`: The offset from the current internal offset (equal to offset in string gotten by
-    /// get_next()
-    /// * `fun`: The error reporting function
-    pub fn print_error<F>(&self, index: usize, fun: &mut F)
-        where F: FnMut(String) {
+    ///   get_next()
+    /// * `len`: The length of the text the location should cover
+    pub fn span(&self, index: usize, len: usize) -> Span {
         if let Some(ref child) = self.child {
-            child.print_error(index, fun);
-            fun("Included from: ".to_string());
+            return child.span(index, len);
+        }
+
+        return Span {
+            file: self.file.clone(),
+            line: self.line,
+            start_column: self.column + index + 1,
+            end_column: self.column + index + len + 1,
+        };
+    }
+
+    /// Get the chain of `!include` lines that lead to the file currently read, innermost first
+    pub fn include_chain(&self) -> Vec<Span> {
+        match self.child {
+            Some(ref child) => {
+                let mut ret = child.include_chain();
+                ret.push(Span { file: self.file.clone(), line: self.line, start_column: 1, end_column: self.line_str.len() + 1 });
+                return ret;
+            },
+            None => {
+                return Vec::new();
+            },
         }
-        fun(format!("Encountered error in {}:{},{}", self.file, self.line, self.column + index + 1));
+    }
+
+    /// Remember the current location, to report an error at it later
+    /// # Arguments
+    /// * `index`: The offset from the current internal offset
+    /// * `len`: The length of the text the location should cover
+    pub fn mark(&self, index: usize, len: usize) -> Mark {
+        return Mark::new(self.span(index, len), self.include_chain());
+    }
+
+    /// Create an error with current file, line and offset
+    /// # Arguments
+    /// * `index`: The offset from the current internal offset (equal to offset in string gotten by
+    ///   get_next()
+    /// * `len`: The length of the offending text
+    /// * `message`: The error message
+    pub fn error<S: Into<String>>(&self, index: usize, len: usize, message: S) -> Diagnostic {
+        return self.mark(index, len).error(message);
     }
 
     /// This will be true if there's no more config to read
     pub fn is_at_end(&self) -> bool {
+        if let Some(ref child) = self.child {
+            return child.is_at_end();
+        }
+
         return self.column == self.line_str.len();
     }
 
+    /// The directory relative paths in the current file are resolved against.
+    ///
+    /// This is the directory of the innermost (`!include`d) file. None for configs that don't come
+    /// from a file, unless set with set_base_dir.
+    pub fn base_dir(&self) -> Option<&Path> {
+        if let Some(ref child) = self.child {
+            return child.base_dir();
+        }
+
+        return self.dir.as_deref();
+    }
+
+    /// Set the directory relative paths are resolved against, e.g. for a config from memory
+    pub fn set_base_dir<P: Into<PathBuf>>(&mut self, dir: P) {
+        self.dir = Some(dir.into());
+    }
+
+    /// Whether relative paths are resolved against base_dir
+    pub fn path_resolution(&self) -> bool {
+        return self.resolve_paths;
+    }
+
+    /// Enable or disable resolving relative paths against base_dir. This is on by default.
+    ///
+    /// Derived parse_from turns it off for fields that have to be `absolute`, so relative paths
+    /// are reported instead of resolved.
+    pub fn set_path_resolution(&mut self, enabled: bool) {
+        self.resolve_paths = enabled;
+    }
+
+    /// The files this config was read from so far: the config file itself and every `!include`d
+    /// file, including those that couldn't be read
+    pub fn files(&self) -> Vec<PathBuf> {
+        let mut ret = self.files.clone();
+        if let Some(ref child) = self.child {
+            ret.extend(child.files());
+        }
+
+        let mut seen = std::collections::HashSet::new();
+        ret.retain(|x| seen.insert(x.clone()));
+        return ret;
+    }
+
+    /// Drop the `!include`d provider once it's done, but remember which files it read
+    fn close_child(&mut self) {
+        if let Some(child) = self.child.take() {
+            self.files.extend(child.files());
+        }
+    }
+
+    /// Whether `${VAR}` and `${VAR:-default}` in string (and path) literals are replaced by the
+    /// environment variable `VAR`
+    pub fn interpolation(&self) -> bool {
+        return self.interpolate;
+    }
+
+    /// Enable or disable the replacement of `${VAR}` and `${VAR:-default}` in string (and path)
+    /// literals. This is off by default, and applies to `!include`d files as well.
+    ///
+    /// `$${` is written as a plain `${`.
+    pub fn set_interpolation(&mut self, enabled: bool) {
+        self.interpolate = enabled;
+    }
+
+    /// How struct fields that are given multiple times are combined
+    pub fn duplicate_policy(&self) -> OverridePolicy {
+        return self.duplicates;
+    }
+
+    /// Set how struct fields that are given multiple times are combined. By default they are
+    /// merged (see ConfigAble::merge), with OverridePolicy::Replace the last one wins.
+    pub fn set_duplicate_policy(&mut self, policy: OverridePolicy) {
+        self.duplicates = policy;
+    }
+
     pub fn new_from_str<S: Into<String>>(line: S) -> ConfigProvider {
-        return ConfigProvider::new_with_provider(Some((0, line.into())).into_iter(), "memory".to_string());
+        return ConfigProvider::new_with_provider(Some((1, line.into())).into_iter(), "memory".to_string());
     }
 
     /// Skip the current line. E.g. when a comment, or only whitespace left
-    fn skip_current(&mut self) -> Result<(), String> {
+    fn skip_current(&mut self) -> Result<(), Box<Diagnostic>> {
         self.column = self.line_str.len();
         return self.get_next_line();
     }
 
     /// Skip the upcomming list of whitespaces. This will be called by every consume
-    fn skip_whitespace(&mut self) -> Result<(), String> {
-        unsafe {
-            let pos = self.line_str.slice_unchecked(self.column, self.line_str.len()).find(|c: char| !c.is_whitespace());
+    fn skip_whitespace(&mut self) -> Result<(), Box<Diagnostic>> {
+        let pos = self.line_str[self.column..].find(|c: char| !c.is_whitespace());
 
-            match pos {
-                Some(0) => {},
-                Some(x) => {
-                    self.consume(x, &mut |_| {}).unwrap();
-                },
-                None => {
-                    self.skip_current()?;
-                },
-            }
+        match pos {
+            Some(0) => {},
+            Some(x) => {
+                self.consume(x, &mut |_| {}).unwrap();
+            },
+            None => {
+                self.skip_current()?;
+            },
         }
 
         return Ok(());
     }
 
     /// Handle a special line. Marked by starting with !
-    fn handle_special(&mut self) -> Result<(), String> {
+    fn handle_special(&mut self) -> Result<(), Box<Diagnostic>> {
         /* This is guaranteed to return Some, or handle_special wouldn't be called */
         let line = self.get_next().unwrap();
 
-        if line.starts_with("!include ") {
-            match line.split(' ').nth(1) {
+        if let Some(rest) = line.strip_prefix("!include ") {
+            /* The rest of the line is the path, which may contain spaces */
+            match Some(rest.trim()).filter(|x| !x.is_empty()) {
                 Some(x) => {
-                    self.child = Some(Box::new(provider_from_file(x)));
-                    return Ok(());
+                    /* Unlike the paths in the config, includes are opened as written */
+                    let path = PathBuf::from(x);
+
+                    self.files.push(path.clone());
+                    match provider_from_file(&path) {
+                        Ok(child) => {
+                            let at_end = child.is_at_end();
+                            self.child = Some(Box::new(child));
+                            if at_end {
+                                self.close_child();
+                                return self.skip_current();
+                            }
+
+                            return Ok(());
+                        },
+                        Err(ConfigError::Io { error, .. }) => {
+                            return Err(Box::new(self.error(0, line.len(), format!("Couldn't include {}: {}", x, error))));
+                        },
+                        Err(ConfigError::Parse { mut diagnostics, .. }) => {
+                            return Err(Box::new(diagnostics.remove(0)));
+                        },
+                    }
                 },
                 None => {
-                    return Err("Found !include, but couldn't figure out which file to include".to_string());
+                    return Err(Box::new(self.error(0, line.len(), "Found !include, but couldn't figure out which file to include")));
                 },
             }
         }
 
-        return Err(format!("Failed while parsing config. Found special line: {}, which I can't handle.", line));
+        return Err(Box::new(self.error(0, line.len(), "Failed while parsing config. Found special line, which I can't handle.").found(line)));
     }
 
     /// Read the next line from 
-    fn get_next_line(&mut self) -> Result<(), String> {
+    fn get_next_line(&mut self) -> Result<(), Box<Diagnostic>> {
         if let Some(ref mut child) = self.child {
             child.get_next_line()?;
             if !child.is_at_end() {
@@ -123,7 +269,7 @@ impl ConfigProvider {
         /* This should be part of the previous if, but the borrow checker doesn't allow that (yet)
          * since child is borrowed there
          */
-        self.child = None;
+        self.close_child();
 
         if let Some(line) = self.line_it.next() {
             self.line_str = line.1;
@@ -149,19 +295,44 @@ impl ConfigProvider {
     /// # Arguments
     /// * `it`: The line iterator
     /// * `file`: The file name (should be a global path)
+    ///
+    /// This panics if the first line can't be handled (e.g. a broken `!include`). Use
+    /// try_new_with_provider to get the error instead.
     pub fn new_with_provider<J>(it: J, file: String) -> Self
         where J: std::iter::Iterator<Item=(usize, String)> + 'static {
-        let mut ret = ConfigProvider { file: file,
+        return ConfigProvider::try_new_with_provider(it, file).unwrap();
+    }
+
+    /// Get a ConfigProvider form a line iterator enumerator.
+    ///
+    /// This fails if the first line can't be handled (e.g. a broken `!include`).
+    /// # Arguments
+    /// * `it`: The line iterator
+    /// * `file`: The file name (should be a global path)
+    pub fn try_new_with_provider<J>(it: J, file: String) -> Result<Self, Box<Diagnostic>>
+        where J: std::iter::Iterator<Item=(usize, String)> + 'static {
+        return ConfigProvider::new_in_dir(it, file, None);
+    }
+
+    /// Create a provider with a base directory. This is needed before reading the first line,
+    /// since that may already be an `!include`
+    fn new_in_dir<J>(it: J, file: String, dir: Option<PathBuf>) -> Result<Self, Box<Diagnostic>>
+        where J: std::iter::Iterator<Item=(usize, String)> + 'static {
+        let mut ret = ConfigProvider { file,
             line: 1, column: 0,
             line_str: String::new(),
             line_it: Box::new(it),
             child: None,
+            dir,
+            resolve_paths: true,
+            interpolate: false,
+            duplicates: OverridePolicy::Merge,
+            files: Vec::new(),
         };
 
-        //TODO: Bother to change this into propagation
-        ret.get_next_line().unwrap();
+        ret.get_next_line()?;
 
-        return ret;
+        return Ok(ret);
     }
 
     /// Consume a single character if it's the upcoming char, otherwise return error
@@ -169,14 +340,18 @@ impl ConfigProvider {
     /// * `c`: The character to skip
     /// * `fun`: The error reporting function
     pub fn consume_char<F>(&mut self, c: char, fun: &mut F) -> Result<(), ParseError>
-        where F: FnMut(String){
-        if self.line_str.chars().nth(self.column) == Some(c) {
+        where F: FnMut(Diagnostic) {
+        let found = self.peek_char();
+        if found == Some(c) {
             self.consume(1, fun)?;
             return Ok(());
         }
 
-        self.print_error(0, fun);
-        fun(format!("Tried to consume {}", c));
+        let diag = self.error(0, 1, format!("Tried to consume {}", c)).expected(format!("'{}'", c));
+        fun(match found {
+            Some(x) => diag.found(format!("'{}'", x)),
+            None => diag.found("end of file"),
+        });
         return Err(ParseError::Final);
     }
 
@@ -188,66 +363,108 @@ impl ConfigProvider {
     /// * `count`: The number of characters to consume
     /// * `fun`: The error reporting function
     pub fn consume<F>(&mut self, count: usize, fun: &mut F) -> Result<(), ParseError>
-        where F: FnMut(String) {
+        where F: FnMut(Diagnostic) {
+        if let Some(ref mut child) = self.child {
+            child.consume(count, fun)?;
+            if !child.is_at_end() {
+                return Ok(());
+            }
+        }
+
+        if self.child.is_some() {
+            /* The included file is done, continue after the !include line */
+            self.close_child();
+            if let Err(x) = self.skip_current() {
+                fun(*x);
+                return Err(ParseError::Final);
+            }
+            return Ok(());
+        }
+
         if self.column + count > self.line_str.len() {
-            self.print_error(0, fun);
-            fun(format!("Tried to consume more than currently available: {}", count));
+            fun(self.error(0, 0, format!("Tried to consume more than currently available: {}", count)));
             return Err(ParseError::Final);
         }
 
-        self.column = self.column + count;
+        self.column += count;
 
-        match self.skip_whitespace() {
-            Err(x) => {
-                self.print_error(0, fun);
-                fun(x);
-                return Err(ParseError::Final);
-            },
-            Ok(()) => {},
+        if let Err(x) = self.skip_whitespace() {
+            fun(*x);
+            return Err(ParseError::Final);
         }
 
         if self.column == self.line_str.len() {
-            self.get_next_line()?;
+            if let Err(x) = self.get_next_line() {
+                fun(*x);
+                return Err(ParseError::Final);
+            }
         }
 
         return Ok(());
     }
 }
 
+/// Read all lines of a file, numbered from 1.
+///
+/// The file is read at once, so I/O errors (including invalid UTF-8) are reported up front
+/// instead of in the middle of parsing.
+pub(crate) fn read_lines(path: &Path) -> Result<Vec<(usize, String)>, ConfigError> {
+    let mut content = String::new();
+    let res = File::open(path).and_then(|mut f| f.read_to_string(&mut content));
+
+    if let Err(error) = res {
+        return Err(ConfigError::Io { path: path.to_path_buf(), error });
+    }
+
+    return Ok(content.lines().enumerate().map(|(i, x)| (i + 1, x.to_string())).collect());
+}
+
+/// Build the provider for a file from its lines and map errors into ConfigError
+fn provider_from_lines<J>(lines: J, path: &Path) -> Result<ConfigProvider, ConfigError>
+    where J: std::iter::Iterator<Item=(usize, String)> + 'static {
+    let path_str = path.to_str().unwrap_or("ERROR");
+
+    /* Make the directory absolute, so resolved paths stay valid if the working directory changes */
+    let dir = path.parent().map(|x| match std::env::current_dir() {
+        Ok(ref cwd) if !x.is_absolute() => cwd.join(x),
+        _ => x.to_path_buf(),
+    });
+
+    let mut ret = ConfigProvider::new_in_dir(lines, path_str.into(), dir)
+        .map_err(|x| ConfigError::Parse { diagnostics: vec![*x], format: None })?;
+    ret.files.insert(0, path.to_path_buf());
+
+    return Ok(ret);
+}
+
 /// Get a provider from a single file.
 /// This is used to build the nesting providers
-pub fn provider_from_file<P: AsRef<Path>>(path: P) -> ConfigProvider {
+pub fn provider_from_file<P: AsRef<Path>>(path: P) -> Result<ConfigProvider, ConfigError> {
     let p = path.as_ref();
-    let f = File::open(p).unwrap();
-
-    let lines = BufReader::new(f).lines().map(|x| x.unwrap()).enumerate();
-
-    let path_str = p.to_str().unwrap_or_else(|| "ERROR");
+    let lines = read_lines(p)?;
 
-    return ConfigProvider::new_with_provider(lines, path_str.into());
+    return provider_from_lines(lines.into_iter(), p);
 }
 
 /// Get a provider for a single file, and wrap it in {}, so the final config doesn't have to be in
 /// an initial {} wrapper.
-pub fn provider_from_file_wrap<P: AsRef<Path>>(path: P) -> ConfigProvider {
+pub fn provider_from_file_wrap<P: AsRef<Path>>(path: P) -> Result<ConfigProvider, ConfigError> {
     let p = path.as_ref();
-    let f = File::open(p).unwrap();
 
     let open = std::iter::once((0, "{".into()));
-    let lines = BufReader::new(f).lines().map(|x| x.unwrap()).enumerate();
-    let close = std::iter::once((usize::max_value(), "}".into()));
+    let lines = read_lines(p)?;
+    let close = std::iter::once((usize::MAX, "}".into()));
 
     let fin = open.chain(lines).chain(close);
 
-    let path_str = p.to_str().unwrap_or_else(|| "ERROR");
-
-    return ConfigProvider::new_with_provider(fin, path_str.into());
+    return provider_from_lines(fin, p);
 }
 
 
 #[cfg(test)]
 mod test {
     use ConfigProvider;
+    use diagnostic::Span;
 
     #[test]
     fn test_config_provider_string() {
@@ -259,7 +476,7 @@ mod test {
         assert!(provider.get_next() == Some("is a line".to_string()));
 
         provider.consume(9, &mut |_| {}).unwrap();
-        assert!(provider.get_next() == None);
+        assert!(provider.get_next().is_none());
     }
 
     #[test]
@@ -273,28 +490,21 @@ mod test {
         assert!(provider.get_next() == Some("Line2".to_string()));
 
         provider.consume(5, &mut |_| {}).unwrap();
-        assert!(provider.get_next() == None);
+        assert!(provider.get_next().is_none());
     }
 
     #[test]
     fn test_config_provider_err_str() {
         let lines = vec![(1, "Line1   \n".to_string()), (2, "  Line2".to_string())];
         let mut provider = ConfigProvider::new_with_provider(lines.into_iter(), "Testfile".to_string());
-        let mut val = String::new();
 
-        {
-            let mut fun = |x| val = x;
-            provider.print_error(2, &mut fun);
-        }
-        assert!(val == "Encountered error in Testfile:1,3");
+        let diag = provider.error(2, 1, "Test");
+        assert!(diag.to_string() == "error: Test\n  --> Testfile:1,3");
 
         provider.consume(5, &mut |_| {}).unwrap();
 
-        {
-            let mut fun = |x| val = x;
-            provider.print_error(1, &mut fun);
-        }
-        assert!(val == "Encountered error in Testfile:2,4");
+        let span = provider.span(1, 2);
+        assert!(span == Span { file: "Testfile".into(), line: 2, start_column: 4, end_column: 6 });
     }
 
     #[test]
@@ -305,7 +515,7 @@ mod test {
         assert!(provider.get_next() == Some("line".to_string()));
         provider.consume(4, &mut |_| {}).unwrap();
 
-        assert!(provider.get_next() == None);
+        assert!(provider.get_next().is_none());
     }
 
     #[test]
@@ -319,6 +529,6 @@ mod test {
         assert!(provider.get_next() == Some("#another".to_string()));
         provider.consume(8, &mut |_| {}).unwrap();
 
-        assert!(provider.get_next() == None);
+        assert!(provider.get_next().is_none());
     }
 }
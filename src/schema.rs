@@ -0,0 +1,497 @@
+use std::collections::BTreeMap;
+
+use formatter::{Delimiter, Node, SyntaxTree};
+use implementations::literals::{parse_char, str_lit};
+
+/// The definitions of named (derived) types, by name
+pub type SchemaDefinitions = BTreeMap<String, SchemaNode>;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A machine readable description of the shape of a ConfigAble type.
+///
+/// This is the structured counterpart to get_format. Derived types are stored as definitions
+/// and referenced by name, so recursive types don't recurse forever.
+pub enum SchemaNode {
+    /// Matches anything. Used as placeholder while a definition is built, and for types without
+    /// their own get_schema
+    Any,
+    /// true or false
+    Boolean,
+    /// A whole number in the given (inclusive) range
+    Integer { minimum: i128, maximum: u128 },
+    /// A floating point number
+    Number,
+    /// A quoted string (or a plain token with a well known format, like an IP address)
+    String {
+        /// The name of a well known format, e.g. "ipv4"
+        format: Option<String>,
+        min_length: Option<usize>,
+        max_length: Option<usize>,
+    },
+    /// `Some(T)` or `None`
+    Optional(Box<SchemaNode>),
+    /// `[T, T, ...]`
+    Array {
+        items: Box<SchemaNode>,
+        min_items: Option<usize>,
+        max_items: Option<usize>,
+    },
+    /// `[T, T, ...]` without duplicates
+    Set(Box<SchemaNode>),
+    /// `(A, B, ...)`
+    Tuple(Vec<SchemaNode>),
+    /// `{key: value, ...}` with arbitrary keys
+    Map {
+        key: Box<SchemaNode>,
+        value: Box<SchemaNode>,
+    },
+    /// `{name: value, ...}`
+    Struct {
+        description: Option<String>,
+        fields: Vec<SchemaField>,
+    },
+    /// One of the variants
+    Enum {
+        description: Option<String>,
+        variants: Vec<SchemaVariant>,
+    },
+    /// A type stored in the definitions
+    Ref(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A named field of a struct (or struct variant)
+pub struct SchemaField {
+    pub name: String,
+    /// Other names the field is accepted by, see `alias`
+    pub aliases: Vec<String>,
+    pub description: Option<String>,
+    /// Whether the field has to be in the config. False if there is any default
+    pub required: bool,
+    /// The default value in the config syntax
+    pub default: Option<String>,
+    pub schema: SchemaNode,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// The content of an enum variant
+pub enum VariantFields {
+    Unit,
+    Tuple(Vec<SchemaNode>),
+    Struct(Vec<SchemaField>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A variant of an enum
+pub struct SchemaVariant {
+    pub name: String,
+    pub description: Option<String>,
+    pub fields: VariantFields,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// The schema of a type with all types it uses
+pub struct Schema {
+    /// The name of the type
+    pub name: String,
+    pub root: SchemaNode,
+    pub definitions: SchemaDefinitions,
+}
+
+/// Minimal JSON value, to keep the output ordered and avoid a dependency
+enum Json {
+    Null,
+    Bool(bool),
+    /// The number as it should be printed
+    Number(String),
+    Str(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+fn write_json_str(s: &str, out: &mut String) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+impl Json {
+    fn str<S: Into<String>>(s: S) -> Self {
+        return Json::Str(s.into());
+    }
+
+    fn write(&self, level: usize, out: &mut String) {
+        let indent = |level: usize, out: &mut String| {
+            for _ in 0..level {
+                out.push_str("  ");
+            }
+        };
+
+        match *self {
+            Json::Null => out.push_str("null"),
+            Json::Bool(x) => out.push_str(if x { "true" } else { "false" }),
+            Json::Number(ref x) => out.push_str(x),
+            Json::Str(ref x) => write_json_str(x, out),
+            Json::Array(ref x) if x.is_empty() => out.push_str("[]"),
+            Json::Object(ref x) if x.is_empty() => out.push_str("{}"),
+            Json::Array(ref x) => {
+                out.push_str("[\n");
+                for (i, val) in x.iter().enumerate() {
+                    indent(level + 1, out);
+                    val.write(level + 1, out);
+                    out.push_str(if i + 1 < x.len() { ",\n" } else { "\n" });
+                }
+                indent(level, out);
+                out.push(']');
+            },
+            Json::Object(ref x) => {
+                out.push_str("{\n");
+                for (i, (key, val)) in x.iter().enumerate() {
+                    indent(level + 1, out);
+                    write_json_str(key, out);
+                    out.push_str(": ");
+                    val.write(level + 1, out);
+                    out.push_str(if i + 1 < x.len() { ",\n" } else { "\n" });
+                }
+                indent(level, out);
+                out.push('}');
+            },
+        }
+    }
+}
+
+/// Convert a value in the config syntax into JSON, following the schema
+fn value_to_json(nodes: &[Node], schema: &SchemaNode, defs: &SchemaDefinitions) -> Option<Json> {
+    let atom = match nodes.first() {
+        Some(Node::Atom(x)) => Some(x.as_str()),
+        _ => None,
+    };
+    let group = |index: usize, delim: Delimiter| match nodes.get(index) {
+        Some(Node::Group(g)) if g.delimiter == delim && nodes.len() == index + 1 => Some(g),
+        _ => None,
+    };
+
+    match *schema {
+        SchemaNode::Any => None,
+        SchemaNode::Boolean => match atom {
+            Some("true") => Some(Json::Bool(true)),
+            Some("false") => Some(Json::Bool(false)),
+            _ => None,
+        },
+        SchemaNode::Integer { .. } | SchemaNode::Number => {
+            /* JSON has no inf or nan */
+            let x = atom?;
+            if !x.parse::<f64>().ok()?.is_finite() {
+                return None;
+            }
+            Some(Json::Number(x.to_string()))
+        },
+        SchemaNode::String { .. } => {
+            let x = atom?;
+            if x.starts_with('"') {
+                Some(Json::Str(str_lit(x).ok()?.1))
+            } else if x.starts_with('\'') {
+                Some(Json::Str(parse_char(x).ok()?.1.to_string()))
+            } else {
+                Some(Json::str(x))
+            }
+        },
+        SchemaNode::Optional(ref inner) => match atom {
+            Some("None") if nodes.len() == 1 => Some(Json::Null),
+            Some("Some") => {
+                let g = group(1, Delimiter::Paren)?;
+                value_to_json(&g.entries.first()?.nodes, inner, defs)
+            },
+            _ => None,
+        },
+        SchemaNode::Array { ref items, .. } => {
+            let g = group(0, Delimiter::Bracket)?;
+            g.entries.iter().map(|x| value_to_json(&x.nodes, items, defs)).collect::<Option<Vec<_>>>().map(Json::Array)
+        },
+        SchemaNode::Set(ref items) => {
+            let g = group(0, Delimiter::Bracket)?;
+            g.entries.iter().map(|x| value_to_json(&x.nodes, items, defs)).collect::<Option<Vec<_>>>().map(Json::Array)
+        },
+        SchemaNode::Map { ref key, ref value } => {
+            let g = group(0, Delimiter::Brace)?;
+            let mut ret = Vec::new();
+            for entry in &g.entries {
+                if entry.nodes.get(1) != Some(&Node::Colon) {
+                    return None;
+                }
+
+                /* JSON keys are always strings */
+                let key = match value_to_json(&entry.nodes[..1], key, defs)? {
+                    Json::Str(x) | Json::Number(x) => x,
+                    _ => { return None; },
+                };
+                ret.push((key, value_to_json(&entry.nodes[2..], value, defs)?));
+            }
+            Some(Json::Object(ret))
+        },
+        SchemaNode::Tuple(ref items) => {
+            let g = group(0, Delimiter::Paren)?;
+            if g.entries.len() != items.len() {
+                return None;
+            }
+            g.entries.iter().zip(items).map(|(x, s)| value_to_json(&x.nodes, s, defs)).collect::<Option<Vec<_>>>().map(Json::Array)
+        },
+        SchemaNode::Struct { ref fields, .. } => {
+            struct_to_json(group(0, Delimiter::Brace)?, fields, defs)
+        },
+        SchemaNode::Enum { ref variants, .. } => {
+            let var = variants.iter().find(|x| Some(x.name.as_str()) == atom)?;
+            let content = match var.fields {
+                VariantFields::Unit => {
+                    return if nodes.len() == 1 { Some(Json::str(var.name.clone())) } else { None };
+                },
+                VariantFields::Tuple(ref items) => {
+                    let g = group(1, Delimiter::Paren)?;
+                    if g.entries.len() != items.len() {
+                        return None;
+                    }
+                    let mut vals = g.entries.iter().zip(items).map(|(x, s)| value_to_json(&x.nodes, s, defs)).collect::<Option<Vec<_>>>()?;
+                    if vals.len() == 1 { vals.remove(0) } else { Json::Array(vals) }
+                },
+                VariantFields::Struct(ref fields) => {
+                    struct_to_json(group(1, Delimiter::Brace)?, fields, defs)?
+                },
+            };
+            Some(Json::Object(vec![(var.name.clone(), content)]))
+        },
+        SchemaNode::Ref(ref name) => value_to_json(nodes, defs.get(name)?, defs),
+    }
+}
+
+fn struct_to_json(group: &::formatter::Group, fields: &[SchemaField], defs: &SchemaDefinitions) -> Option<Json> {
+    let mut ret = Vec::new();
+
+    for entry in &group.entries {
+        let key = match (entry.nodes.first(), entry.nodes.get(1)) {
+            (Some(Node::Atom(key)), Some(Node::Colon)) => key,
+            _ => { return None; },
+        };
+        let field = fields.iter().find(|x| x.name == *key)?;
+        ret.push((key.clone(), value_to_json(&entry.nodes[2..], &field.schema, defs)?));
+    }
+
+    return Some(Json::Object(ret));
+}
+
+/// Convert a default (in config syntax) into JSON
+fn default_to_json(text: &str, schema: &SchemaNode, defs: &SchemaDefinitions) -> Option<Json> {
+    let tree = SyntaxTree::parse_str(text, "default").ok()?;
+    if tree.entries.len() != 1 {
+        return None;
+    }
+
+    return value_to_json(&tree.entries[0].nodes, schema, defs);
+}
+
+fn fields_to_json(fields: &[SchemaField], description: Option<&String>, defs: &SchemaDefinitions) -> Json {
+    let mut ret = vec![("type".to_string(), Json::str("object"))];
+    if let Some(x) = description {
+        ret.push(("description".to_string(), Json::str(x.clone())));
+    }
+
+    let mut properties = Vec::new();
+    for field in fields {
+        let mut prop = match node_to_json(&field.schema, defs) {
+            Json::Object(x) => x,
+            _ => Vec::new(),
+        };
+        if let Some(ref x) = field.description {
+            prop.push(("description".to_string(), Json::str(x.clone())));
+        }
+        if let Some(x) = field.default.as_ref().and_then(|x| default_to_json(x, &field.schema, defs)) {
+            prop.push(("default".to_string(), x));
+        }
+        properties.push((field.name.clone(), Json::Object(prop)));
+    }
+    ret.push(("properties".to_string(), Json::Object(properties)));
+
+    let required: Vec<Json> = fields.iter().filter(|x| x.required).map(|x| Json::str(x.name.clone())).collect();
+    if !required.is_empty() {
+        ret.push(("required".to_string(), Json::Array(required)));
+    }
+    ret.push(("additionalProperties".to_string(), Json::Bool(false)));
+
+    return Json::Object(ret);
+}
+
+/// A fixed length JSON array
+fn tuple_to_json(items: &[SchemaNode], defs: &SchemaDefinitions) -> Json {
+    return Json::Object(vec![
+        ("type".to_string(), Json::str("array")),
+        ("prefixItems".to_string(), Json::Array(items.iter().map(|x| node_to_json(x, defs)).collect())),
+        ("minItems".to_string(), Json::Number(items.len().to_string())),
+        ("maxItems".to_string(), Json::Number(items.len().to_string())),
+        ("items".to_string(), Json::Bool(false)),
+    ]);
+}
+
+/// An object with exactly one key, as used for enum variants with content
+fn tagged_to_json(name: &str, content: Json, description: Option<&String>) -> Json {
+    let mut ret = vec![("type".to_string(), Json::str("object"))];
+    if let Some(x) = description {
+        ret.push(("description".to_string(), Json::str(x.clone())));
+    }
+    ret.push(("properties".to_string(), Json::Object(vec![(name.to_string(), content)])));
+    ret.push(("required".to_string(), Json::Array(vec![Json::str(name)])));
+    ret.push(("additionalProperties".to_string(), Json::Bool(false)));
+
+    return Json::Object(ret);
+}
+
+fn node_to_json(node: &SchemaNode, defs: &SchemaDefinitions) -> Json {
+    let obj = |x: Vec<(&str, Json)>| Json::Object(x.into_iter().map(|(k, v)| (k.to_string(), v)).collect());
+
+    match *node {
+        SchemaNode::Any => obj(vec![]),
+        SchemaNode::Boolean => obj(vec![("type", Json::str("boolean"))]),
+        SchemaNode::Integer { minimum, maximum } => obj(vec![
+            ("type", Json::str("integer")),
+            ("minimum", Json::Number(minimum.to_string())),
+            ("maximum", Json::Number(maximum.to_string())),
+        ]),
+        SchemaNode::Number => obj(vec![("type", Json::str("number"))]),
+        SchemaNode::String { ref format, min_length, max_length } => {
+            let mut ret = vec![("type", Json::str("string"))];
+            if let Some(ref x) = *format {
+                ret.push(("format", Json::str(x.clone())));
+            }
+            if let Some(x) = min_length {
+                ret.push(("minLength", Json::Number(x.to_string())));
+            }
+            if let Some(x) = max_length {
+                ret.push(("maxLength", Json::Number(x.to_string())));
+            }
+            obj(ret)
+        },
+        SchemaNode::Optional(ref inner) => obj(vec![
+            ("anyOf", Json::Array(vec![node_to_json(inner, defs), obj(vec![("type", Json::str("null"))])])),
+        ]),
+        SchemaNode::Array { ref items, min_items, max_items } => {
+            let mut ret = vec![("type", Json::str("array")), ("items", node_to_json(items, defs))];
+            if let Some(x) = min_items {
+                ret.push(("minItems", Json::Number(x.to_string())));
+            }
+            if let Some(x) = max_items {
+                ret.push(("maxItems", Json::Number(x.to_string())));
+            }
+            obj(ret)
+        },
+        SchemaNode::Set(ref items) => obj(vec![
+            ("type", Json::str("array")),
+            ("items", node_to_json(items, defs)),
+            ("uniqueItems", Json::Bool(true)),
+        ]),
+        SchemaNode::Tuple(ref items) => tuple_to_json(items, defs),
+        SchemaNode::Map { ref key, ref value } => {
+            let mut ret = vec![("type", Json::str("object"))];
+            /* Only string keys can be described, everything else is turned into a string */
+            if let SchemaNode::String { .. } = **key {
+                ret.push(("propertyNames", node_to_json(key, defs)));
+            }
+            ret.push(("additionalProperties", node_to_json(value, defs)));
+            obj(ret)
+        },
+        SchemaNode::Struct { ref description, ref fields } => fields_to_json(fields, description.as_ref(), defs),
+        SchemaNode::Enum { ref description, ref variants } => {
+            let mut options = Vec::new();
+            for var in variants {
+                options.push(match var.fields {
+                    VariantFields::Unit => {
+                        let mut ret = vec![("const", Json::str(var.name.clone()))];
+                        if let Some(ref x) = var.description {
+                            ret.push(("description", Json::str(x.clone())));
+                        }
+                        obj(ret)
+                    },
+                    VariantFields::Tuple(ref items) if items.len() == 1 => {
+                        tagged_to_json(&var.name, node_to_json(&items[0], defs), var.description.as_ref())
+                    },
+                    VariantFields::Tuple(ref items) => {
+                        tagged_to_json(&var.name, tuple_to_json(items, defs), var.description.as_ref())
+                    },
+                    VariantFields::Struct(ref fields) => {
+                        tagged_to_json(&var.name, fields_to_json(fields, None, defs), var.description.as_ref())
+                    },
+                });
+            }
+
+            let mut ret = Vec::new();
+            if let Some(ref x) = *description {
+                ret.push(("description", Json::str(x.clone())));
+            }
+            ret.push(("oneOf", Json::Array(options)));
+            obj(ret)
+        },
+        SchemaNode::Ref(ref name) => obj(vec![("$ref", Json::str(format!("#/$defs/{}", name)))]),
+    }
+}
+
+impl Schema {
+    /// Export the schema as JSON Schema (draft 2020-12).
+    ///
+    /// Values map to JSON the same way serde does it by default: structs are objects, unit
+    /// variants are strings, other variants are objects with the variant name as only key.
+    pub fn to_json_schema(&self) -> String {
+        let mut ret = vec![
+            ("$schema".to_string(), Json::str("https://json-schema.org/draft/2020-12/schema")),
+            ("title".to_string(), Json::str(self.name.clone())),
+        ];
+
+        if let Json::Object(x) = node_to_json(&self.root, &self.definitions) {
+            ret.extend(x);
+        }
+
+        if !self.definitions.is_empty() {
+            let defs = self.definitions.iter().map(|(k, v)| (k.clone(), node_to_json(v, &self.definitions))).collect();
+            ret.push(("$defs".to_string(), Json::Object(defs)));
+        }
+
+        let mut out = String::new();
+        Json::Object(ret).write(0, &mut out);
+        out.push('\n');
+        return out;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use schema::{default_to_json, SchemaDefinitions, SchemaNode};
+
+    fn json(text: &str, schema: &SchemaNode) -> Option<String> {
+        let mut out = String::new();
+        default_to_json(text, schema, &SchemaDefinitions::new())?.write(0, &mut out);
+        return Some(out);
+    }
+
+    #[test]
+    fn test_default_to_json() {
+        let string = SchemaNode::String { format: None, min_length: None, max_length: None };
+        let list = SchemaNode::Array { items: Box::new(string.clone()), min_items: None, max_items: None };
+
+        assert!(json("\"a\\\"b\\u{1b}\"", &string) == Some("\"a\\\"b\\u001b\"".to_string()));
+        assert!(json("'c'", &string) == Some("\"c\"".to_string()));
+        assert!(json("[\"a\", \"b\"]", &list) == Some("[\n  \"a\",\n  \"b\"\n]".to_string()));
+        assert!(json("Some(\"a\")", &SchemaNode::Optional(Box::new(string.clone()))) == Some("\"a\"".to_string()));
+        assert!(json("None", &SchemaNode::Optional(Box::new(string.clone()))) == Some("null".to_string()));
+        assert!(json("-12", &SchemaNode::Integer { minimum: -128, maximum: 127 }) == Some("-12".to_string()));
+        assert!(json("Some", &SchemaNode::Integer { minimum: -128, maximum: 127 }).is_none());
+
+        let map = SchemaNode::Map { key: Box::new(SchemaNode::Integer { minimum: 0, maximum: 255 }), value: Box::new(string.clone()) };
+        assert!(json("{1: \"a\"}", &map) == Some("{\n  \"1\": \"a\"\n}".to_string()));
+    }
+}
@@ -0,0 +1,268 @@
+//! Checks for the values of fields, set with `#[ConfigAttrs(...)]` on the field.
+//!
+//! These are called by the derived parse_from after a field was parsed, and by apply_override
+//! after an override. A violation is reported at the location of the value, and the field is
+//! treated like one that failed to parse (an override is rejected). Overrides are checked on a
+//! clone, so fields with checks need to be Clone.
+//!
+//! * `range = "1..=65535"`: The value is in the range
+//! * `min_len = 1`, `max_len = 10`: The length of a string (in chars) or a list
+//! * `non_empty`: The string or list isn't empty
+//! * `one_of("a", "b")`: The value is one of the literals
+//! * `unique`: The elements of the list are unique
+//! * `validate = "path::to::fn"`: A custom check `fn(&T) -> Result<(), E>`, with `E: Display`
+//!
+//! Checks that span multiple fields are set on the struct (or enum variant), and run once the
+//! entire value was parsed or overridden. Overrides don't know which fields the config gave, so
+//! there the fields that differ from their default count as given:
+//!
+//! * `mutually_exclusive("socket", "port")`: At most one of the fields is given in the config
+//! * `required_together("tls_cert", "tls_key")`: If one of the fields is given, all of them are
+//! * `validate = "path::to::fn"`: A custom check `fn(&T, &ValidationContext) -> Result<(), Box<Diagnostic>>`.
+//!   This can also be set on an enum, to check every variant
+//!
+//! Like for fields, a type with these checks needs to be Clone for its overrides.
+use ConfigAble;
+use Diagnostic;
+use Mark;
+use ParseError;
+
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+use std::fmt::{Debug, Display};
+use std::ops::RangeBounds;
+
+/// Values with a length, for `min_len`, `max_len` and `non_empty`
+pub trait Length {
+    /// The length. Strings count chars, not bytes
+    fn length(&self) -> usize;
+}
+
+impl Length for String {
+    fn length(&self) -> usize { self.chars().count() }
+}
+
+impl<T> Length for Vec<T> {
+    fn length(&self) -> usize { self.len() }
+}
+
+impl<T> Length for Box<[T]> {
+    fn length(&self) -> usize { self.len() }
+}
+
+impl<T> Length for VecDeque<T> {
+    fn length(&self) -> usize { self.len() }
+}
+
+impl<T, S> Length for HashSet<T, S> {
+    fn length(&self) -> usize { self.len() }
+}
+
+impl<T> Length for BTreeSet<T> {
+    fn length(&self) -> usize { self.len() }
+}
+
+impl<K, V, S> Length for HashMap<K, V, S> {
+    fn length(&self) -> usize { self.len() }
+}
+
+impl<K, V> Length for BTreeMap<K, V> {
+    fn length(&self) -> usize { self.len() }
+}
+
+/// The locations of a value and its fields, for checks on the entire value.
+///
+/// Only fields that are given in the config have a location. Errors for the other fields are
+/// reported at the value.
+pub struct ValidationContext {
+    mark: Mark,
+    fields: Vec<(&'static str, Mark)>,
+}
+
+impl ValidationContext {
+    /// A context without any fields
+    /// # Arguments
+    /// * `mark`: The location of the value
+    pub fn new(mark: Mark) -> Self {
+        return ValidationContext { mark, fields: Vec::new() };
+    }
+
+    /// Add the location of a field. Used by the derived parse_from
+    pub fn push(&mut self, field: &'static str, mark: Mark) {
+        self.fields.push((field, mark));
+    }
+
+    /// Whether the field is given in the config, instead of using its default
+    pub fn is_set(&self, field: &str) -> bool {
+        return self.fields.iter().any(|x| x.0 == field);
+    }
+
+    /// The location of the field, or of the entire value if the field isn't given in the config
+    pub fn mark(&self, field: &str) -> &Mark {
+        return self.fields.iter().find(|x| x.0 == field).map(|x| &x.1).unwrap_or(&self.mark);
+    }
+
+    /// Create an error at the location of the field, see mark
+    pub fn error<S: Into<String>>(&self, field: &str, message: S) -> Diagnostic {
+        return self.mark(field).error(message);
+    }
+}
+
+/// Report a violation, and turn it into the error of the field
+fn violation<F>(mark: &Mark, message: String, fun: &mut F) -> Result<(), ParseError>
+    where F: FnMut(Diagnostic) {
+    fun(mark.error(message));
+    return Err(ParseError::Recoverable);
+}
+
+/// Check `range = "..."`. `text` is the range as written in the attribute
+pub fn check_range<T, R, F>(value: &T, range: R, text: &str, field: &str, mark: &Mark, fun: &mut F) -> Result<(), ParseError>
+    where T: ConfigAble + PartialOrd,
+          R: RangeBounds<T>,
+          F: FnMut(Diagnostic) {
+    if range.contains(value) {
+        return Ok(());
+    }
+
+    return violation(mark, format!("{} is {}, but has to be in {}", field, value.to_config_string(), text), fun);
+}
+
+/// Check `min_len`, `max_len` and `non_empty` (which is a `min_len` of 1)
+pub fn check_len<T, F>(value: &T, min: Option<usize>, max: Option<usize>, field: &str, mark: &Mark, fun: &mut F) -> Result<(), ParseError>
+    where T: Length,
+          F: FnMut(Diagnostic) {
+    let len = value.length();
+
+    if min == Some(1) && len == 0 {
+        return violation(mark, format!("{} can't be empty", field), fun);
+    }
+    if let Some(min) = min.filter(|x| len < *x) {
+        return violation(mark, format!("{} has a length of {}, but has to be at least {} long", field, len, min), fun);
+    }
+    if let Some(max) = max.filter(|x| len > *x) {
+        return violation(mark, format!("{} has a length of {}, but has to be at most {} long", field, len, max), fun);
+    }
+
+    return Ok(());
+}
+
+/// Check `one_of(...)`
+pub fn check_one_of<T, U, F>(value: &T, allowed: &[U], field: &str, mark: &Mark, fun: &mut F) -> Result<(), ParseError>
+    where T: ConfigAble + PartialEq<U>,
+          U: Debug,
+          F: FnMut(Diagnostic) {
+    if allowed.iter().any(|x| value == x) {
+        return Ok(());
+    }
+
+    let allowed: Vec<String> = allowed.iter().map(|x| format!("{:?}", x)).collect();
+    let value = value.to_config_string();
+    fun(mark.error(format!("{} is {}, but has to be one of the allowed values", field, value))
+        .expected(format!("one of: {}", allowed.join(", "))).found(value));
+    return Err(ParseError::Recoverable);
+}
+
+/// Check `unique`. Elements are compared by their config text
+pub fn check_unique<'a, T, V, F>(value: &'a V, field: &str, mark: &Mark, fun: &mut F) -> Result<(), ParseError>
+    where &'a V: IntoIterator<Item=&'a T>,
+          T: ConfigAble + 'a,
+          V: ?Sized,
+          F: FnMut(Diagnostic) {
+    let mut seen = HashSet::new();
+    let mut failed = false;
+
+    for element in value {
+        let text = element.to_config_string();
+        if !seen.insert(text.clone()) {
+            fun(mark.error(format!("{} is in {} more than once", text, field)));
+            failed = true;
+        }
+    }
+
+    return if failed { Err(ParseError::Recoverable) } else { Ok(()) };
+}
+
+/// Report the result of `validate = "..."`
+pub fn check_custom<E, F>(result: Result<(), E>, field: &str, mark: &Mark, fun: &mut F) -> Result<(), ParseError>
+    where E: Display,
+          F: FnMut(Diagnostic) {
+    return match result {
+        Ok(()) => Ok(()),
+        Err(x) => violation(mark, format!("Invalid {}: {}", field, x), fun),
+    };
+}
+
+/// Check `mutually_exclusive(...)`. The error is reported at the second field that's given
+pub fn check_mutually_exclusive<F>(context: &ValidationContext, fields: &[&str], fun: &mut F) -> Result<(), ParseError>
+    where F: FnMut(Diagnostic) {
+    let set: Vec<&str> = fields.iter().cloned().filter(|x| context.is_set(x)).collect();
+    if set.len() < 2 {
+        return Ok(());
+    }
+
+    fun(context.error(set[1], format!("{} and {} can't be used together", set[0], set[1]))
+        .expected(format!("only one of: {}", fields.join(", "))));
+    return Err(ParseError::Recoverable);
+}
+
+/// Check `required_together(...)`. The error is reported at the first field that's given
+pub fn check_required_together<F>(context: &ValidationContext, fields: &[&str], fun: &mut F) -> Result<(), ParseError>
+    where F: FnMut(Diagnostic) {
+    let given = match fields.iter().find(|x| context.is_set(x)) {
+        Some(x) => x,
+        None => { return Ok(()); },
+    };
+    let missing: Vec<&str> = fields.iter().cloned().filter(|x| !context.is_set(x)).collect();
+    if missing.is_empty() {
+        return Ok(());
+    }
+
+    fun(context.error(given, format!("{} requires {} as well", given, missing.join(", ")))
+        .expected(format!("all of: {}", fields.join(", "))));
+    return Err(ParseError::Recoverable);
+}
+
+/// Report the result of a `validate = "..."` on an entire value
+pub fn check_value<F>(result: Result<(), Box<Diagnostic>>, fun: &mut F) -> Result<(), ParseError>
+    where F: FnMut(Diagnostic) {
+    return match result {
+        Ok(()) => Ok(()),
+        Err(x) => {
+            fun(*x);
+            Err(ParseError::Recoverable)
+        },
+    };
+}
+
+#[cfg(test)]
+mod test {
+    use ConfigProvider;
+    use ParseError;
+    use super::*;
+
+    #[test]
+    fn test_checks() {
+        let mark = ConfigProvider::new_from_str("x").mark(0, 1);
+        let mut errors = Vec::new();
+
+        assert!(check_range(&80u16, 1..=1024, "1..=1024", "port", &mark, &mut |x| errors.push(x)).is_ok());
+        assert!(check_range(&0u16, 1..=1024, "1..=1024", "port", &mark, &mut |x| errors.push(x)) == Err(ParseError::Recoverable));
+        assert!(check_len(&"äö".to_string(), Some(2), Some(2), "name", &mark, &mut |x| errors.push(x)).is_ok());
+        assert!(check_len(&Vec::<u8>::new(), Some(1), None, "list", &mark, &mut |x| errors.push(x)).is_err());
+        assert!(check_len(&vec![1u8, 2, 3], None, Some(2), "list", &mark, &mut |x| errors.push(x)).is_err());
+        assert!(check_one_of(&"info".to_string(), &["debug", "info"], "level", &mark, &mut |x| errors.push(x)).is_ok());
+        assert!(check_one_of(&3u8, &[1, 2], "level", &mark, &mut |x| errors.push(x)).is_err());
+        assert!(check_unique(&vec!['a', 'b', 'a'], "list", &mark, &mut |x| errors.push(x)).is_err());
+        assert!(check_custom(Err("too odd"), "number", &mark, &mut |x| errors.push(x)).is_err());
+
+        let messages: Vec<String> = errors.iter().map(|x| x.message.clone()).collect();
+        assert!(messages == vec![
+            "port is 0, but has to be in 1..=1024",
+            "list can't be empty",
+            "list has a length of 3, but has to be at most 2 long",
+            "level is 3, but has to be one of the allowed values",
+            "'a' is in list more than once",
+            "Invalid number: too odd",
+        ]);
+        assert!(errors[3].to_string().contains("expected one of: 1, 2, found 3"));
+    }
+}
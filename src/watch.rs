@@ -0,0 +1,131 @@
+//! Reloading a config when its files change.
+//!
+//! The files are polled for changes of their modification time and size, so this works without
+//! any notification service. Every `!include`d file is watched as well.
+use std;
+use ConfigAble;
+use ConfigError;
+use read_config_files;
+
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// What's compared to detect a change. None for files that don't exist (or can't be accessed)
+type Stamp = Option<(Option<SystemTime>, u64)>;
+
+fn stamp(path: &Path) -> Stamp {
+    return std::fs::metadata(path).ok().map(|x| (x.modified().ok(), x.len()));
+}
+
+/// A config that's read again whenever one of its files changes.
+///
+/// The last config that could be read is kept when a changed file is broken, until it's fixed.
+pub struct ConfigWatcher<T> {
+    path: PathBuf,
+    files: Vec<(PathBuf, Stamp)>,
+    value: T,
+}
+
+impl<T> ConfigWatcher<T>
+    where T: ConfigAble {
+    /// Read the config file at `path`, like read_config, and start watching it
+    pub fn new<P: Into<PathBuf>>(path: P) -> Result<Self, ConfigError> {
+        let path = path.into();
+        let before = stamp_all(vec![path.clone()]);
+        let (value, files) = read_config_files(&path);
+
+        return Ok(ConfigWatcher { files: restamp(files, &before), path, value: value? });
+    }
+
+    /// The last config that was read successfully
+    pub fn value(&self) -> &T {
+        return &self.value;
+    }
+
+    /// Stop watching, and get the last config that was read successfully
+    pub fn into_value(self) -> T {
+        return self.value;
+    }
+
+    /// The watched files: the config file and every `!include`d file
+    pub fn files(&self) -> Vec<&Path> {
+        return self.files.iter().map(|x| x.0.as_path()).collect();
+    }
+
+    /// Whether any of the watched files changed since the config was read
+    pub fn changed(&self) -> bool {
+        return self.files.iter().any(|x| stamp(&x.0) != x.1);
+    }
+
+    /// Read the config again, whether a file changed or not.
+    ///
+    /// The config is only replaced if it could be read. Otherwise the error is returned, and the
+    /// old config is kept.
+    pub fn reload(&mut self) -> Result<&T, ConfigError> {
+        /* Stamp before reading, so a file that changes while it's read is read again next time */
+        let before = stamp_all(self.files.iter().map(|x| x.0.clone()).collect());
+        let (value, mut files) = read_config_files(&self.path);
+
+        match value {
+            Ok(x) => {
+                self.value = x;
+            },
+            Err(x) => {
+                /* The files after the error weren't read, keep watching them until it's fixed */
+                files.extend(self.files.drain(..).map(|x| x.0));
+                let mut seen = std::collections::HashSet::new();
+                files.retain(|x| seen.insert(x.clone()));
+
+                self.files = restamp(files, &before);
+                return Err(x);
+            },
+        }
+
+        self.files = restamp(files, &before);
+        return Ok(&self.value);
+    }
+
+    /// Read the config again if any of the watched files changed.
+    ///
+    /// None if nothing changed, otherwise the result of reload
+    pub fn poll(&mut self) -> Option<Result<&T, ConfigError>> {
+        if !self.changed() {
+            return None;
+        }
+
+        return Some(self.reload());
+    }
+
+    /// Poll the files every `interval`, and call `fun` with the new config or the error whenever
+    /// they change.
+    ///
+    /// This blocks until `fun` returns false, so it's usually run in its own thread.
+    pub fn watch<F>(&mut self, interval: Duration, mut fun: F)
+        where F: FnMut(Result<&T, ConfigError>) -> bool {
+        loop {
+            if let Some(x) = self.poll() {
+                if !fun(x) {
+                    return;
+                }
+            }
+
+            std::thread::sleep(interval);
+        }
+    }
+}
+
+fn stamp_all(files: Vec<PathBuf>) -> Vec<(PathBuf, Stamp)> {
+    return files.into_iter().map(|x| { let s = stamp(&x); (x, s) }).collect();
+}
+
+/// Stamp the files that were read, using the stamps taken before reading where there are any.
+/// Only files that weren't known before (new `!include`s) are stamped after reading
+fn restamp(files: Vec<PathBuf>, before: &[(PathBuf, Stamp)]) -> Vec<(PathBuf, Stamp)> {
+    return files.into_iter().map(|x| {
+        let s = match before.iter().find(|y| y.0 == x) {
+            Some(y) => y.1,
+            None => stamp(&x),
+        };
+        (x, s)
+    }).collect();
+}
@@ -0,0 +1,12 @@
+use std::io::Write;
+use std::path::PathBuf;
+
+/// Write a config file into the temp directory. The name is prefixed with the process id, so
+/// test runs don't interfere
+pub fn write_config(name: &str, content: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("rs-config-test-{}-{}", std::process::id(), name));
+    let mut file = std::fs::File::create(&path).unwrap();
+    file.write_all(content.as_bytes()).unwrap();
+
+    return path;
+}
@@ -0,0 +1,84 @@
+#[macro_use]
+extern crate rs_config_derive;
+
+extern crate rs_config;
+
+use rs_config::ConfigAble;
+
+#[derive(ConfigAble, PartialEq, Eq, Debug)]
+enum Direction {
+    Left,
+    Right,
+}
+
+#[derive(ConfigAble, PartialEq, Eq, Debug)]
+struct Input {
+    source: String,
+    #[ConfigAttrs(default = "'x'")]
+    layer: char,
+}
+
+#[derive(ConfigAble, PartialEq, Eq, Debug)]
+struct Nested {
+    #[ConfigAttrs(default = "Direction::Right")]
+    direction: Direction,
+    name: Option<String>,
+}
+
+#[derive(ConfigAble, PartialEq, Eq, Debug)]
+struct Config {
+    #[ConfigAttrs(default = "\"ongybar\".to_string()")]
+    title: String,
+    inputs: Vec<Input>,
+    position: Direction,
+    nested: Nested,
+    input: Input,
+}
+
+#[derive(ConfigAble, PartialEq, Eq, Debug)]
+struct Complete {
+    #[ConfigAttrs(default = "vec!['a', 'b']")]
+    chars: Vec<char>,
+    nested: Nested,
+}
+
+#[test]
+fn test_default_config_text() {
+    let expected = "\
+title: \"ongybar\",
+inputs: [],
+# position: <Direction>
+#   Direction: Left | Right
+nested: {
+    direction: Right,
+    name: None,
+},
+input: {
+    # source: <String>
+    #   String: \"Rust String\"
+    layer: 'x',
+},
+";
+
+    assert_eq!(Config::default_config_text(), expected);
+}
+
+#[test]
+fn test_default_config_text_parses() {
+    let text = Complete::default_config_text();
+    assert_eq!(text, "chars: ['a', 'b'],\nnested: {\n    direction: Right,\n    name: None,\n},\n");
+
+    let mut lines: Vec<(usize, String)> = text.lines().map(|x| x.to_string()).enumerate().collect();
+    lines.insert(0, (0, "{".to_string()));
+    lines.push((usize::MAX, "}".to_string()));
+    let mut provider = rs_config::ConfigProvider::new_with_provider(lines.into_iter(), "sample".to_string());
+
+    let val = rs_config::read_from_provider::<Complete>(&mut provider).unwrap();
+    assert_eq!(val, Complete { chars: vec!['a', 'b'], nested: Nested { direction: Direction::Right, name: None } });
+}
+
+#[test]
+fn test_default_config_text_other() {
+    assert_eq!(<Vec<char> as ConfigAble>::default_config_text(), "[]\n");
+    assert_eq!(Direction::default_config_text(), "# Direction\n#   Direction: Left | Right\n");
+}
@@ -0,0 +1,91 @@
+#[macro_use]
+extern crate rs_config_derive;
+
+extern crate rs_config;
+
+use rs_config::{Change, ConfigAble};
+
+#[derive(ConfigAble, Debug, Clone)]
+struct Input {
+    name: String,
+    layer: u8,
+}
+
+#[derive(ConfigAble, Debug, Clone)]
+struct Color(u8, u8, u8);
+
+#[derive(ConfigAble, Debug, Clone)]
+enum Output {
+    Stdout,
+    File(String, Option<u32>),
+    Socket { host: String, port: u16 },
+}
+
+#[derive(ConfigAble, Debug, Clone)]
+struct DiffConfig {
+    inputs: Vec<Input>,
+    color: Color,
+    output: Output,
+    margins: [u8; 4],
+    title: Option<Input>,
+}
+
+fn changes(old: &DiffConfig, new: &DiffConfig) -> Vec<String> {
+    return old.diff(new).iter().map(|x| x.to_string()).collect();
+}
+
+fn sample() -> DiffConfig {
+    return DiffConfig {
+        inputs: vec![Input { name: "a".to_string(), layer: 0 }, Input { name: "b".to_string(), layer: 1 }],
+        color: Color(0, 0, 0),
+        output: Output::Socket { host: "localhost".to_string(), port: 80 },
+        margins: [0, 0, 0, 0],
+        title: None,
+    };
+}
+
+#[test]
+fn test_diff_fields() {
+    let old = sample();
+    assert!(old.diff(&old.clone()).is_empty());
+
+    let mut new = old.clone();
+    new.inputs[1].layer = 2;
+    assert!(old.diff(&new) == vec![Change { path: "inputs[1].layer".to_string(), old: Some("1".to_string()), new: Some("2".to_string()) }]);
+
+    new.color.2 = 255;
+    new.margins[3] = 4;
+    new.inputs.push(Input { name: "c".to_string(), layer: 0 });
+    assert!(changes(&old, &new) == vec![
+        "inputs[1].layer: 1 -> 2",
+        "inputs[2]: added {name: \"c\", layer: 0}",
+        "color.2: 0 -> 255",
+        "margins[3]: 0 -> 4",
+    ]);
+}
+
+#[test]
+fn test_diff_enum_option() {
+    let old = sample();
+
+    let mut new = old.clone();
+    new.output = Output::Socket { host: "localhost".to_string(), port: 8080 };
+    assert!(changes(&old, &new) == vec!["output.port: 80 -> 8080"]);
+
+    new.output = Output::File("log".to_string(), None);
+    assert!(changes(&old, &new) == vec!["output: Socket{host: \"localhost\", port: 80} -> File(\"log\", None)"]);
+
+    let mut other = new.clone();
+    other.output = Output::File("log".to_string(), Some(3));
+    assert!(changes(&new, &other) == vec!["output.1: None -> Some(3)"]);
+
+    other.output = Output::Stdout;
+    assert!(changes(&other, &other.clone()).is_empty());
+
+    new.title = Some(Input { name: "t".to_string(), layer: 0 });
+    assert!(changes(&old, &new)[1] == "title: None -> Some({name: \"t\", layer: 0})");
+
+    other = new.clone();
+    other.title.as_mut().unwrap().name = "u".to_string();
+    assert!(changes(&new, &other) == vec!["title.name: \"t\" -> \"u\""]);
+}
@@ -0,0 +1,69 @@
+#[macro_use]
+extern crate rs_config_derive;
+
+extern crate rs_config;
+
+use rs_config::ConfigAble;
+
+#[derive(ConfigAble, PartialEq, Eq, Debug)]
+/// Where to put things
+enum Position {
+    /// Relative to all monitors
+    Global(char),
+    Monitor {
+        /// The monitor name
+        name: String,
+    },
+}
+
+/// The main configuration.
+///
+/// Everything else is optional.
+#[derive(ConfigAble, PartialEq, Eq, Debug)]
+struct DocConfig {
+    /// Where the bar is placed.
+    /// Defaults to the left side of every monitor.
+    #[ConfigAttrs(default = "Position::Global('l')")]
+    position: Position,
+    #[doc = "The window title"]
+    title: String,
+    undocumented: Option<char>,
+}
+
+#[test]
+fn test_doc_format() {
+    let format = DocConfig::get_format_str();
+
+    assert!(format.starts_with("DocConfig: {position: Position, title: String, undocumented: Option < char >}
+    The main configuration.
+
+    Everything else is optional.
+    position: Where the bar is placed.
+              Defaults to the left side of every monitor.
+    title: The window title
+"));
+    assert!(format.contains("\nPosition: Global(char) | Monitor{name: String}
+    Where to put things
+    Global: Relative to all monitors
+    Monitor.name: The monitor name
+"));
+}
+
+#[test]
+fn test_doc_sample() {
+    let expected = "\
+# The main configuration.
+#
+# Everything else is optional.
+
+# Where the bar is placed.
+# Defaults to the left side of every monitor.
+position: Global('l'),
+# The window title
+# title: <String>
+#   String: \"Rust String\"
+undocumented: None,
+";
+
+    assert_eq!(DocConfig::default_config_text(), expected);
+}
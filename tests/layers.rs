@@ -0,0 +1,102 @@
+#[macro_use]
+extern crate rs_config_derive;
+
+extern crate rs_config;
+
+mod common;
+
+use rs_config::ConfigAble;
+use rs_config::ConfigError;
+use rs_config::{LayeredConfig, OverridePolicy};
+
+use common::write_config;
+
+#[derive(ConfigAble, PartialEq, Eq, Debug)]
+struct Output {
+    path: String,
+    #[ConfigAttrs(default = "0")]
+    level: u8,
+}
+
+#[derive(ConfigAble, PartialEq, Eq, Debug)]
+struct LayeredTest {
+    #[ConfigAttrs(default = "vec![]")]
+    plugins: Vec<String>,
+    output: Output,
+    #[ConfigAttrs(default = "None")]
+    name: Option<String>,
+}
+
+#[test]
+fn test_layers_merge() {
+    let system = write_config("layers-system", "plugins: [\"a\"],\noutput: {path: \"/var/log\"}\n");
+    let user = std::env::temp_dir().join("rs-config-test-does-not-exist");
+    let project = write_config("layers-project", "plugins: [\"b\"],\nname: Some(\"p\")\n");
+
+    let val = LayeredConfig::new().layer(&system).layer(&user).layer(&project).policy(OverridePolicy::Merge).read::<LayeredTest>().unwrap();
+    assert!(val.value.plugins == vec!["a".to_string(), "b".to_string()]);
+    assert!(val.value.output == Output { path: "/var/log".to_string(), level: 0 });
+    assert!(val.value.name == Some("p".to_string()));
+
+    assert!(val.layers == vec![system.clone(), project.clone()]);
+    assert!(val.skipped == vec![user]);
+
+    assert!(val.sources("plugins") == vec![system.as_path(), project.as_path()]);
+    assert!(val.sources("plugins[0]") == vec![system.as_path()]);
+    assert!(val.sources("plugins[1]") == vec![project.as_path()]);
+    assert!(val.source("output.path") == Some(system.as_path()));
+    assert!(val.source("output.level").is_none());
+    assert!(val.source("name") == Some(project.as_path()));
+    assert!(val.source("missing").is_none());
+
+    std::fs::remove_file(system).unwrap();
+    std::fs::remove_file(project).unwrap();
+}
+
+#[test]
+fn test_layers_last_wins() {
+    let system = write_config("layers-replace-system", "plugins: [\"a\"],\noutput: {path: \"/var/log\", level: 1},\nname: Some(\"s\")\n");
+    let user = write_config("layers-replace-user", "output: {path: \"~/log\"},\nname: Some(\"u\")\n");
+
+    /* The last layer wins by default */
+    let val = LayeredConfig::new().layer(&system).layer(&user).read::<LayeredTest>().unwrap();
+    assert!(val.value.plugins == vec!["a".to_string()]);
+    assert!(val.value.output == Output { path: "~/log".to_string(), level: 0 });
+    assert!(val.value.name == Some("u".to_string()));
+
+    assert!(val.sources("plugins") == vec![system.as_path()]);
+    assert!(val.sources("plugins[0]") == vec![system.as_path()]);
+    assert!(val.sources("output") == vec![user.as_path()]);
+    assert!(val.sources("name") == vec![user.as_path()]);
+    assert!(val.source("output.level").is_none());
+
+    /* Merging fails for values that can't be combined */
+    match LayeredConfig::new().layer(&system).layer(&user).policy(OverridePolicy::Merge).read::<LayeredTest>() {
+        Err(ConfigError::Parse { diagnostics, .. }) => assert!(diagnostics.iter().any(|x| x.is_error())),
+        _ => panic!("Expected parse error"),
+    }
+
+    std::fs::remove_file(system).unwrap();
+    std::fs::remove_file(user).unwrap();
+}
+
+#[test]
+fn test_layers_errors() {
+    let broken = write_config("layers-broken", "output: {path: 1}\n");
+
+    match LayeredConfig::new().layer(&broken).read::<LayeredTest>() {
+        Err(ConfigError::Parse { diagnostics, .. }) => {
+            assert!(diagnostics.iter().any(|x| x.is_error() && x.to_string().contains("layers-broken")));
+        },
+        _ => panic!("Expected parse error"),
+    }
+
+    /* Without any layers, everything is defaulted */
+    match LayeredConfig::new().read::<LayeredTest>() {
+        Err(ConfigError::Parse { diagnostics, .. }) => assert!(diagnostics.iter().any(|x| x.message.contains("output"))),
+        _ => panic!("Expected parse error"),
+    }
+    assert!(LayeredConfig::new().read::<Option<u8>>().is_err());
+
+    std::fs::remove_file(broken).unwrap();
+}
@@ -0,0 +1,110 @@
+#[macro_use]
+extern crate rs_config_derive;
+
+extern crate rs_config;
+
+use rs_config::ConfigAble;
+use rs_config::ConfigError;
+use rs_config::OverridePolicy;
+
+#[derive(ConfigAble, Clone, PartialEq, Eq, Debug)]
+enum Output {
+    Stdout,
+    File { path: String, append: bool },
+}
+
+#[derive(ConfigAble, Clone, PartialEq, Eq, Debug)]
+struct Input {
+    layer: i32,
+    max_size: Option<u32>,
+}
+
+#[derive(ConfigAble, Clone, PartialEq, Eq, Debug)]
+struct App {
+    title: String,
+    inputs: Vec<Input>,
+    tags: Vec<String>,
+    output: Output,
+}
+
+fn parse(s: &str) -> App {
+    let mut provider = rs_config::ConfigProvider::new_from_str(s);
+    return App::parse_from(&mut provider, &mut |_| {}).unwrap();
+}
+
+fn base() -> App {
+    parse("{ title: \"app\", inputs: [{ layer: 1, max_size: None }], tags: [\"a\"], output: File { path: \"out\", append: false } }")
+}
+
+#[test]
+fn test_env_overrides() {
+    std::env::set_var("RSCFG_ENV_INPUTS_0_LAYER", "3");
+    std::env::set_var("RSCFG_ENV_INPUTS_0_MAX_SIZE", "Some(10)");
+    std::env::set_var("RSCFG_ENV_TITLE", "\"x\"");
+    std::env::set_var("RSCFG_ENV_OUTPUT_APPEND", "true");
+
+    let mut val = base();
+    rs_config::apply_env_overrides(&mut val, "RSCFG_ENV", OverridePolicy::Replace).unwrap();
+    assert!(val.title == "x");
+    assert!(val.inputs == vec![Input { layer: 3, max_size: Some(10) }]);
+    assert!(val.output == Output::File { path: "out".to_string(), append: true });
+}
+
+#[test]
+fn test_env_override_merge() {
+    std::env::set_var("RSCFG_MERGE_TAGS", "[\"b\"]");
+
+    let mut val = base();
+    rs_config::apply_env_overrides(&mut val, "RSCFG_MERGE", OverridePolicy::Merge).unwrap();
+    assert!(val.tags == vec!["a".to_string(), "b".to_string()]);
+
+    let mut val = base();
+    rs_config::apply_env_overrides(&mut val, "RSCFG_MERGE", OverridePolicy::Replace).unwrap();
+    assert!(val.tags == vec!["b".to_string()]);
+}
+
+#[test]
+fn test_env_override_errors() {
+    std::env::set_var("RSCFG_ERR_INPUTS_1_LAYER", "3");
+    std::env::set_var("RSCFG_ERR_INPUTS_0_DEPTH", "3");
+    std::env::set_var("RSCFG_ERR_TITLE", "unquoted");
+
+    let mut val = base();
+    match rs_config::apply_env_overrides(&mut val, "RSCFG_ERR", OverridePolicy::Replace) {
+        Err(ConfigError::Parse { diagnostics, .. }) => {
+            assert!(diagnostics.len() == 3);
+            assert!(diagnostics[0].message == "Invalid override RSCFG_ERR_INPUTS_0_DEPTH: Unknown field 'DEPTH' in 'INPUTS_0'");
+            assert!(diagnostics[0].expected == Some("one of: LAYER, MAX_SIZE".to_string()));
+            assert!(diagnostics[1].message == "Invalid override RSCFG_ERR_INPUTS_1_LAYER: Unknown element '1_LAYER' in 'INPUTS'");
+            assert!(diagnostics[2].span.as_ref().map(|x| x.file.as_str()) == Some("RSCFG_ERR_TITLE"));
+        },
+        _ => panic!("Expected override errors"),
+    }
+    assert!(val == base());
+}
+
+#[test]
+fn test_set_overrides() {
+    let mut val = base();
+    rs_config::apply_set_overrides(&mut val, ["output=Stdout", "inputs[0].layer=2", "inputs.0.max_size = Some(4)"], OverridePolicy::Replace).unwrap();
+    assert!(val.output == Output::Stdout);
+    assert!(val.inputs == vec![Input { layer: 2, max_size: Some(4) }]);
+}
+
+#[test]
+fn test_set_override_errors() {
+    let mut val = base();
+    let overrides = vec!["inputs[0].lyer=2".to_string(), "title.len=3".to_string(), "output.path".to_string(), "inputs[0].layer=x".to_string()];
+
+    match rs_config::apply_set_overrides(&mut val, overrides, OverridePolicy::Replace) {
+        Err(ConfigError::Parse { diagnostics, .. }) => {
+            assert!(diagnostics[0].to_string() == "error: Invalid override inputs[0].lyer: Unknown field 'lyer' in 'inputs[0]'\n  expected one of: layer, max_size, found lyer");
+            assert!(diagnostics[1].message == "Invalid override title.len: 'title' is a String without fields, but the path continues with 'len'");
+            assert!(diagnostics[2].message == "Invalid override output.path: missing '='");
+            assert!(diagnostics[3].message.starts_with("Failed to parse 'x' into i32"));
+            assert!(diagnostics[3].span.as_ref().map(|x| x.file.as_str()) == Some("inputs[0].layer"));
+        },
+        _ => panic!("Expected override errors"),
+    }
+    assert!(val == base());
+}
@@ -0,0 +1,79 @@
+#[macro_use]
+extern crate rs_config_derive;
+
+extern crate rs_config;
+
+use rs_config::ConfigAble;
+use rs_config::ConfigError;
+
+use std::path::PathBuf;
+
+#[derive(ConfigAble, PartialEq, Eq, Debug)]
+struct PathConfig {
+    log: PathBuf,
+    data: PathBuf,
+    #[ConfigAttrs(is_dir)]
+    dir: PathBuf,
+    #[ConfigAttrs(expand_tilde)]
+    home: Option<PathBuf>,
+}
+
+#[derive(ConfigAble, PartialEq, Eq, Debug)]
+struct CheckedConfig {
+    #[ConfigAttrs(must_exist, absolute)]
+    input: PathBuf,
+}
+
+fn test_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("rs-config-test-{}-{}", std::process::id(), name));
+    std::fs::create_dir_all(dir.join("sub")).unwrap();
+    return dir;
+}
+
+#[test]
+fn test_paths_relative_to_file() {
+    let dir = test_dir("paths");
+    std::fs::write(dir.join("app.cfg"), format!("log: \"log.txt\",\n!include {}\n", dir.join("sub/inc.cfg").display())).unwrap();
+    std::fs::write(dir.join("sub/inc.cfg"), "data: \"files\",\ndir: \".\",\nhome: Some(\"~/x\")\n").unwrap();
+
+    let val = rs_config::read_config::<PathConfig, _>(dir.join("app.cfg")).unwrap();
+    assert!(val.log == dir.join("log.txt"));
+    assert!(val.data == dir.join("sub/files"));
+    assert!(val.dir == dir.join("sub/."));
+    if let Some(home) = std::env::var_os("HOME") {
+        assert!(val.home == Some(PathBuf::from(home).join("x")));
+    }
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_path_checks() {
+    let dir = test_dir("checks");
+    std::fs::write(dir.join("app.cfg"), format!("input: \"{}\"\n", dir.join("missing").display())).unwrap();
+
+    match rs_config::read_config::<CheckedConfig, _>(dir.join("app.cfg")) {
+        Err(ConfigError::Parse { diagnostics, .. }) => {
+            let diag = diagnostics.iter().find(|x| x.is_error()).unwrap();
+            assert!(diag.message == format!("Path {} doesn't exist", dir.join("missing").display()));
+            assert!(diag.span.as_ref().map(|x| (x.line, x.start_column)) == Some((1, 8)));
+        },
+        _ => panic!("Expected parse error"),
+    }
+
+    /* Relative paths aren't resolved for absolute, even in a file */
+    std::fs::write(dir.join("app.cfg"), "input: \"sub\"\n").unwrap();
+    match rs_config::read_config::<CheckedConfig, _>(dir.join("app.cfg")) {
+        Err(ConfigError::Parse { diagnostics, .. }) => {
+            let diag = diagnostics.iter().find(|x| x.is_error()).unwrap();
+            assert!(diag.message == "Path sub must be absolute");
+            assert!(diag.span.as_ref().map(|x| (x.line, x.start_column)) == Some((1, 8)));
+        },
+        _ => panic!("Expected parse error"),
+    }
+
+    std::fs::write(dir.join("app.cfg"), format!("input: \"{}\"\n", dir.join("sub").display())).unwrap();
+    assert!(rs_config::read_config::<CheckedConfig, _>(dir.join("app.cfg")).unwrap().input == dir.join("sub"));
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,105 @@
+#[macro_use]
+extern crate rs_config_derive;
+
+extern crate rs_config;
+
+mod common;
+
+use rs_config::ConfigAble;
+use rs_config::ConfigError;
+
+use common::write_config;
+
+#[derive(ConfigAble, PartialEq, Eq, Debug)]
+struct ReadConfig {
+    name: String,
+    #[ConfigAttrs(default = "'x'")]
+    c: char,
+}
+
+#[test]
+fn test_read_config_ok() {
+    let path = write_config("ok", "name: \"Test\",\n# A comment\nc: 'y'\n");
+
+    let val = rs_config::read_config::<ReadConfig, _>(&path).unwrap();
+    assert!(val == ReadConfig { name: "Test".to_string(), c: 'y' });
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_read_config_missing_file() {
+    let path = std::env::temp_dir().join("rs-config-test-does-not-exist");
+
+    match rs_config::read_config::<ReadConfig, _>(&path) {
+        Err(ConfigError::Io { path: p, .. }) => assert!(p == path),
+        _ => panic!("Expected I/O error"),
+    }
+}
+
+#[test]
+fn test_read_config_parse_error() {
+    let path = write_config("parse", "c: 'y'\n");
+
+    match rs_config::read_config::<ReadConfig, _>(&path) {
+        Err(ConfigError::Parse { diagnostics, format }) => {
+            assert!(diagnostics.iter().any(|x| x.is_error() && x.message.contains("Couldn't default name")));
+            assert!(format.unwrap().starts_with("ReadConfig: {name: String, c: char}"));
+        },
+        _ => panic!("Expected parse error"),
+    }
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_read_config_bad_include() {
+    let path = write_config("include", "!include /rs-config-test-does-not-exist\n");
+
+    match rs_config::read_config::<ReadConfig, _>(&path) {
+        Err(ConfigError::Parse { diagnostics, .. }) => {
+            assert!(diagnostics.len() == 1);
+            assert!(diagnostics[0].message.starts_with("Couldn't include /rs-config-test-does-not-exist"));
+            assert!(diagnostics[0].span.as_ref().map(|x| x.line) == Some(1));
+        },
+        _ => panic!("Expected parse error"),
+    }
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_read_config_include_spaces() {
+    let include = write_config("include with spaces", "c: 'z'\n");
+    let path = write_config("include-spaces", &format!("name: \"a\",\n!include  {} \n", include.display()));
+
+    let val = rs_config::read_config::<ReadConfig, _>(&path).unwrap();
+    assert!(val == ReadConfig { name: "a".to_string(), c: 'z' });
+
+    std::fs::remove_file(include).unwrap();
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_read_config_interpolation() {
+    std::env::set_var("RS_CONFIG_TEST_NAME", "from env");
+    let path = write_config("interpolation", "name: \"${RS_CONFIG_TEST_NAME} ${RS_CONFIG_TEST_MISSING}\"\n");
+
+    let mut provider = rs_config::provider_from_file_wrap(&path).unwrap();
+    provider.set_interpolation(true);
+    match rs_config::read_from_provider::<ReadConfig>(&mut provider) {
+        Err(ConfigError::Parse { diagnostics, .. }) => {
+            let diag = diagnostics.iter().find(|x| x.is_error()).unwrap();
+            assert!(diag.message.starts_with("Environment variable RS_CONFIG_TEST_MISSING isn't set"));
+            assert!(diag.span.as_ref().map(|x| (x.line, x.start_column, x.end_column)) == Some((1, 31, 56)));
+        },
+        _ => panic!("Expected parse error"),
+    }
+
+    std::fs::write(&path, "name: \"${RS_CONFIG_TEST_NAME}\"\n").unwrap();
+    let mut provider = rs_config::provider_from_file_wrap(&path).unwrap();
+    provider.set_interpolation(true);
+    assert!(rs_config::read_from_provider::<ReadConfig>(&mut provider).unwrap().name == "from env");
+
+    std::fs::remove_file(path).unwrap();
+}
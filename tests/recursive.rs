@@ -0,0 +1,50 @@
+#[macro_use]
+extern crate rs_config_derive;
+
+extern crate rs_config;
+
+use rs_config::ConfigAble;
+
+use std::num::NonZeroU32;
+
+#[derive(ConfigAble, PartialEq, Eq, Debug)]
+enum Layout {
+    Leaf(String),
+    Split(Box<Layout>, Box<Layout>),
+}
+
+#[derive(ConfigAble, PartialEq, Eq, Debug)]
+struct Window {
+    layout: Layout,
+    gap: NonZeroU32,
+}
+
+#[test]
+fn test_recursive_parse() {
+    let mut provider = rs_config::ConfigProvider::new_from_str("{ layout: Split(Leaf(\"a\"), Split(Leaf(\"b\"), Leaf(\"c\"))), gap: 4 }");
+    let val = Window::parse_from(&mut provider, &mut |_| {}).unwrap();
+
+    let leaf = |x: &str| Box::new(Layout::Leaf(x.to_string()));
+    assert!(val.layout == Layout::Split(leaf("a"), Box::new(Layout::Split(leaf("b"), leaf("c")))));
+    assert!(val.to_config_string() == "{layout: Split(Leaf(\"a\"), Split(Leaf(\"b\"), Leaf(\"c\"))), gap: 4}");
+}
+
+#[test]
+fn test_recursive_format() {
+    let format = Window::get_format_str();
+    assert!(format.starts_with("Window: {layout: Layout, gap: NonZeroU32}"));
+    assert!(format.matches("Layout: Leaf(String) | Split(").count() == 1);
+    assert!(format.contains("Box<Layout>: Layout"));
+    assert!(format.contains("NonZeroU32: Integer in 1..=4294967295"));
+}
+
+#[test]
+fn test_non_zero_field() {
+    let mut errors = Vec::new();
+    let mut provider = rs_config::ConfigProvider::new_from_str("{ layout: Leaf(\"a\"), gap: 0 }");
+    assert!(Window::parse_from(&mut provider, &mut |x| errors.push(x)).is_err());
+
+    let diag = errors.iter().find(|x| x.is_error()).unwrap();
+    assert!(diag.message == "NonZeroU32 can't be zero");
+    assert!(diag.span.as_ref().map(|x| (x.start_column, x.end_column)) == Some((27, 28)));
+}
@@ -0,0 +1,130 @@
+#[macro_use]
+extern crate rs_config_derive;
+
+extern crate rs_config;
+
+mod common;
+
+use rs_config::{ConfigAble, LayeredConfig, OverridePath, OverridePolicy};
+
+use common::write_config;
+
+#[derive(ConfigAble, PartialEq, Eq, Debug)]
+struct Pipe {
+    #[ConfigAttrs(rename = "in")]
+    input: u8,
+    inputs: Vec<u8>,
+    #[ConfigAttrs(rename = "max-connections", alias = "max_conns", alias = "connections")]
+    max_connections: u32,
+}
+
+#[derive(ConfigAble, PartialEq, Eq, Debug)]
+#[ConfigAttrs(rename_all = "kebab-case")]
+struct Server {
+    listen_port: u16,
+    #[ConfigAttrs(rename = "host")]
+    host_name: String,
+    log_target: Target,
+}
+
+#[derive(ConfigAble, PartialEq, Eq, Debug)]
+#[ConfigAttrs(rename_all = "snake_case")]
+enum Target {
+    StdErr,
+    #[ConfigAttrs(rename_all = "camelCase")]
+    LogFile { file_path: String, max_size: u32 },
+}
+
+fn parse<T: ConfigAble>(s: &str) -> Result<T, Vec<String>> {
+    let mut provider = rs_config::ConfigProvider::new_from_str(s);
+    let mut errors = Vec::new();
+    return T::parse_from(&mut provider, &mut |x| errors.push(x.message)).map_err(|_| errors);
+}
+
+#[test]
+fn test_exact_keys() {
+    let val: Pipe = parse("{ inputs: [1, 2], in: 3, max-connections: 4 }").unwrap();
+    assert!(val == Pipe { input: 3, inputs: vec![1, 2], max_connections: 4 });
+
+    /* A key that starts with the name of another field isn't that field */
+    assert!(parse::<Pipe>("{ in: 3, inputs: [], input: 1, max-connections: 4 }").is_err());
+    assert!(parse::<Pipe>("{ in: 3, inputs: [], max_connections: 4 }").is_err());
+    assert!(parse::<Pipe>("{ in: 3, inputs: [], max_conns: 4 }").is_ok());
+}
+
+#[test]
+fn test_aliases() {
+    let val: Pipe = parse("{ in: 1, inputs: [], connections: 8 }").unwrap();
+    assert!(val.max_connections == 8);
+
+    let format = Pipe::get_format_str();
+    assert!(format.contains("max-connections: "));
+    assert!(!format.contains("max_connections"));
+    assert!(!format.contains("input:"));
+}
+
+#[test]
+fn test_rename_all() {
+    let val: Server = parse("{ listen-port: 80, host: \"a\", log-target: log_file { filePath: \"x\", maxSize: 2 } }").unwrap();
+    assert!(val.log_target == Target::LogFile { file_path: "x".to_string(), max_size: 2 });
+    assert!(parse::<Server>("{ listen-port: 80, host: \"a\", log-target: std_err }").unwrap().log_target == Target::StdErr);
+    assert!(parse::<Server>("{ listen_port: 80, host: \"a\", log-target: std_err }").is_err());
+
+    let text = val.to_config_string();
+    assert!(text.contains("listen-port: 80"));
+    assert!(text.contains("log_file{filePath: \"x\""));
+    assert!(parse::<Server>(&text).unwrap() == val);
+
+    let format = Server::get_format_str();
+    assert!(format.contains("log-target: "));
+    assert!(format.contains("std_err"));
+}
+
+#[test]
+fn test_renamed_overrides() {
+    let mut val: Server = parse("{ listen-port: 80, host: \"a\", log-target: log_file { filePath: \"x\", maxSize: 2 } }").unwrap();
+
+    let mut path = OverridePath::dotted("log-target.maxSize");
+    assert!(val.apply_override(&mut path, "5", OverridePolicy::Replace, &mut |_| {}).is_ok());
+    let mut path = OverridePath::env("APP_LISTEN_PORT", "LISTEN_PORT");
+    assert!(val.apply_override(&mut path, "81", OverridePolicy::Replace, &mut |_| {}).is_ok());
+    assert!(val.listen_port == 81);
+    assert!(val.log_target == Target::LogFile { file_path: "x".to_string(), max_size: 5 });
+
+    let mut errors = Vec::new();
+    let mut path = OverridePath::dotted("listen_port");
+    assert!(val.apply_override(&mut path, "1", OverridePolicy::Replace, &mut |x| errors.push(x.to_string())).is_err());
+    assert!(errors[0].contains("one of: listen-port, host, log-target"));
+
+    let mut pipe: Pipe = parse("{ in: 1, inputs: [], connections: 8 }").unwrap();
+    let mut path = OverridePath::dotted("max_conns");
+    assert!(pipe.apply_override(&mut path, "9", OverridePolicy::Replace, &mut |_| {}).is_ok());
+    assert!(pipe.max_connections == 9);
+}
+
+#[test]
+fn test_renamed_diff() {
+    let old: Server = parse("{ listen-port: 80, host: \"a\", log-target: log_file { filePath: \"x\", maxSize: 2 } }").unwrap();
+    let new: Server = parse("{ listen-port: 81, host: \"a\", log-target: log_file { filePath: \"x\", maxSize: 3 } }").unwrap();
+
+    let changes: Vec<String> = old.diff(&new).iter().map(|x| x.to_string()).collect();
+    assert!(changes == vec!["listen-port: 80 -> 81", "log-target.maxSize: 2 -> 3"]);
+}
+
+#[test]
+fn test_renamed_layers() {
+    let system = write_config("rename-layers-system", "in: 1,\ninputs: [2],\nmax_conns: 4\n");
+    let user = write_config("rename-layers-user", "connections: 8\n");
+
+    let val = LayeredConfig::new().layer(&system).layer(&user).read::<Pipe>().unwrap();
+    assert!(val.value == Pipe { input: 1, inputs: vec![2], max_connections: 8 });
+
+    /* Sources are recorded by the name of the field, not the alias it was given with */
+    assert!(val.sources("max-connections") == vec![user.as_path()]);
+    assert!(val.sources("connections").is_empty());
+    assert!(val.source("in") == Some(system.as_path()));
+    assert!(val.source("inputs[0]") == Some(system.as_path()));
+
+    std::fs::remove_file(system).unwrap();
+    std::fs::remove_file(user).unwrap();
+}
@@ -0,0 +1,177 @@
+#[macro_use]
+extern crate rs_config_derive;
+
+extern crate rs_config;
+
+use rs_config::ConfigAble;
+use rs_config::SchemaNode;
+use rs_config::schema::VariantFields;
+
+use std::collections::HashSet;
+
+#[derive(ConfigAble, PartialEq, Eq, Debug)]
+/// Where to put things
+enum Position {
+    /// Relative to all monitors
+    Global(char),
+    Monitor {
+        name: String,
+        offset: Option<u32>,
+    },
+    Hidden,
+}
+
+#[derive(ConfigAble, PartialEq, Eq, Debug)]
+struct Pair(u8, String);
+
+#[derive(ConfigAble, PartialEq, Eq, Debug)]
+struct Tree {
+    children: Vec<Tree>,
+}
+
+/// A hand written implementation, that doesn't describe its schema
+struct Opaque;
+
+impl ConfigAble for Opaque {
+    fn get_format<F>(set: &mut HashSet<String>, fun: &mut F)
+        where F: FnMut(&str) {
+        if set.insert("Opaque".to_string()) {
+            fun("Opaque: anything");
+        }
+    }
+
+    fn get_name() -> &'static str { "Opaque" }
+
+    fn parse_from<F>(provider: &mut rs_config::ConfigProvider, fun: &mut F) -> Result<Self, rs_config::ParseError>
+        where F: FnMut(rs_config::Diagnostic) {
+        provider.consume(1, fun)?;
+        return Ok(Opaque);
+    }
+
+    fn get_default() -> Result<Self, ()> { Err(()) }
+
+    fn write_to<F>(&self, fun: &mut F)
+        where F: FnMut(&str) {
+        fun("x");
+    }
+}
+
+#[derive(ConfigAble)]
+struct WithOpaque {
+    opaque: Opaque,
+}
+
+#[derive(ConfigAble, PartialEq, Eq, Debug)]
+struct SchemaConfig {
+    /// Where the bar is placed
+    #[ConfigAttrs(default = "Position::Global('l')")]
+    position: Position,
+    title: String,
+    pair: Option<Pair>,
+    corners: [char; 4],
+    tree: Tree,
+}
+
+#[test]
+fn test_schema_tree() {
+    let schema = SchemaConfig::schema();
+
+    assert!(schema.name == "SchemaConfig");
+    assert!(schema.root == SchemaNode::Ref("SchemaConfig".to_string()));
+
+    let fields = match schema.definitions["SchemaConfig"] {
+        SchemaNode::Struct { ref fields, .. } => fields,
+        _ => panic!("Expected struct"),
+    };
+    assert!(fields.iter().map(|x| x.name.as_str()).collect::<Vec<_>>() == ["position", "title", "pair", "corners", "tree"]);
+
+    assert!(!fields[0].required);
+    assert!(fields[0].default.as_deref() == Some("Global('l')"));
+    assert!(fields[0].description.as_deref() == Some("Where the bar is placed"));
+    assert!(fields[1].required);
+    assert!(!fields[2].required);
+    assert!(fields[3].schema == SchemaNode::Array {
+        items: Box::new(SchemaNode::String { format: None, min_length: Some(1), max_length: Some(1) }),
+        min_items: Some(4),
+        max_items: Some(4),
+    });
+
+    match schema.definitions["Position"] {
+        SchemaNode::Enum { ref variants, .. } => {
+            assert!(variants.len() == 3);
+            assert!(variants[0].description.as_deref() == Some("Relative to all monitors"));
+            assert!(variants[2].fields == VariantFields::Unit);
+        },
+        _ => panic!("Expected enum"),
+    }
+
+    assert!(schema.definitions["Pair"] == SchemaNode::Tuple(vec![
+        SchemaNode::Integer { minimum: 0, maximum: 255 },
+        SchemaNode::String { format: None, min_length: None, max_length: None },
+    ]));
+
+    /* Recursive types refer to themselves */
+    match schema.definitions["Tree"] {
+        SchemaNode::Struct { ref fields, .. } => {
+            assert!(fields[0].schema == SchemaNode::Array {
+                items: Box::new(SchemaNode::Ref("Tree".to_string())),
+                min_items: None,
+                max_items: None,
+            });
+        },
+        _ => panic!("Expected struct"),
+    }
+}
+
+#[test]
+fn test_json_schema() {
+    let json = SchemaConfig::schema().to_json_schema();
+
+    assert!(json.starts_with("{\n  \"$schema\": \"https://json-schema.org/draft/2020-12/schema\",\n  \"title\": \"SchemaConfig\",\n  \"$ref\": \"#/$defs/SchemaConfig\",\n"));
+    assert!(json.contains("
+      \"properties\": {
+        \"position\": {
+          \"$ref\": \"#/$defs/Position\",
+          \"description\": \"Where the bar is placed\",
+          \"default\": {
+            \"Global\": \"l\"
+          }
+        },"));
+    assert!(json.contains("
+      \"required\": [
+        \"title\",
+        \"corners\"
+      ],
+      \"additionalProperties\": false"));
+    assert!(json.contains("
+        \"corners\": {
+          \"type\": \"array\",
+          \"items\": {
+            \"type\": \"string\",
+            \"minLength\": 1,
+            \"maxLength\": 1
+          },
+          \"minItems\": 4,
+          \"maxItems\": 4
+        },"));
+    assert!(json.contains("
+        {
+          \"const\": \"Hidden\"
+        }"));
+    assert!(json.contains("
+      \"type\": \"array\",
+      \"prefixItems\": [
+        {
+          \"type\": \"integer\",
+          \"minimum\": 0,
+          \"maximum\": 255
+        },"));
+}
+
+#[test]
+fn test_schema_default() {
+    match WithOpaque::schema().definitions["WithOpaque"] {
+        SchemaNode::Struct { ref fields, .. } => assert!(fields[0].schema == SchemaNode::Any),
+        _ => panic!("Expected struct"),
+    }
+}
@@ -20,18 +20,19 @@ fn test_simple_enum_format() {
 #[test]
 fn test_simple_enum_parse() {
     let mut builder = String::new();
-    let mut fun = |x: String| builder.push_str(x.as_str());
+    let mut fun = |x: rs_config::Diagnostic| builder.push_str(&x.to_string());
     let mut provider = rs_config::ConfigProvider::new_from_str("SimpleCon1");
     assert!(SimpleEnum::parse_from(&mut provider, &mut fun) == Ok(SimpleEnum::SimpleCon1));
-    assert!(provider.get_next() == None);
+    assert!(provider.get_next().is_none());
 
-    let mut provider2 = rs_config::ConfigProvider::new_from_str("SimpleCon2asdf");
+    let mut provider2 = rs_config::ConfigProvider::new_from_str("SimpleCon2 asdf");
     assert!(SimpleEnum::parse_from(&mut provider2, &mut fun) == Ok(SimpleEnum::SimpleCon2));
     assert!(provider2.get_next() == Some("asdf".to_string()));
 
+    /* The name has to be the entire identifier, not just its start */
+    let mut provider4 = rs_config::ConfigProvider::new_from_str("SimpleCon2asdf");
+    assert!(SimpleEnum::parse_from(&mut provider4, &mut fun).is_err());
+
     let mut provider3 = rs_config::ConfigProvider::new_from_str("SimpleCon3");
-    match SimpleEnum::parse_from(&mut provider3, &mut fun) {
-        Ok(_) => assert!(false),
-        Err(_) => assert!(true),
-    }
+    assert!(SimpleEnum::parse_from(&mut provider3, &mut fun).is_err());
 }
@@ -19,9 +19,9 @@ fn test_struct_enum_format() {
 #[test]
 fn test_struct_enum_parse() {
     let mut builder = String::new();
-    let mut fun = |x: String| builder.push_str(x.as_str());
+    let mut fun = |x: rs_config::Diagnostic| builder.push_str(&x.to_string());
     let mut provider = rs_config::ConfigProvider::new_from_str("Var2 { s: 'C', c: \"TestStr\" }");
     assert!(StructEnum::parse_from(&mut provider, &mut fun) == Ok(StructEnum::Var2{c: "TestStr".to_string(), s:'C'}));
-    assert!(provider.get_next() == None);
+    assert!(provider.get_next().is_none());
 }
 
@@ -20,16 +20,28 @@ fn test_struct_struct_format() {
 #[test]
 fn test_struct_struct_parse() {
     let mut builder = String::new();
-    let mut fun = |x: String| builder.push_str(x.as_str());
+    let mut fun = |x: rs_config::Diagnostic| builder.push_str(&x.to_string());
     let mut provider = rs_config::ConfigProvider::new_from_str("{ c: 'C', s: \"TestStr\" }");
     assert!(StructStruct::parse_from(&mut provider, &mut fun) == Ok(StructStruct{s: "TestStr".to_string(), c:'C'}));
-    assert!(provider.get_next() == None);
+    assert!(provider.get_next().is_none());
 }
 
 #[test]
 fn test_struct_struct_parse_fail() {
     let mut builder = String::new();
-    let mut fun = |x: String| builder.push_str(x.as_str());
+    let mut fun = |x: rs_config::Diagnostic| builder.push_str(&x.to_string());
     let mut provider = rs_config::ConfigProvider::new_from_str("{ k: 'C', s: \"TestStr\" }");
     assert!(StructStruct::parse_from(&mut provider, &mut fun) == Err(rs_config::ParseError::Final));
 }
+
+#[test]
+fn test_struct_struct_parse_fail_diagnostic() {
+    let mut errors = Vec::new();
+    let mut provider = rs_config::ConfigProvider::new_from_str("{ k: 'C', s: \"TestStr\" }");
+    assert!(StructStruct::parse_from(&mut provider, &mut |x| errors.push(x)) == Err(rs_config::ParseError::Final));
+
+    assert!(errors.len() == 1);
+    assert!(errors[0].found == Some("k".to_string()));
+    assert!(errors[0].expected == Some("one of: s, c".to_string()));
+    assert!(errors[0].span.as_ref().map(|x| (x.line, x.start_column, x.end_column)) == Some((1, 3, 4)));
+}
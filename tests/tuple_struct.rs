@@ -16,8 +16,8 @@ fn test_tuple_struct_format() {
 #[test]
 fn test_tuple_struct_parse() {
     let mut builder = String::new();
-    let mut fun = |x: String| builder.push_str(x.as_str());
+    let mut fun = |x: rs_config::Diagnostic| builder.push_str(&x.to_string());
     let mut provider = rs_config::ConfigProvider::new_from_str("( \"TestStr1\", 'C' )");
     assert!(TupleStruct::parse_from(&mut provider, &mut fun) == Ok(TupleStruct("TestStr1".to_string(), 'C')));
-    assert!(provider.get_next() == None);
+    assert!(provider.get_next().is_none());
 }
@@ -0,0 +1,46 @@
+#[macro_use]
+extern crate rs_config_derive;
+
+extern crate rs_config;
+
+use rs_config::ConfigAble;
+
+#[derive(ConfigAble, PartialEq, Eq, Debug)]
+struct Tracing;
+
+#[derive(ConfigAble, PartialEq, Eq, Debug)]
+struct Features {
+    tracing: Option<Tracing>,
+    metrics: Option<Tracing>,
+}
+
+#[test]
+fn test_unit_struct_format() {
+    assert!(Tracing::get_format_str() == "Tracing: Tracing | {}");
+}
+
+#[test]
+fn test_unit_struct_parse() {
+    let mut provider = rs_config::ConfigProvider::new_from_str("Tracing");
+    assert!(Tracing::parse_from(&mut provider, &mut |_| {}) == Ok(Tracing));
+    assert!(provider.get_next().is_none());
+
+    let mut provider = rs_config::ConfigProvider::new_from_str("{ tracing: Some({}), metrics: None }");
+    assert!(Features::parse_from(&mut provider, &mut |_| {}) == Ok(Features { tracing: Some(Tracing), metrics: None }));
+
+    let mut errors = Vec::new();
+    let mut provider = rs_config::ConfigProvider::new_from_str("Metrics");
+    assert!(Tracing::parse_from(&mut provider, &mut |x| errors.push(x)) == Err(rs_config::ParseError::Final));
+    assert!(errors[0].expected == Some("Tracing".to_string()));
+}
+
+#[test]
+fn test_unit_struct_default_merge() {
+    assert!(Tracing::get_default() == Ok(Tracing));
+    assert!(Tracing.merge(Tracing).is_ok());
+    assert!(Tracing.to_config_string() == "Tracing");
+
+    let mut val = Features { tracing: Some(Tracing), metrics: None };
+    assert!(val.merge(Features { tracing: Some(Tracing), metrics: Some(Tracing) }).is_ok());
+    assert!(val == Features { tracing: Some(Tracing), metrics: Some(Tracing) });
+}
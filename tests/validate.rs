@@ -0,0 +1,223 @@
+#[macro_use]
+extern crate rs_config_derive;
+
+extern crate rs_config;
+
+use rs_config::{ConfigAble, ConfigError, ConfigProvider, Diagnostic, OverridePolicy, ParseError};
+use rs_config::validate::ValidationContext;
+
+fn even(x: &u32) -> Result<(), String> {
+    if x.is_multiple_of(2) { Ok(()) } else { Err(format!("{} isn't even", x)) }
+}
+
+#[derive(ConfigAble, Clone, PartialEq, Eq, Debug)]
+struct Server {
+    #[ConfigAttrs(range = "1..=65535")]
+    port: u32,
+    /// The host name
+    #[ConfigAttrs(non_empty, max_len = 8)]
+    host: String,
+    #[ConfigAttrs(default = "\"info\".to_string()", one_of("debug", "info", "warn"))]
+    level: String,
+    #[ConfigAttrs(default = "vec![]", unique, min_len = 0)]
+    tags: Vec<char>,
+    #[ConfigAttrs(default = "2", validate = "even")]
+    workers: u32,
+}
+
+#[derive(ConfigAble, Clone, PartialEq, Eq, Debug)]
+struct Limits(#[ConfigAttrs(range = "..100")] u8, #[ConfigAttrs(min_len = 2)] Vec<u8>);
+
+fn check_bounds(val: &Listen, context: &ValidationContext) -> Result<(), Box<Diagnostic>> {
+    if val.min > val.max {
+        return Err(Box::new(context.error("min", format!("min ({}) has to be at most max ({})", val.min, val.max))));
+    }
+
+    return Ok(());
+}
+
+#[derive(ConfigAble, Clone, PartialEq, Eq, Debug)]
+#[ConfigAttrs(validate = "check_bounds", mutually_exclusive("socket", "port"), required_together("tls_cert", "tls_key"))]
+struct Listen {
+    #[ConfigAttrs(default = "None")]
+    socket: Option<String>,
+    #[ConfigAttrs(default = "None")]
+    port: Option<u16>,
+    #[ConfigAttrs(default = "None")]
+    tls_cert: Option<String>,
+    #[ConfigAttrs(default = "None")]
+    tls_key: Option<String>,
+    #[ConfigAttrs(default = "0")]
+    min: u8,
+    #[ConfigAttrs(default = "255")]
+    max: u8,
+}
+
+fn check_mode(val: &Mode, context: &ValidationContext) -> Result<(), Box<Diagnostic>> {
+    match *val {
+        Mode::Fixed(0) => Err(Box::new(context.error("", "Fixed can't be 0"))),
+        _ => Ok(()),
+    }
+}
+
+#[derive(ConfigAble, Clone, PartialEq, Eq, Debug)]
+#[ConfigAttrs(validate = "check_mode")]
+enum Mode {
+    Auto,
+    Fixed(u8),
+    #[ConfigAttrs(mutually_exclusive("a", "b"))]
+    Pick {
+        #[ConfigAttrs(default = "0")]
+        a: u8,
+        #[ConfigAttrs(default = "0")]
+        b: u8,
+    },
+}
+
+fn parse<T: ConfigAble>(text: &str) -> (Result<T, ParseError>, Vec<Diagnostic>) {
+    let mut provider = ConfigProvider::new_from_str(text);
+    let mut errors = Vec::new();
+    let ret = T::parse_from(&mut provider, &mut |x| if x.is_error() { errors.push(x) });
+
+    return (ret, errors);
+}
+
+#[test]
+fn test_validate_ok() {
+    let (val, errors) = parse::<Server>("{port: 80, host: \"local\", tags: ['a', 'b']}");
+    assert!(errors.is_empty());
+    assert!(val == Ok(Server { port: 80, host: "local".to_string(), level: "info".to_string(), tags: vec!['a', 'b'], workers: 2 }));
+
+    assert!(parse::<Limits>("(99, [1, 2])").0 == Ok(Limits(99, vec![1, 2])));
+}
+
+#[test]
+fn test_validate_errors() {
+    let (val, errors) = parse::<Server>("{port: 0, host: \"\", level: \"trace\", tags: ['a', 'a'], workers: 3}");
+    assert!(val.is_err());
+
+    let messages: Vec<&str> = errors.iter().map(|x| x.message.as_str()).collect();
+    assert!(messages == vec![
+        "port is 0, but has to be in 1..=65535",
+        "host can't be empty",
+        "level is \"trace\", but has to be one of the allowed values",
+        "'a' is in tags more than once",
+        "Invalid workers: 3 isn't even",
+    ]);
+    assert!(errors[0].span.as_ref().map(|x| x.start_column) == Some(8));
+    assert!(errors[2].to_string().contains("expected one of: \"debug\", \"info\", \"warn\", found \"trace\""));
+
+    let (_, errors) = parse::<Server>("{port: 80, host: \"localhost\"}");
+    assert!(errors[0].message == "host has a length of 9, but has to be at most 8 long");
+
+    let (val, errors) = parse::<Limits>("(100, [1])");
+    assert!(val == Err(ParseError::Recoverable));
+    assert!(errors[0].message == "0 is 100, but has to be in ..100");
+}
+
+#[test]
+fn test_validate_format() {
+    let format = Server::get_format_str();
+    assert!(format.contains("
+    port: Rules: in 1..=65535
+    host: The host name
+          Rules: not empty, at most 8 long
+    level: Rules: one of \"debug\", \"info\", \"warn\"
+    tags: Rules: at least 0 long, without duplicates
+    workers: Rules: checked by even"));
+
+    assert!(Limits::get_format_str().contains("\n    0: Rules: in ..100\n    1: Rules: at least 2 long"));
+}
+
+#[test]
+fn test_validate_overrides() {
+    let (val, _) = parse::<Server>("{port: 80, host: \"local\", tags: ['a']}");
+    let mut val = val.unwrap();
+
+    assert!(rs_config::apply_set_overrides(&mut val, ["port=8080"], OverridePolicy::Replace).is_ok());
+    assert!(rs_config::apply_set_overrides(&mut val, ["tags=['b']"], OverridePolicy::Merge).is_ok());
+    assert!(val.port == 8080 && val.tags == vec!['a', 'b']);
+
+    /* Overrides are checked like the config, and rejected ones don't change the value */
+    match rs_config::apply_set_overrides(&mut val, ["port=0", "workers=4"], OverridePolicy::Replace) {
+        Err(ConfigError::Parse { diagnostics, .. }) => {
+            assert!(diagnostics.len() == 1);
+            assert!(diagnostics[0].message == "port is 0, but has to be in 1..=65535");
+            assert!(diagnostics[0].span.as_ref().map(|x| x.file.as_str()) == Some("port"));
+        },
+        _ => panic!("Expected override error"),
+    }
+    match rs_config::apply_set_overrides(&mut val, ["tags=['a']"], OverridePolicy::Merge) {
+        Err(ConfigError::Parse { diagnostics, .. }) => assert!(diagnostics[0].message == "'a' is in tags more than once"),
+        _ => panic!("Expected override error"),
+    }
+    assert!(val == Server { port: 8080, host: "local".to_string(), level: "info".to_string(), tags: vec!['a', 'b'], workers: 2 });
+
+    let mut limits = Limits(1, vec![1, 2]);
+    assert!(rs_config::apply_set_overrides(&mut limits, ["0=100"], OverridePolicy::Replace).is_err());
+    assert!(rs_config::apply_set_overrides(&mut limits, ["1=[3]"], OverridePolicy::Replace).is_err());
+    assert!(limits == Limits(1, vec![1, 2]));
+}
+
+#[test]
+fn test_validate_value() {
+    let (val, errors) = parse::<Listen>("{port: Some(80), tls_cert: Some(\"cert\"), tls_key: Some(\"key\"), min: 1, max: 2}");
+    assert!(errors.is_empty());
+    assert!(val.map(|x| x.port) == Ok(Some(80)));
+
+    let (val, errors) = parse::<Listen>("{socket: Some(\"/run/s\"),\n port: Some(80), tls_key: Some(\"key\"), min: 3, max: 2}");
+    assert!(val == Err(ParseError::Recoverable));
+
+    let messages: Vec<&str> = errors.iter().map(|x| x.message.as_str()).collect();
+    assert!(messages == vec![
+        "socket and port can't be used together",
+        "tls_key requires tls_cert as well",
+        "min (3) has to be at most max (2)",
+    ]);
+    let spans: Vec<(usize, usize)> = errors.iter().map(|x| x.span.as_ref().map(|x| (x.start_column, x.end_column)).unwrap()).collect();
+    assert!(spans == vec![(27, 31), (43, 50), (65, 68)]);
+    assert!(errors[0].to_string().contains("expected only one of: socket, port"));
+}
+
+#[test]
+fn test_validate_enum() {
+    assert!(parse::<Mode>("Auto").0 == Ok(Mode::Auto));
+    assert!(parse::<Mode>("Pick {a: 1}").0 == Ok(Mode::Pick { a: 1, b: 0 }));
+
+    let (val, errors) = parse::<Mode>("Fixed(0)");
+    assert!(val == Err(ParseError::Recoverable));
+    assert!(errors[0].message == "Fixed can't be 0");
+    assert!(errors[0].span.as_ref().map(|x| x.start_column) == Some(1));
+
+    let (val, errors) = parse::<Mode>("Pick {a: 1, b: 2}");
+    assert!(val == Err(ParseError::Recoverable));
+    assert!(errors[0].message == "a and b can't be used together");
+}
+
+#[test]
+fn test_validate_value_overrides() {
+    let (val, _) = parse::<Listen>("{socket: Some(\"/run/s\"), tls_cert: Some(\"cert\"), tls_key: Some(\"key\"), max: 2}");
+    let mut val = val.unwrap();
+
+    assert!(rs_config::apply_set_overrides(&mut val, ["tls_key=Some(\"other\")", "min=2"], OverridePolicy::Replace).is_ok());
+    assert!(val.tls_key == Some("other".to_string()) && val.min == 2);
+
+    /* The checks on the entire value see the fields from the config and the override */
+    match rs_config::apply_set_overrides(&mut val, ["port=Some(80)", "min=3"], OverridePolicy::Replace) {
+        Err(ConfigError::Parse { diagnostics, .. }) => {
+            let messages: Vec<&str> = diagnostics.iter().map(|x| x.message.as_str()).collect();
+            assert!(messages == vec!["socket and port can't be used together", "min (3) has to be at most max (2)"]);
+        },
+        _ => panic!("Expected override errors"),
+    }
+    assert!(val.port.is_none() && val.min == 2);
+
+    let mut mode = Mode::Pick { a: 1, b: 0 };
+    assert!(rs_config::apply_set_overrides(&mut mode, ["b=2"], OverridePolicy::Replace).is_err());
+    assert!(mode == Mode::Pick { a: 1, b: 0 });
+
+    let mut mode = Mode::Fixed(1);
+    assert!(rs_config::apply_set_overrides(&mut mode, ["0=0"], OverridePolicy::Replace).is_err());
+    assert!(rs_config::apply_set_overrides(&mut mode, ["0=2"], OverridePolicy::Replace).is_ok());
+    assert!(mode == Mode::Fixed(2));
+}
@@ -0,0 +1,86 @@
+#[macro_use]
+extern crate rs_config_derive;
+
+extern crate rs_config;
+
+mod common;
+
+use rs_config::ConfigAble;
+use rs_config::{ConfigError, ConfigWatcher};
+
+use common::write_config;
+
+use std::time::Duration;
+
+#[derive(ConfigAble, PartialEq, Eq, Debug)]
+struct WatchConfig {
+    name: String,
+    #[ConfigAttrs(default = "'x'")]
+    c: char,
+}
+
+#[test]
+fn test_watch_include() {
+    let include = write_config("watch-include", "c: 'y'\n");
+    let path = write_config("watch", &format!("name: \"a\",\n!include {}\n", include.display()));
+
+    let mut watcher = ConfigWatcher::<WatchConfig>::new(&path).unwrap();
+    assert!(*watcher.value() == WatchConfig { name: "a".to_string(), c: 'y' });
+    assert!(watcher.files() == vec![path.as_path(), include.as_path()]);
+    assert!(watcher.poll().is_none());
+
+    /* The last good config is kept while the file is broken */
+    write_config("watch-include", "c: 'zz'\n");
+    match watcher.poll() {
+        Some(Err(ConfigError::Parse { diagnostics, .. })) => {
+            assert!(diagnostics.iter().any(|x| x.is_error() && x.to_string().contains("watch-include")));
+        },
+        _ => panic!("Expected parse error"),
+    }
+    assert!(watcher.value().c == 'y');
+    assert!(watcher.poll().is_none());
+
+    write_config("watch-include", "c:  'z' \n");
+    let mut calls = 0;
+    watcher.watch(Duration::from_millis(1), |x| {
+        calls += 1;
+        assert!(x.unwrap().c == 'z');
+        false
+    });
+    assert!(calls == 1);
+    assert!(watcher.into_value() == WatchConfig { name: "a".to_string(), c: 'z' });
+
+    std::fs::remove_file(path).unwrap();
+    std::fs::remove_file(include).unwrap();
+}
+
+#[test]
+fn test_watch_missing_include() {
+    let include = std::env::temp_dir().join(format!("rs-config-test-{}-watch-later", std::process::id()));
+    let path = write_config("watch-missing", "name: \"a\"\n");
+
+    let mut watcher = ConfigWatcher::<WatchConfig>::new(&path).unwrap();
+    assert!(watcher.files() == vec![path.as_path()]);
+
+    write_config("watch-missing", &format!("name: \"b\",\n!include {}\n", include.display()));
+    match watcher.poll() {
+        Some(Err(ConfigError::Parse { diagnostics, .. })) => {
+            assert!(diagnostics[0].message.starts_with("Couldn't include"));
+        },
+        _ => panic!("Expected parse error"),
+    }
+    assert!(watcher.value().name == "a");
+
+    /* The include is watched, even though it doesn't exist yet */
+    assert!(watcher.files().contains(&include.as_path()));
+    write_config("watch-later", "c: 'y'\n");
+    match watcher.poll() {
+        Some(Ok(x)) => assert!(*x == WatchConfig { name: "b".to_string(), c: 'y' }),
+        _ => panic!("Expected a new config"),
+    }
+
+    std::fs::remove_file(path).unwrap();
+    std::fs::remove_file(include).unwrap();
+
+    assert!(ConfigWatcher::<WatchConfig>::new(std::env::temp_dir().join("rs-config-test-does-not-exist")).is_err());
+}
@@ -0,0 +1,107 @@
+#[macro_use]
+extern crate rs_config_derive;
+
+extern crate rs_config;
+
+use rs_config::ConfigAble;
+
+#[derive(ConfigAble, PartialEq, Eq, Debug)]
+enum WriteEnum {
+    Unit,
+    Tuple(String, char),
+    Struct { num: Option<char>, list: Vec<String> },
+}
+
+#[derive(ConfigAble, PartialEq, Eq, Debug)]
+struct WriteTuple(WriteEnum, char);
+
+#[derive(ConfigAble, PartialEq, Eq, Debug)]
+struct WriteStruct {
+    name: String,
+    items: Vec<WriteTuple>,
+}
+
+#[derive(ConfigAble, PartialEq, Debug)]
+struct WriteNumbers {
+    a: u8,
+    b: i64,
+    ratio: f32,
+    enabled: bool,
+}
+
+/// A hand written implementation from before write_to
+struct Legacy;
+
+impl ConfigAble for Legacy {
+    fn get_format<F>(_: &mut std::collections::HashSet<String>, fun: &mut F)
+        where F: FnMut(&str) {
+        fun("Legacy: anything");
+    }
+
+    fn get_name() -> &'static str { "Legacy" }
+
+    fn parse_from<F>(provider: &mut rs_config::ConfigProvider, fun: &mut F) -> Result<Self, rs_config::ParseError>
+        where F: FnMut(rs_config::Diagnostic) {
+        provider.consume(1, fun)?;
+        return Ok(Legacy);
+    }
+
+    fn get_default() -> Result<Self, ()> { Err(()) }
+}
+
+fn round_trip<T: ConfigAble + PartialEq + std::fmt::Debug>(val: T) {
+    let mut provider = rs_config::ConfigProvider::new_from_str(val.to_config_string());
+    assert_eq!(T::parse_from(&mut provider, &mut |x| panic!("{}", x)), Ok(val));
+    assert!(provider.get_next().is_none());
+}
+
+#[test]
+fn test_write_enum() {
+    assert!(WriteEnum::Unit.to_config_string() == "Unit");
+    assert!(WriteEnum::Tuple("a\"b".into(), 'c').to_config_string() == "Tuple(\"a\\\"b\", 'c')");
+    assert!(WriteEnum::Struct { num: Some('3'), list: vec!["1".into(), "2".into()] }.to_config_string() == "Struct{num: Some('3'), list: [\"1\", \"2\"]}");
+
+    round_trip(WriteEnum::Unit);
+    round_trip(WriteEnum::Tuple("a\"b".into(), 'c'));
+    round_trip(WriteEnum::Struct { num: None, list: vec![] });
+}
+
+#[test]
+fn test_write_numbers() {
+    let val = WriteNumbers { a: 1, b: -2, ratio: 0.5, enabled: true };
+
+    assert!(val.to_config_string() == "{a: 1, b: -2, ratio: 0.5, enabled: true}");
+    round_trip(val);
+
+    let mut provider = rs_config::ConfigProvider::new_from_str("{a: 0xff, b: -1_000, ratio: 1e3, enabled: false}");
+    assert!(WriteNumbers::parse_from(&mut provider, &mut |x| panic!("{}", x)) == Ok(WriteNumbers { a: 255, b: -1000, ratio: 1000.0, enabled: false }));
+}
+
+#[test]
+fn test_write_struct() {
+    let val = WriteStruct {
+        name: "Test\n".into(),
+        items: vec![WriteTuple(WriteEnum::Unit, '1'), WriteTuple(WriteEnum::Tuple("x".into(), 'y'), '2')],
+    };
+
+    assert!(val.to_config_string() == "{name: \"Test\\n\", items: [(Unit, '1'), (Tuple(\"x\", 'y'), '2')]}");
+    round_trip(val);
+}
+
+#[test]
+fn test_write_config_file() {
+    let val = WriteStruct { name: "File".into(), items: vec![] };
+    let path = std::env::temp_dir().join(format!("rs-config-test-{}-write", std::process::id()));
+
+    rs_config::write_config(&val, &path).unwrap();
+    assert!(std::fs::read_to_string(&path).unwrap() == "name: \"File\", items: []\n");
+    assert!(rs_config::read_config::<WriteStruct, _>(&path).unwrap() == val);
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_write_default() {
+    assert!(Legacy.to_config_string() == "<Legacy>");
+    assert!(vec![Legacy].to_config_string() == "[<Legacy>]");
+}